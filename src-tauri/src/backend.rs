@@ -52,14 +52,52 @@ lazy_static::lazy_static! {
 
 #[path = "blacklist.rs"]
 mod blacklist;
+#[path = "catalog_sources.rs"]
+mod catalog_sources;
+#[path = "cli.rs"]
+mod cli;
 #[path = "crash_analysis.rs"]
 mod crash_analysis;
+#[path = "crash_bisect.rs"]
+mod crash_bisect;
+#[path = "crash_bundle.rs"]
+mod crash_bundle;
+#[path = "crash_history.rs"]
+mod crash_history;
+#[path = "crash_rules.rs"]
+mod crash_rules;
+#[path = "dependency_check.rs"]
+mod dependency_check;
 #[path = "everest.rs"]
 mod everest;
 #[path = "keybindings.rs"]
 mod keybindings;
+#[path = "launch_snapshot.rs"]
+mod launch_snapshot;
+#[path = "lockfile.rs"]
+mod lockfile;
+#[path = "log_tail.rs"]
+mod log_tail;
 #[path = "miaonet_atlas.rs"]
 mod miaonet_atlas;
+#[path = "mod_backups.rs"]
+mod mod_backups;
+#[path = "mod_index.rs"]
+mod mod_index;
+#[path = "mod_updates.rs"]
+mod mod_updates;
+#[path = "mod_watcher.rs"]
+mod mod_watcher;
+#[path = "profile_diff.rs"]
+mod profile_diff;
+#[path = "profile_saves.rs"]
+mod profile_saves;
+#[path = "profile_settings.rs"]
+mod profile_settings;
+#[path = "resolver.rs"]
+mod resolver;
+#[path = "stack_trace.rs"]
+mod stack_trace;
 #[path = "ureq.rs"]
 mod ureq;
 #[path = "wegfan.rs"]
@@ -160,7 +198,16 @@ struct MiaoNetSettingsUpdate {
     emotes: Vec<String>,
 }
 
+/// Where the game keeps settings and saves. A profile with isolated saves
+/// replaces every other location, as `EVEREST_SAVEPATH` does for the game.
 fn miaonet_settings_directories(game_path: &Path) -> Vec<PathBuf> {
+    match profile_saves::active_save_root(game_path) {
+        Some(root) => vec![root.join("Saves")],
+        None => shared_save_directories(game_path),
+    }
+}
+
+fn shared_save_directories(game_path: &Path) -> Vec<PathBuf> {
     let mut directories = Vec::new();
     if let Some(override_path) = std::env::var_os("EVEREST_SAVEPATH") {
         directories.push(PathBuf::from(override_path).join("Saves"));
@@ -1209,24 +1256,6 @@ fn set_window_vibrancy(window: tauri::WebviewWindow, enabled: bool) -> Result<()
     }
 }
 
-fn compare_version(a: &str, b: &str) -> i32 {
-    let a_parts: Vec<&str> = a.split('.').collect();
-    let b_parts: Vec<&str> = b.split('.').collect();
-    for i in 0..std::cmp::max(a_parts.len(), b_parts.len()) {
-        let a_part = a_parts.get(i).unwrap_or(&"0");
-        let b_part = b_parts.get(i).unwrap_or(&"0");
-        if a_part == b_part {
-            continue;
-        }
-        if a_part.parse::<i32>().unwrap() > b_part.parse::<i32>().unwrap() {
-            return 1;
-        } else {
-            return -1;
-        }
-    }
-    0
-}
-
 fn read_mod_yaml_bytes(path: &Path) -> anyhow::Result<Vec<u8>> {
     let zipfile = std::fs::File::open(path)?;
     let mut archive = zip::ZipArchive::new(zipfile)?;
@@ -1244,14 +1273,14 @@ fn read_mod_yaml_bytes(path: &Path) -> anyhow::Result<Vec<u8>> {
     Ok(buffer)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct EverestModDependency {
     name: Option<String>,
     version: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct EverestModMetadata {
     name: Option<String>,
@@ -1274,27 +1303,6 @@ fn parse_mod_yaml(path: &Path) -> anyhow::Result<Vec<EverestModMetadata>> {
     )?)
 }
 
-fn extract_mod_for_yaml(path: &Path) -> anyhow::Result<Vec<EverestModMetadata>> {
-    use std::io::Write;
-    use strip_bom::StripBom;
-
-    let buffer = read_mod_yaml_bytes(path)?;
-    let cache_dir = path
-        .parent()
-        .context("Mod archive has no parent folder")?
-        .parent()
-        .context("Mods folder has no parent folder")?
-        .join("celemod_yaml_cache");
-    std::fs::create_dir_all(&cache_dir)?;
-
-    let mut file =
-        std::fs::File::create(cache_dir.join(path.with_extension("yaml").file_name().unwrap()))?;
-    file.write_all(&buffer)?;
-    Ok(parse_mod_yaml_document(
-        String::from_utf8(buffer)?.strip_bom(),
-    )?)
-}
-
 fn is_valid_zip_archive(path: &Path) -> bool {
     std::fs::File::open(path)
         .ok()
@@ -1417,18 +1425,115 @@ fn check_all_mod_contents_sync(
     }
 }
 
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum ModHashAuditStatus {
+    /// The archive is byte-identical to a build the catalog publishes.
+    Published,
+    /// The catalog publishes this version, but with a different hash.
+    Modified,
+    /// The catalog only has hashes for another version, so this build cannot
+    /// be checked.
+    Outdated,
+    /// Not in the catalog, or the catalog has no hash for it.
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModHashAudit {
+    file: String,
+    name: String,
+    version: String,
+    catalog_version: Option<String>,
+    xx_hash: String,
+    expected_hashes: Vec<String>,
+    status: ModHashAuditStatus,
+}
+
+fn audit_installed_mod_hashes_sync(
+    mods_folder_path: &str,
+    mod_data: &HashMap<String, everest::ModInfoCached>,
+) -> Vec<ModHashAudit> {
+    let installed = get_installed_mods_sync_with_catalog(mods_folder_path.to_string(), None);
+    let mut files = installed
+        .iter()
+        .map(|item| item.file.clone())
+        .filter(|file| Path::new(mods_folder_path).join(file).is_file())
+        .collect::<Vec<_>>();
+    files.sort_unstable_by_key(|file| file.to_ascii_lowercase());
+    files.dedup();
+
+    let mut audits = Vec::new();
+    for file in files {
+        let path = Path::new(mods_folder_path).join(&file);
+        let xx_hash = match mod_index::archive_xx_hash(&path) {
+            Ok(hash) => hash,
+            Err(error) => {
+                crate::logging::warn(format_args!("Failed to hash {}: {error:#}", path.display()));
+                continue;
+            }
+        };
+        // A zip can ship several everest.yaml entries; any of them matching a
+        // published build is enough.
+        let entries = installed
+            .iter()
+            .filter(|item| item.file == file)
+            .collect::<Vec<_>>();
+        let published = entries.iter().find_map(|item| {
+            mod_data
+                .get(&item.name)
+                .filter(|data| !data.xx_hash.is_empty())
+                .map(|data| (*item, data))
+        });
+        let matched = entries.iter().find_map(|item| {
+            mod_data
+                .get(&item.name)
+                .filter(|data| {
+                    data.xx_hash
+                        .iter()
+                        .any(|hash| hash.eq_ignore_ascii_case(&xx_hash))
+                })
+                .map(|data| (*item, data))
+        });
+        let (item, data, status) = match (matched, published) {
+            (Some((item, data)), _) => (item, Some(data), ModHashAuditStatus::Published),
+            (None, Some((item, data)))
+                if resolver::ModVersion::parse(&item.version)
+                    == resolver::ModVersion::parse(&data.version) =>
+            {
+                (item, Some(data), ModHashAuditStatus::Modified)
+            }
+            (None, Some((item, data))) => (item, Some(data), ModHashAuditStatus::Outdated),
+            (None, None) => (entries[0], None, ModHashAuditStatus::Unknown),
+        };
+        audits.push(ModHashAudit {
+            file,
+            name: item.name.clone(),
+            version: item.version.clone(),
+            catalog_version: data.map(|data| data.version.clone()),
+            xx_hash,
+            expected_hashes: data.map(|data| data.xx_hash.clone()).unwrap_or_default(),
+            status,
+        });
+    }
+    mod_index::flush(Path::new(mods_folder_path));
+    audits
+}
+
+fn mods_folder_game_path(mods_folder_path: &Path) -> &Path {
+    mods_folder_path.parent().unwrap_or(mods_folder_path)
+}
+
 fn delete_mod_files_sync(mods_folder_path: &str, file_names: &[String]) -> anyhow::Result<()> {
+    let game_path = mods_folder_game_path(Path::new(mods_folder_path));
     for file_name in file_names {
         let safe_name = Path::new(file_name)
             .file_name()
             .context("Invalid mod file name")?;
         let path = Path::new(mods_folder_path).join(safe_name);
         if path.exists() {
-            if path.is_dir() {
-                fs::remove_dir_all(path)?;
-            } else {
-                fs::remove_file(path)?;
-            }
+            mod_backups::remove_to_backups(game_path, &path, mod_backups::BackupReason::Delete)?;
         }
     }
     Ok(())
@@ -1440,6 +1545,7 @@ fn download_mod_archive_with_cancel(
     progress_callback: &mut dyn FnMut(DownloadCallbackInfo),
     multi_thread: bool,
     cancel_flag: &Arc<AtomicBool>,
+    expected_hashes: &[String],
 ) -> anyhow::Result<Vec<EverestModMetadata>> {
     let destination = Path::new(dest);
     let destination_lock = DOWNLOAD_DESTINATION_LOCKS
@@ -1470,6 +1576,9 @@ fn download_mod_archive_with_cancel(
             multi_thread,
             cancel_flag,
         )?;
+        verify_mod_archive_hash(&temporary, expected_hashes).inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })?;
 
         commit_downloaded_mod_archive(&temporary, destination)?
     };
@@ -1477,6 +1586,44 @@ fn download_mod_archive_with_cancel(
     result
 }
 
+/// Hashes a Mod archive the way Everest and the Mod catalog do: xxHash64 with
+/// seed 0, formatted as 16 lowercase hex digits.
+fn mod_archive_xx_hash(path: &Path) -> anyhow::Result<String> {
+    use std::hash::Hasher;
+
+    let mut file = fs::File::open(path)
+        .with_context(|| format!("Failed to open Mod archive {}", path.display()))?;
+    let mut hasher = twox_hash::XxHash64::with_seed(0);
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.write(&buffer[..read]);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// An empty list means the source published no hash, so there is nothing to
+/// check against.
+fn verify_mod_archive_hash(path: &Path, expected_hashes: &[String]) -> anyhow::Result<()> {
+    if expected_hashes.is_empty() {
+        return Ok(());
+    }
+    let actual = mod_archive_xx_hash(path)?;
+    if !expected_hashes
+        .iter()
+        .any(|expected| expected.trim().eq_ignore_ascii_case(&actual))
+    {
+        bail!(
+            "Downloaded archive hash {actual} does not match the expected xxHash ({}); the installed archive was kept",
+            expected_hashes.join(", ")
+        );
+    }
+    Ok(())
+}
+
 fn mod_download_sidecar_path(destination: &Path) -> PathBuf {
     let mut temporary_name = destination.as_os_str().to_os_string();
     temporary_name.push(".celemod");
@@ -1491,6 +1638,16 @@ fn replace_mod_archive(temporary: &Path, destination: &Path) -> anyhow::Result<(
         return Ok(());
     }
 
+    if destination.is_file() {
+        let mods_folder = destination.parent().unwrap_or(destination);
+        mod_backups::store_archive(
+            mods_folder_game_path(mods_folder),
+            destination,
+            mod_backups::BackupReason::Replace,
+        )
+        .context("Failed to back up the installed Mod archive")?;
+    }
+
     let unique = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
@@ -1541,16 +1698,6 @@ fn commit_downloaded_mod_archive(
     // incomplete update must never remove the currently working Mod.
     let metadata_entries = parse_mod_yaml(temporary)?;
     replace_mod_archive(temporary, destination)?;
-
-    // The cache is only an optimization. The archive has already been fully
-    // validated, so a cache write failure should not turn a successful update
-    // into a destructive retry path.
-    if let Err(error) = extract_mod_for_yaml(destination) {
-        crate::logging::warn(format_args!(
-            "Failed to refresh Mod metadata cache for {}: {error:#}",
-            destination.display()
-        ));
-    }
     Ok(metadata_entries)
 }
 
@@ -1579,13 +1726,13 @@ fn cleanup_game_mod_download_temp_files(game_path: &Path) -> anyhow::Result<usiz
     cleanup_mod_download_temp_files_impl(&normalize_game_path_buf(game_path).join("Mods"))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct ModDependency {
     name: String,
     version: String,
     optional: bool,
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct LocalMod {
     game_banana_id: i64,
     name: String,
@@ -1646,120 +1793,40 @@ fn get_installed_mods_sync_with_catalog(
     mods_folder_path: String,
     mod_data: Option<Arc<HashMap<String, everest::ModInfoCached>>>,
 ) -> Vec<LocalMod> {
+    let mods_dir = Path::new(&mods_folder_path);
     let mut mods = Vec::new();
-
-    let Ok(entries) = fs::read_dir(mods_folder_path) else {
-        return mods;
-    };
-
-    for entry in entries {
-        let Ok(entry) = entry else {
+    for indexed in mod_index::scan_mods(mods_dir) {
+        let Ok(metadata) = fs::metadata(mods_dir.join(&indexed.file)) else {
             continue;
         };
-        let res: anyhow::Result<_> = try {
-            if false {
-                anyhow::Ok(())?
-            }
+        let size = metadata.len();
+        let modified_at = metadata
+            .modified()
+            .ok()
+            .and_then(|value| value.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|value| value.as_millis() as u64)
+            .unwrap_or_default();
 
-            let yaml = if entry.file_type().context("invalid file type")?.is_dir() {
-                let cache_path = entry.path().read_dir().unwrap().find(|v| {
-                    v.as_ref()
-                        .map(|v| {
-                            let name = v.file_name().to_string_lossy().to_string().to_lowercase();
-                            name == "everest.yaml" || name == "everest.yml"
-                        })
-                        .unwrap_or(false)
-                });
-                match cache_path {
-                    Some(cache_path) => {
-                        let cache_path = cache_path.unwrap().path();
-                        read_to_string_bom(&cache_path)?
-                    }
-                    None => {
-                        continue;
-                    }
-                }
-            } else if entry
-                .path()
-                .extension()
-                .context("Unable to get the extension")?
-                == "zip"
-            {
-                let cache_path = entry
-                    .path()
-                    .parent()
-                    .unwrap()
-                    .parent()
-                    .unwrap()
-                    .join("celemod_yaml_cache")
-                    .join(entry.path().with_extension("yaml").file_name().unwrap());
-
-                let mod_date = entry.metadata().unwrap().modified().unwrap();
-                let cache_date = cache_path.metadata().ok().map(|v| v.modified().unwrap());
-
-                if !cache_path.exists() || cache_date.is_none() || cache_date.unwrap() < mod_date {
-                    extract_mod_for_yaml(&entry.path())?;
-                }
-                read_to_string_bom(&cache_path)?
-            } else {
+        for metadata_entry in &indexed.metadata {
+            let Some(name) = metadata_entry.name.as_ref() else {
                 continue;
             };
-
-            let metadata_entries = match parse_mod_yaml_document(&yaml) {
-                Ok(metadata_entries) => metadata_entries,
-                Err(error) => {
-                    crate::logging::warn(format_args!(
-                        "[ WARNING ] Failed to parse {:?}: {}",
-                        entry.file_name(),
-                        error
-                    ));
-                    continue;
-                }
-            };
-
-            let metadata = entry.metadata().context("Failed to read Mod metadata")?;
-            let size = metadata.len();
-            let modified_at = metadata
-                .modified()
-                .ok()
-                .and_then(|value| value.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|value| value.as_millis() as u64)
-                .unwrap_or_default();
-
-            let file = entry.file_name().to_string_lossy().to_string();
-            for metadata_entry in &metadata_entries {
-                let Some(name) = metadata_entry.name.as_ref() else {
-                    crate::logging::warn(format_args!(
-                        "[ WARNING ] Skipping unnamed metadata in {:?}",
-                        entry.file_name()
-                    ));
-                    continue;
-                };
-                let name = name.clone();
-                let gbid = mod_data
-                    .as_ref()
-                    .and_then(|catalog| catalog.get(&name))
-                    .map(|item| item.game_banana_id)
-                    .unwrap_or(-1);
-
-                mods.push(LocalMod {
-                    name,
-                    version: parse_version(metadata_entry.version.as_deref()),
-                    game_banana_id: gbid,
-                    deps: parse_mod_dependencies(metadata_entry),
-                    file: file.clone(),
-                    size,
-                    modified_at,
-                });
-            }
-        };
-
-        if let Err(e) = res {
-            crate::logging::warn(format_args!(
-                "[ WARNING ] Failed to parse {:?}: {}",
-                entry.file_name(),
-                e
-            ));
+            let name = name.clone();
+            let gbid = mod_data
+                .as_ref()
+                .and_then(|catalog| catalog.get(&name))
+                .map(|item| item.game_banana_id)
+                .unwrap_or(-1);
+
+            mods.push(LocalMod {
+                name,
+                version: parse_version(metadata_entry.version.as_deref()),
+                game_banana_id: gbid,
+                deps: parse_mod_dependencies(metadata_entry),
+                file: indexed.file.clone(),
+                size,
+                modified_at,
+            });
         }
     }
     mods
@@ -1785,9 +1852,16 @@ fn download_and_install_mod(
     progress_callback: &mut dyn FnMut(DownloadCallbackInfo),
     multi_thread: bool,
     cancel_flag: &Arc<AtomicBool>,
+    expected_hashes: &[String],
 ) -> anyhow::Result<Vec<(String, String)>> {
-    let metadata_entries =
-        download_mod_archive_with_cancel(url, dest, progress_callback, multi_thread, cancel_flag)?;
+    let metadata_entries = download_mod_archive_with_cancel(
+        url,
+        dest,
+        progress_callback,
+        multi_thread,
+        cancel_flag,
+        expected_hashes,
+    )?;
 
     let mut deps: Vec<(String, String)> = Vec::new();
 
@@ -1812,15 +1886,16 @@ fn download_and_install_mod(
 
 fn rm_mod_sync(mods_folder_path: &str, mod_name: &str) -> anyhow::Result<()> {
     let mods = get_installed_mods_sync(mods_folder_path.to_string());
+    let game_path = mods_folder_game_path(Path::new(mods_folder_path));
     for mod_ in mods {
         if mod_.name == mod_name {
             let path = Path::new(mods_folder_path).join(&mod_.file);
             if path.exists() {
-                if path.is_dir() {
-                    fs::remove_dir_all(path)?;
-                } else {
-                    fs::remove_file(path)?;
-                }
+                mod_backups::remove_to_backups(
+                    game_path,
+                    &path,
+                    mod_backups::BackupReason::Delete,
+                )?;
             }
         }
     }
@@ -1841,6 +1916,14 @@ fn get_celestes() -> Vec<Game> {
     games
 }
 
+/// Steam copies restart through Steam, which drops the environment we set.
+fn is_steam_copy(path: &str) -> bool {
+    game_scanner::steam::find("504230")
+        .ok()
+        .and_then(|game| game.path)
+        .is_some_and(|game_path| normalize_game_path_buf(&game_path).to_string_lossy() == path)
+}
+
 fn normalize_game_path_impl(path: &str) -> String {
     normalize_game_path_buf(Path::new(path))
         .to_string_lossy()
@@ -1965,54 +2048,239 @@ fn emit_download_tasks(tasks: &[DownloadInfo], on_event: &Channel<IpcEvent>, sta
     );
 }
 
+/// Where a download of `name` goes: an update overwrites the installed
+/// archive instead of adding a second copy of the Mod next to it.
+fn download_file_name(installed: &[LocalMod], mods_dir: &str, name: &str) -> String {
+    installed
+        .iter()
+        .find(|item| {
+            item.name.eq_ignore_ascii_case(name)
+                && Path::new(mods_dir).join(&item.file).is_file()
+                && Path::new(&item.file)
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+        })
+        .map(|item| item.file.clone())
+        .unwrap_or_else(|| format!("{}.zip", make_path_compatible_name(name)))
+}
+
+fn describe_resolution_problem(problem: &resolver::ResolutionProblem) -> String {
+    let required = problem
+        .constraints
+        .iter()
+        .map(|constraint| format!("{} {}", constraint.required_by, constraint.version))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if required.is_empty() {
+        format!("Dependency conflict ({:?}): {}", problem.kind, problem.name)
+    } else {
+        format!(
+            "Dependency conflict ({:?}): {} is required by {required}",
+            problem.kind, problem.name
+        )
+    }
+}
+
+fn queue_planned_step(
+    tasks: &mut Vec<DownloadInfo>,
+    queued: &mut HashMap<String, usize>,
+    step: &resolver::PlannedInstall,
+    installed: &[LocalMod],
+    mods_dir: &str,
+) -> bool {
+    let dest = Path::new(mods_dir)
+        .join(download_file_name(installed, mods_dir, &step.name))
+        .to_string_lossy()
+        .to_string();
+    if queued
+        .keys()
+        .any(|name| name.eq_ignore_ascii_case(&step.name))
+        || tasks.iter().any(|task| task.dest == dest)
+    {
+        return false;
+    }
+    queued.insert(step.name.clone(), tasks.len());
+    tasks.push(DownloadInfo {
+        name: step.name.clone(),
+        url: step.download_url.clone(),
+        dest,
+        status: DownloadStatus::Waiting,
+        data: "0".to_string(),
+        downloaded_bytes: 0,
+        total_bytes: 0,
+        speed_bytes_per_sec: 0.0,
+    });
+    true
+}
+
+/// Resolves `requested` against the installed Mods, the catalog and the
+/// dependency graph before anything is downloaded, and returns the
+/// dependency downloads the plan needs. A plan with conflicts is refused, so
+/// the Mods folder stays untouched. Mods the catalog does not know are left
+/// to the check after their own download.
+fn plan_dependency_downloads(
+    requested: &[String],
+    installed: &[LocalMod],
+    mod_data: &HashMap<String, everest::ModInfoCached>,
+    graph: &HashMap<String, resolver::DependencyGraphEntry>,
+    mods_dir: &str,
+) -> anyhow::Result<Vec<DownloadInfo>> {
+    let known = requested
+        .iter()
+        .filter(|name| {
+            mod_data.contains_key(*name)
+                || mod_data.keys().any(|key| key.eq_ignore_ascii_case(name))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    let plan = resolver::plan_mod_install(&known, &[], installed, mod_data, graph);
+    if !plan.is_installable() {
+        bail!(
+            "Cannot install {}: {}",
+            requested.join(", "),
+            plan.problems
+                .iter()
+                .filter(|problem| problem.kind != resolver::ProblemKind::Cycle)
+                .map(describe_resolution_problem)
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+    let mut tasks = Vec::new();
+    let mut queued = HashMap::new();
+    for step in plan.steps.iter().filter(|step| {
+        !requested
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&step.name))
+    }) {
+        queue_planned_step(&mut tasks, &mut queued, step, installed, mods_dir);
+    }
+    Ok(tasks)
+}
+
+/// Queues what the just-downloaded `parent` needs beyond the plan the queue
+/// started from. That happens when the dependency graph does not know the
+/// downloaded build yet, so the resolver plans again from the Mods folder as
+/// it is now. Only what `parent` pulls in is queued; conflicts become failed
+/// tasks instead of downloads.
 fn enqueue_missing_dependencies(
     tasks: &mut Vec<DownloadInfo>,
     queued: &mut HashMap<String, usize>,
+    parent: &str,
     dependencies: Vec<(String, String)>,
-    installed: &[LocalMod],
     mod_data: &HashMap<String, everest::ModInfoCached>,
+    graph: &HashMap<String, resolver::DependencyGraphEntry>,
     mods_dir: &str,
 ) -> usize {
+    // queued 记录 Waiting / Downloading / Finished / Failed 的整个任务队列，
+    // 任意父依赖重复发现同一个名字时都不会再次入队。
+    let pending = dependencies
+        .into_iter()
+        .filter(|(dependency, _)| {
+            !resolver::is_loader_dependency(dependency)
+                && !queued
+                    .keys()
+                    .any(|name| name.eq_ignore_ascii_case(dependency))
+        })
+        .collect::<Vec<_>>();
+    if pending.is_empty() {
+        return 0;
+    }
+    let installed = get_installed_mods_without_catalog_sync(mods_dir.to_string());
+    if pending.iter().all(|(dependency, min_version)| {
+        installed.iter().any(|item| {
+            item.name.eq_ignore_ascii_case(dependency)
+                && resolver::version_satisfies(min_version, &item.version)
+        })
+    }) {
+        return 0;
+    }
+
+    let plan = resolver::plan_mod_install(&[], &[], &installed, mod_data, graph);
+    // Steps come dependencies first, so walking them backwards meets every
+    // dependent before what it requires.
+    let mut needed = HashSet::from([parent.to_ascii_lowercase()]);
+    let mut steps = plan
+        .steps
+        .iter()
+        .rev()
+        .filter(|step| {
+            let wanted = step
+                .required_by
+                .iter()
+                .any(|name| needed.contains(&name.to_ascii_lowercase()));
+            if wanted {
+                needed.insert(step.name.to_ascii_lowercase());
+            }
+            wanted
+        })
+        .collect::<Vec<_>>();
+    steps.reverse();
     let mut added = 0;
-    for (dependency, min_version) in dependencies {
-        // queued 记录 Waiting / Downloading / Finished / Failed 的整个任务队列，
-        // 任意父依赖重复发现同一个名字时都不会再次入队。
-        if queued.contains_key(&dependency)
-            || installed.iter().any(|item| {
-                item.name == dependency && compare_version(&item.version, &min_version) >= 0
+    for step in steps {
+        if queue_planned_step(tasks, queued, step, &installed, mods_dir) {
+            added += 1;
+        }
+    }
+
+    for problem in &plan.problems {
+        if problem.kind == resolver::ProblemKind::Cycle
+            || !problem.constraints.iter().any(|constraint| {
+                !constraint.optional
+                    && needed.contains(&constraint.required_by.to_ascii_lowercase())
             })
+            || queued
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case(&problem.name))
         {
             continue;
         }
-        let Some(data) = mod_data.get(&dependency) else {
-            crate::logging::error(format_args!(
-                "Failed to resolve dependency {dependency} in Mod data"
-            ));
-            continue;
-        };
-
-        let index = tasks.len();
-        queued.insert(dependency.clone(), index);
+        let message = describe_resolution_problem(problem);
+        crate::logging::warn(format_args!("{message}"));
+        queued.insert(problem.name.clone(), tasks.len());
         tasks.push(DownloadInfo {
-            name: dependency.clone(),
-            url: data.download_url.clone(),
-            dest: Path::new(mods_dir)
-                .join(format!("{}.zip", make_path_compatible_name(&dependency)))
-                .to_string_lossy()
-                .to_string(),
-            status: DownloadStatus::Waiting,
-            data: "0".to_string(),
+            name: problem.name.clone(),
+            url: String::new(),
+            dest: String::new(),
+            status: DownloadStatus::Failed,
+            data: message,
             downloaded_bytes: 0,
             total_bytes: 0,
             speed_bytes_per_sec: 0.0,
         });
-        added += 1;
     }
     added
 }
 
+/// The catalog only publishes hashes for the current file of each Mod, so they
+/// are enforced only when the URL points at that file through any mirror. An
+/// older file picked from the GameBanana file list is downloaded unchecked.
+fn catalog_hashes_for_download(
+    mod_data: &HashMap<String, everest::ModInfoCached>,
+    name: &str,
+    url: &str,
+) -> Vec<String> {
+    let Some(item) = mod_data.get(name) else {
+        return Vec::new();
+    };
+    let file_id = url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .map(|segment| segment.trim_end_matches(".zip"))
+        .and_then(|segment| segment.parse::<i64>().ok());
+    if url == item.download_url
+        || (item.game_banana_file_id > 0 && file_id == Some(item.game_banana_file_id))
+    {
+        item.xx_hash.clone()
+    } else {
+        Vec::new()
+    }
+}
+
 fn start_waiting_mod_downloads(
     tasks: &mut [DownloadInfo],
+    mod_data: &HashMap<String, everest::ModInfoCached>,
     started_or_finished: &mut HashSet<String>,
     sender: &std::sync::mpsc::Sender<DownloadWorkerMessage>,
     handles: &mut Vec<std::thread::JoinHandle<()>>,
@@ -2043,6 +2311,7 @@ fn start_waiting_mod_downloads(
         let sender = sender.clone();
         let task_url = tasks[index].url.clone();
         let task_dest = tasks[index].dest.clone();
+        let expected_hashes = catalog_hashes_for_download(mod_data, &tasks[index].name, &task_url);
         let cancel_flag = Arc::clone(cancel_flag);
         handles.push(std::thread::spawn(move || {
             let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
@@ -2056,6 +2325,7 @@ fn start_waiting_mod_downloads(
                     },
                     multi_thread,
                     &cancel_flag,
+                    &expected_hashes,
                 )
                 .map_err(|error| format!("{error:#}"))
             }))
@@ -2072,8 +2342,8 @@ fn start_waiting_mod_downloads(
 /// 并马上启动所有 Waiting 项，不等待同一层的其他下载结束。
 fn download_mod_queue(
     tasks: &mut Vec<DownloadInfo>,
-    installed: &[LocalMod],
     mod_data: &HashMap<String, everest::ModInfoCached>,
+    graph: &HashMap<String, resolver::DependencyGraphEntry>,
     mods_dir: &str,
     on_event: &Channel<IpcEvent>,
     multi_thread: bool,
@@ -2090,6 +2360,7 @@ fn download_mod_queue(
     let mut failed = false;
     let mut active = start_waiting_mod_downloads(
         tasks,
+        mod_data,
         &mut started_or_finished,
         &sender,
         &mut handles,
@@ -2119,12 +2390,14 @@ fn download_mod_queue(
                         tasks[index].data = "100".to_string();
                         tasks[index].speed_bytes_per_sec = 0.0;
                         if !cancel_flag.load(Ordering::Relaxed) {
+                            let parent = tasks[index].name.clone();
                             enqueue_missing_dependencies(
                                 tasks,
                                 &mut queued,
+                                &parent,
                                 task_dependencies,
-                                installed,
                                 mod_data,
+                                graph,
                                 mods_dir,
                             );
                         }
@@ -2140,6 +2413,7 @@ fn download_mod_queue(
                 // 新依赖一入队就立即启动；不等待其他 active 任务结束。
                 active += start_waiting_mod_downloads(
                     tasks,
+                    mod_data,
                     &mut started_or_finished,
                     &sender,
                     &mut handles,
@@ -2168,7 +2442,12 @@ fn download_mod_queue(
         }
     }
 
-    failed || cancel_flag.load(Ordering::Relaxed)
+    // Dependency conflicts are queued as failed tasks without a download.
+    failed
+        || cancel_flag.load(Ordering::Relaxed)
+        || tasks
+            .iter()
+            .any(|task| task.status == DownloadStatus::Failed)
 }
 
 fn make_path_compatible_name(name: &str) -> String {
@@ -2283,7 +2562,16 @@ fn classify_local_package(path: &Path) -> anyhow::Result<LocalPackageKind> {
     }
 }
 
-fn replace_local_mod_archive(source: &Path, destination: &Path) -> anyhow::Result<()> {
+fn replace_local_mod_archive(
+    source: &Path,
+    destination: &Path,
+    reason: mod_backups::BackupReason,
+) -> anyhow::Result<()> {
+    if destination.is_file() {
+        let mods_folder = destination.parent().unwrap_or(destination);
+        mod_backups::store_archive(mods_folder_game_path(mods_folder), destination, reason)
+            .context("Failed to back up the installed Mod archive")?;
+    }
     let file_name = destination
         .file_name()
         .context("Failed to resolve Mod archive file name")?
@@ -2334,7 +2622,11 @@ fn install_local_mod(game_path: &Path, package_path: &Path) -> anyhow::Result<(S
     fs::create_dir_all(&mods_path)?;
     let destination = mods_path.join(source_name);
 
-    replace_local_mod_archive(package_path, &destination)?;
+    replace_local_mod_archive(
+        package_path,
+        &destination,
+        mod_backups::BackupReason::Replace,
+    )?;
     Ok((mod_name, destination_name))
 }
 
@@ -2390,7 +2682,11 @@ fn replace_installed_mod_with_fix(
     {
         bail!("Automatic repair currently supports zip-installed Mods only");
     }
-    replace_local_mod_archive(package_path, &destination)?;
+    replace_local_mod_archive(
+        package_path,
+        &destination,
+        mod_backups::BackupReason::CrashFix,
+    )?;
     Ok(local_mod.file.clone())
 }
 
@@ -2561,6 +2857,65 @@ fn is_newly_installed_mod(name: &str, installed_before: &HashSet<String>) -> boo
     !installed_before.contains(&name.to_ascii_lowercase())
 }
 
+/// Updates every outdated zipped Mod (or only `names`) through the download
+/// queue. Archives are backed up and journaled first, so the returned batch
+/// can be rolled back with `mod_updates::rollback_mod_updates`.
+fn update_all_mods_sync(
+    game_path: &Path,
+    names: &[String],
+    on_event: &Channel<IpcEvent>,
+    multi_thread: bool,
+    cancel_flag: &Arc<AtomicBool>,
+) -> anyhow::Result<(bool, Vec<DownloadInfo>, mod_updates::UpdateJournal)> {
+    let mods_dir = game_path.join("Mods").to_string_lossy().into_owned();
+    let mod_data = get_mod_cached_new().context("Failed to get Mod data")?;
+    let installed = get_installed_mods_sync(mods_dir.clone());
+    let names = names
+        .iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    let mut updates = mod_updates::plan_mod_updates(Path::new(&mods_dir), &installed, &mod_data)
+        .into_iter()
+        .filter(|update| names.is_empty() || names.contains(&update.name.to_ascii_lowercase()))
+        .collect::<Vec<_>>();
+    if updates.is_empty() {
+        bail!("All Mods are up to date");
+    }
+    mod_updates::attach_changelogs(&mut updates);
+
+    let mut journal = mod_updates::begin_update_batch(game_path, &updates)?;
+    let mut tasks = updates
+        .iter()
+        .map(|update| DownloadInfo {
+            name: update.name.clone(),
+            url: update.download_url.clone(),
+            dest: Path::new(&mods_dir)
+                .join(&update.file)
+                .to_string_lossy()
+                .to_string(),
+            status: DownloadStatus::Waiting,
+            data: String::new(),
+            downloaded_bytes: 0,
+            total_bytes: 0,
+            speed_bytes_per_sec: 0.0,
+        })
+        .collect::<Vec<_>>();
+    // Without the graph, dependencies are still planned from each downloaded
+    // everest.yaml.
+    let graph = resolver::get_dependency_graph().unwrap_or_default();
+    let failed = download_mod_queue(
+        &mut tasks,
+        &mod_data,
+        &graph,
+        &mods_dir,
+        on_event,
+        multi_thread,
+        cancel_flag,
+    );
+    mod_updates::finish_update_batch(game_path, &mut journal, &tasks, &mod_data)?;
+    Ok((failed, tasks, journal))
+}
+
 #[cfg(test)]
 mod local_package_tests {
     use super::*;
@@ -2971,50 +3326,199 @@ mod local_package_tests {
     #[test]
     fn deletes_mod_archives_and_directories() {
         let root = test_dir("delete-mod-files");
-        let archive = root.join("Archive.zip");
-        let directory = root.join("DirectoryMod");
-        fs::write(&archive, b"archive").unwrap();
+        let mods = root.join("Mods");
+        let archive = mods.join("Archive.zip");
+        let directory = mods.join("DirectoryMod");
         fs::create_dir_all(directory.join("nested")).unwrap();
+        fs::write(&archive, b"archive").unwrap();
         fs::write(directory.join("nested").join("file.txt"), b"folder mod").unwrap();
 
         delete_mod_files_sync(
-            root.to_str().unwrap(),
+            mods.to_str().unwrap(),
             &["Archive.zip".to_string(), "DirectoryMod".to_string()],
         )
         .unwrap();
 
         assert!(!archive.exists());
         assert!(!directory.exists());
+        let mut backed_up = mod_backups::list_backups(&root)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.file)
+            .collect::<Vec<_>>();
+        backed_up.sort();
+        assert_eq!(backed_up, ["Archive.zip", "DirectoryMod"]);
         fs::remove_dir_all(root).unwrap();
     }
 
     #[test]
-    fn dependency_queue_deduplicates_all_task_states() {
-        let root = test_dir("dependency-queue");
-        let mut tasks = Vec::new();
-        let mut queued = HashMap::new();
+    fn catalog_hashes_only_apply_to_the_published_file() {
         let mod_data = HashMap::from([(
-            "SharedDependency".to_string(),
+            "Example".to_string(),
             everest::ModInfoCached {
-                name: "SharedDependency".to_string(),
+                name: "Example".to_string(),
                 version: "1.0.0".to_string(),
                 game_banana_id: 1,
-                game_banana_file_id: 2,
-                download_url: "https://example.invalid/dependency.zip".to_string(),
+                game_banana_file_id: 1234,
+                download_url: "https://celeste.weg.fan/api/v2/download/mods/Example".to_string(),
+                xx_hash: vec!["0123456789abcdef".to_string()],
             },
         )]);
-        let dependencies = vec![
-            ("SharedDependency".to_string(), "1.0.0".to_string()),
-            ("SharedDependency".to_string(), "1.0.0".to_string()),
-        ];
-
-        assert_eq!(
-            enqueue_missing_dependencies(
-                &mut tasks,
-                &mut queued,
-                dependencies.clone(),
-                &[],
-                &mod_data,
+        for url in [
+            "https://celeste.weg.fan/api/v2/download/mods/Example",
+            "https://celeste.weg.fan/api/v2/download/gamebanana-files/1234",
+            "https://celestemodupdater.0x0a.de/banana-mirror/1234.zip",
+            "https://gamebanana.com/dl/1234",
+        ] {
+            assert_eq!(
+                catalog_hashes_for_download(&mod_data, "Example", url),
+                ["0123456789abcdef"],
+                "{url}"
+            );
+        }
+        assert!(
+            catalog_hashes_for_download(&mod_data, "Example", "https://gamebanana.com/dl/999")
+                .is_empty()
+        );
+        assert!(
+            catalog_hashes_for_download(&mod_data, "Other", "https://gamebanana.com/dl/1234")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn rejects_archives_with_unexpected_hashes() {
+        let root = test_dir("hash-verify");
+        let archive = root.join("Example.zip");
+        write_zip(
+            &archive,
+            &[("everest.yaml", b"- Name: Example\n  Version: 1.0.0\n")],
+        );
+        let hash = mod_archive_xx_hash(&archive).unwrap();
+
+        assert!(verify_mod_archive_hash(&archive, &[]).is_ok());
+        assert!(verify_mod_archive_hash(&archive, &[hash.to_ascii_uppercase()]).is_ok());
+        let error = verify_mod_archive_hash(&archive, &["0000000000000000".to_string()])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains(&hash));
+        assert!(error.contains("0000000000000000"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn audits_installed_archives_against_catalog_hashes() {
+        let root = test_dir("hash-audit");
+        let mods = root.join("Mods");
+        fs::create_dir_all(&mods).unwrap();
+        write_zip(
+            &mods.join("Published.zip"),
+            &[("everest.yaml", b"- Name: Published\n  Version: 1.0.0\n")],
+        );
+        write_zip(
+            &mods.join("Modified.zip"),
+            &[("everest.yaml", b"- Name: Modified\n  Version: 1.0.0\n")],
+        );
+        write_zip(
+            &mods.join("Outdated.zip"),
+            &[("everest.yaml", b"- Name: Outdated\n  Version: 0.9.0\n")],
+        );
+        write_zip(
+            &mods.join("Local.zip"),
+            &[("everest.yaml", b"- Name: Local\n  Version: 1.0.0\n")],
+        );
+        fs::create_dir_all(mods.join("Unpacked")).unwrap();
+        fs::write(
+            mods.join("Unpacked").join("everest.yaml"),
+            "- Name: Unpacked\n  Version: 1.0.0\n",
+        )
+        .unwrap();
+        let entry = |name: &str, xx_hash: String| {
+            (
+                name.to_string(),
+                everest::ModInfoCached {
+                    name: name.to_string(),
+                    version: "1.0.0".to_string(),
+                    game_banana_id: 1,
+                    game_banana_file_id: 2,
+                    download_url: String::new(),
+                    xx_hash: vec![xx_hash],
+                },
+            )
+        };
+        let mod_data = HashMap::from([
+            entry(
+                "Published",
+                mod_archive_xx_hash(&mods.join("Published.zip")).unwrap(),
+            ),
+            entry("Modified", "0000000000000000".to_string()),
+            entry("Outdated", "0000000000000000".to_string()),
+            entry("Unpacked", "0000000000000000".to_string()),
+        ]);
+
+        let audits = audit_installed_mod_hashes_sync(&mods.to_string_lossy(), &mod_data);
+        let statuses = audits
+            .iter()
+            .map(|audit| (audit.name.as_str(), audit.status))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            statuses,
+            [
+                ("Local", ModHashAuditStatus::Unknown),
+                ("Modified", ModHashAuditStatus::Modified),
+                ("Outdated", ModHashAuditStatus::Outdated),
+                ("Published", ModHashAuditStatus::Published),
+            ]
+        );
+        assert_eq!(audits[2].catalog_version.as_deref(), Some("1.0.0"));
+        assert_eq!(audits[1].expected_hashes, ["0000000000000000"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    fn catalog_entry(name: &str, version: &str) -> (String, everest::ModInfoCached) {
+        (
+            name.to_string(),
+            everest::ModInfoCached {
+                name: name.to_string(),
+                version: version.to_string(),
+                game_banana_id: 1,
+                game_banana_file_id: 2,
+                download_url: format!("https://example.invalid/{name}.zip"),
+                xx_hash: Vec::new(),
+            },
+        )
+    }
+
+    fn write_mod(mods: &Path, file: &str, yaml: &str) {
+        write_zip(&mods.join(file), &[("everest.yaml", yaml.as_bytes())]);
+    }
+
+    #[test]
+    fn dependency_queue_deduplicates_all_task_states() {
+        let root = test_dir("dependency-queue");
+        fs::create_dir_all(&root).unwrap();
+        write_mod(
+            &root,
+            "Parent.zip",
+            "- Name: Parent\n  Version: 1.0.0\n  Dependencies:\n  - Name: SharedDependency\n    Version: 1.0.0\n",
+        );
+        let mut tasks = Vec::new();
+        let mut queued = HashMap::new();
+        let mod_data = HashMap::from([catalog_entry("SharedDependency", "1.0.0")]);
+        let dependencies = vec![
+            ("SharedDependency".to_string(), "1.0.0".to_string()),
+            ("SharedDependency".to_string(), "1.0.0".to_string()),
+        ];
+
+        assert_eq!(
+            enqueue_missing_dependencies(
+                &mut tasks,
+                &mut queued,
+                "Parent",
+                dependencies.clone(),
+                &mod_data,
+                &HashMap::new(),
                 root.to_string_lossy().as_ref(),
             ),
             1
@@ -3032,9 +3536,10 @@ mod local_package_tests {
                 enqueue_missing_dependencies(
                     &mut tasks,
                     &mut queued,
+                    "Parent",
                     dependencies.clone(),
-                    &[],
                     &mod_data,
+                    &HashMap::new(),
                     root.to_string_lossy().as_ref(),
                 ),
                 0
@@ -3045,6 +3550,141 @@ mod local_package_tests {
         fs::remove_dir_all(root).unwrap();
     }
 
+    #[test]
+    fn dependency_queue_replans_from_the_mods_folder() {
+        let root = test_dir("dependency-queue-replan");
+        let (conflicting, updating) = (root.join("conflicting"), root.join("updating"));
+        let map = "- Name: Map\n  Version: 1.0.0\n  Dependencies:\n  - Name: Helper\n    Version: 1.2.0\n";
+        for mods in [&conflicting, &updating] {
+            fs::create_dir_all(mods).unwrap();
+            write_mod(mods, "Map.zip", map);
+        }
+        write_mod(
+            &conflicting,
+            "Helper-v2.zip",
+            "- Name: helper\n  Version: 2.0.0\n",
+        );
+        write_mod(
+            &conflicting,
+            "Other.zip",
+            "- Name: Other\n  Version: 1.0.0\n  Dependencies:\n  - Name: Helper\n    Version: 2.0.0\n",
+        );
+        write_mod(
+            &updating,
+            "Helper-v1.zip",
+            "- Name: helper\n  Version: 1.0.0\n",
+        );
+        let mod_data = HashMap::from([catalog_entry("Helper", "1.4.0")]);
+        let dependencies = vec![("Helper".to_string(), "1.2.0".to_string())];
+
+        let (mut tasks, mut queued) = (Vec::new(), HashMap::new());
+        for _ in 0..2 {
+            assert_eq!(
+                enqueue_missing_dependencies(
+                    &mut tasks,
+                    &mut queued,
+                    "Map",
+                    dependencies.clone(),
+                    &mod_data,
+                    &HashMap::new(),
+                    conflicting.to_string_lossy().as_ref(),
+                ),
+                0
+            );
+        }
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].status, DownloadStatus::Failed);
+        assert!(tasks[0].data.contains("Map 1.2.0"), "{}", tasks[0].data);
+
+        let (mut tasks, mut queued) = (Vec::new(), HashMap::new());
+        assert_eq!(
+            enqueue_missing_dependencies(
+                &mut tasks,
+                &mut queued,
+                "Map",
+                dependencies,
+                &mod_data,
+                &HashMap::new(),
+                updating.to_string_lossy().as_ref(),
+            ),
+            1
+        );
+        assert!(tasks[0].dest.ends_with("Helper-v1.zip"));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn dependency_plan_refuses_conflicts_before_downloading() {
+        let root = test_dir("dependency-plan");
+        fs::create_dir_all(&root).unwrap();
+        let installed = [
+            LocalMod {
+                game_banana_id: -1,
+                name: "Helper".to_string(),
+                deps: Vec::new(),
+                version: "2.0.0".to_string(),
+                file: "Helper.zip".to_string(),
+                size: 0,
+                modified_at: 0,
+            },
+            LocalMod {
+                game_banana_id: -1,
+                name: "Other".to_string(),
+                deps: vec![ModDependency {
+                    name: "Helper".to_string(),
+                    version: "2.0.0".to_string(),
+                    optional: false,
+                }],
+                version: "1.0.0".to_string(),
+                file: "Other.zip".to_string(),
+                size: 0,
+                modified_at: 0,
+            },
+        ];
+        let mod_data = HashMap::from([
+            catalog_entry("Map", "1.0.0"),
+            catalog_entry("Campaign", "1.0.0"),
+            catalog_entry("Helper", "2.0.0"),
+            catalog_entry("Lib", "1.1.0"),
+        ]);
+        let graph: HashMap<String, resolver::DependencyGraphEntry> = serde_yaml::from_str(
+            "Map:\n  Dependencies:\n  - Name: Helper\n    Version: 1.2.0\nCampaign:\n  Dependencies:\n  - Name: Lib\n    Version: 1.0.0\n",
+        )
+        .unwrap();
+        let mods_dir = root.to_string_lossy();
+
+        let error = plan_dependency_downloads(
+            &["Map".to_string()],
+            &installed,
+            &mod_data,
+            &graph,
+            &mods_dir,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.starts_with("Cannot install Map"), "{error}");
+        assert!(error.contains("Other 2.0.0"), "{error}");
+
+        let tasks = plan_dependency_downloads(
+            &["Campaign".to_string()],
+            &installed,
+            &mod_data,
+            &graph,
+            &mods_dir,
+        )
+        .unwrap();
+        assert_eq!(
+            tasks
+                .iter()
+                .map(|task| task.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Lib"]
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
     #[test]
     fn enable_downloaded_mod_adds_it_to_active_profile() {
         let root = test_dir("download-default-enable-profile");
@@ -3272,7 +3912,7 @@ fn start_game_directly_with_loader_impl(
     path: String,
     origin: bool,
     legacy_loader: bool,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<std::process::Child> {
     let path = normalize_game_path_impl(&path);
     let path = Path::new(&path);
 
@@ -3305,6 +3945,14 @@ fn start_game_directly_with_loader_impl(
     if origin && game_origin.exists() {
         command.arg("--vanilla");
     }
+    if let Some(save_root) = profile_saves::active_save_root(path) {
+        if is_steam_copy(&path.to_string_lossy()) {
+            bail!(
+                "The active profile uses isolated saves, but Steam restarts this copy of Celeste without EVEREST_SAVEPATH. Turn off save isolation for the profile or use a copy outside Steam."
+            );
+        }
+        command.env("EVEREST_SAVEPATH", save_root);
+    }
     if legacy_loader {
         // EverestUltra's accelerated loader can be disabled for one launch through
         // these environment switches, without changing the user's normal setup.
@@ -3313,12 +3961,18 @@ fn start_game_directly_with_loader_impl(
             .env("EVEREST_ILHOOK_STARTUP_TRANSACTION", "0")
             .env("EVEREST_LOADER_PGO_REORDER", "0");
     }
-    command.spawn()?;
-    Ok(())
+    Ok(command.spawn()?)
 }
 
 fn start_game_directly_impl(path: String, origin: bool) -> anyhow::Result<()> {
-    start_game_directly_with_loader_impl(path, origin, false)
+    let game_path = normalize_game_path_impl(&path);
+    let legacy_loader = !origin && profile_settings::active_legacy_loader(Path::new(&game_path));
+    let launch = (!origin).then(|| launch_snapshot::prepare_launch(Path::new(&game_path)));
+    let child = start_game_directly_with_loader_impl(path, origin, legacy_loader)?;
+    if let Some(launch) = launch {
+        launch_snapshot::watch_launch(game_path, launch, child);
+    }
+    Ok(())
 }
 
 fn stop_celeste_for_restart(game_path: &Path) -> anyhow::Result<usize> {
@@ -3369,7 +4023,10 @@ fn stop_celeste_for_restart(game_path: &Path) -> anyhow::Result<usize> {
 fn restart_game_with_loader_impl(game_path: String, legacy_loader: bool) -> anyhow::Result<()> {
     let game_path = normalize_game_path_impl(&game_path);
     stop_celeste_for_restart(Path::new(&game_path))?;
-    start_game_directly_with_loader_impl(game_path, false, legacy_loader)
+    let launch = launch_snapshot::prepare_launch(Path::new(&game_path));
+    let child = start_game_directly_with_loader_impl(game_path.clone(), false, legacy_loader)?;
+    launch_snapshot::watch_launch(game_path, launch, child);
+    Ok(())
 }
 
 #[derive(Deserialize, Serialize)]
@@ -3766,6 +4423,10 @@ fn start_loenn(install_root: String) -> Result<(), String> {
 #[tauri::command]
 fn start_game(path: String) -> Result<(), String> {
     let path = normalize_game_path_impl(&path);
+    // Launchers cannot pass EVEREST_SAVEPATH, so isolated saves start directly.
+    if profile_saves::active_save_root(Path::new(&path)).is_some() {
+        return start_game_directly_impl(path, false).map_err(|error| format!("{error:#}"));
+    }
     let celestes = get_celestes();
     if let Some(game) = celestes.iter().find(|game| {
         game.path
@@ -3787,12 +4448,17 @@ fn start_game_directly(path: String, origin: bool) -> Result<(), String> {
 #[tauri::command]
 async fn check_everest_crash(
     game_path: String,
+    language: Option<String>,
 ) -> Result<Option<crash_analysis::CrashAnalysis>, String> {
     let game_path = normalize_game_path_impl(&game_path);
-    tauri::async_runtime::spawn_blocking(move || crash_analysis::analyze_latest_crash(&game_path))
-        .await
-        .map_err(|error| format!("Crash analysis worker failed: {error}"))?
-        .map_err(|error| format!("{error:#}"))
+    // Older frontends do not send a language; they showed Chinese reports.
+    let language = language.unwrap_or_else(|| "zh-CN".to_string());
+    tauri::async_runtime::spawn_blocking(move || {
+        crash_analysis::analyze_latest_crash(&game_path, &language)
+    })
+    .await
+    .map_err(|error| format!("Crash analysis worker failed: {error}"))?
+    .map_err(|error| format!("{error:#}"))
 }
 
 #[tauri::command]
@@ -3811,6 +4477,117 @@ fn reveal_crash_report(path: String) -> Result<(), String> {
     crash_analysis::reveal_report(&path).map_err(|error| format!("{error:#}"))
 }
 
+#[tauri::command]
+fn get_crash_rules_info() -> crash_rules::CrashRulesInfo {
+    crash_rules::rules_info()
+}
+
+#[tauri::command]
+async fn refresh_crash_rules(
+    source: Option<String>,
+) -> Result<crash_rules::CrashRulesInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || crash_rules::refresh_rules(source.as_deref()))
+        .await
+        .map_err(|error| format!("Crash rule worker failed: {error}"))?
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+async fn export_crash_bundle(
+    game_path: String,
+    language: Option<String>,
+    destination: String,
+) -> Result<(), String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    let language = language.unwrap_or_else(|| "zh-CN".to_string());
+    tauri::async_runtime::spawn_blocking(move || {
+        let analysis = match crash_analysis::last_analysis(&game_path) {
+            Some(analysis) => analysis,
+            None => crash_analysis::analyze_latest_crash(&game_path, &language)?
+                .context("There is no recent crash to export")?,
+        };
+        crash_bundle::export_bundle(Path::new(&game_path), &analysis, Path::new(&destination))
+    })
+    .await
+    .map_err(|error| format!("Crash bundle worker failed: {error}"))?
+    .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+async fn import_crash_bundle(
+    path: String,
+    language: Option<String>,
+) -> Result<crash_bundle::ImportedBundle, String> {
+    let language = language.unwrap_or_else(|| "zh-CN".to_string());
+    tauri::async_runtime::spawn_blocking(move || {
+        crash_bundle::import_bundle(Path::new(&path), &language)
+    })
+    .await
+    .map_err(|error| format!("Crash bundle worker failed: {error}"))?
+    .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+async fn list_everest_crashes(
+    game_path: String,
+) -> Result<Vec<crash_analysis::CrashListing>, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    tauri::async_runtime::spawn_blocking(move || crash_analysis::list_crashes(&game_path))
+        .await
+        .map_err(|error| format!("Crash analysis worker failed: {error}"))
+}
+
+#[tauri::command]
+async fn analyze_everest_crash(
+    game_path: String,
+    index: usize,
+    fingerprint: Option<String>,
+    language: String,
+) -> Result<crash_analysis::CrashAnalysis, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        crash_analysis::analyze_crash(&game_path, index, fingerprint.as_deref(), &language)
+    })
+    .await
+    .map_err(|error| format!("Crash analysis worker failed: {error}"))?
+    .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn get_crash_history(game_path: String) -> Result<crash_history::CrashHistory, String> {
+    crash_history::crash_history(&normalize_game_path_impl(&game_path))
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn start_crash_bisect(
+    game_path: String,
+    always_on_mods: Vec<String>,
+) -> Result<crash_bisect::BisectStatus, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    crash_bisect::start(&game_path, &always_on_mods).map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn run_crash_bisect_step(game_path: String, on_event: Channel<IpcEvent>) -> Result<(), String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    crash_bisect::run_step(game_path, on_event).map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn get_crash_bisect_status(
+    game_path: String,
+) -> Result<Option<crash_bisect::BisectStatus>, String> {
+    crash_bisect::get_status(&normalize_game_path_impl(&game_path))
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn finish_crash_bisect(game_path: String) -> Result<(), String> {
+    crash_bisect::finish(&normalize_game_path_impl(&game_path))
+        .map_err(|error| format!("{error:#}"))
+}
+
 #[tauri::command]
 fn open_url(url: String) -> Result<(), String> {
     open::that(url).map_err(|error| error.to_string())
@@ -3891,6 +4668,16 @@ fn configure_mod_cache(ttl_seconds: u64) {
     everest::set_mod_cache_ttl(ttl_seconds);
 }
 
+#[tauri::command]
+fn configure_mod_catalog_sources(sources: Vec<String>) -> Result<(), String> {
+    everest::set_mod_catalog_sources(&sources).map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn get_mod_catalog_sources() -> Vec<String> {
+    catalog_sources::catalog_sources()
+}
+
 #[tauri::command]
 fn get_mod_catalog(force_refresh: bool) -> Result<String, String> {
     everest::get_mod_catalog_json(force_refresh).map_err(|error| format!("{error:#}"))
@@ -3954,53 +4741,322 @@ fn get_database_path() -> String {
 }
 
 #[tauri::command]
-fn get_installed_mod_ids(mods_folder_path: String, on_event: Channel<IpcEvent>) {
-    std::thread::spawn(move || {
-        let ids = get_installed_mods_sync(mods_folder_path)
-            .into_iter()
-            .map(|item| item.game_banana_id.to_string())
-            .collect::<Vec<_>>()
-            .join("\n");
-        send_event(&on_event, vec![serde_json::json!(ids)]);
-    });
+fn get_installed_mod_ids(mods_folder_path: String, on_event: Channel<IpcEvent>) {
+    std::thread::spawn(move || {
+        let ids = get_installed_mods_sync(mods_folder_path)
+            .into_iter()
+            .map(|item| item.game_banana_id.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        send_event(&on_event, vec![serde_json::json!(ids)]);
+    });
+}
+
+#[tauri::command]
+fn get_installed_miaonet(mods_folder_path: String, on_event: Channel<IpcEvent>) {
+    std::thread::spawn(move || {
+        let installed = get_installed_mods_sync(mods_folder_path)
+            .into_iter()
+            .any(|item| item.name == "MiaoNet");
+        send_event(&on_event, vec![serde_json::json!(installed)]);
+    });
+}
+
+#[tauri::command]
+fn get_installed_mods(mods_folder_path: String, on_event: Channel<IpcEvent>) {
+    std::thread::spawn(move || {
+        let installed = get_installed_mods_sync(mods_folder_path);
+        let payload = serde_json::to_string(&installed).unwrap_or_else(|_| "[]".to_string());
+        send_event(&on_event, vec![serde_json::json!(payload)]);
+    });
+}
+
+#[tauri::command]
+fn get_invalid_zip_mod_files(mods_folder_path: String, on_event: Channel<IpcEvent>) {
+    std::thread::spawn(move || {
+        let invalid = get_invalid_zip_mod_files_sync(&mods_folder_path);
+        let payload = serde_json::to_string(&invalid).unwrap_or_else(|_| "[]".to_string());
+        send_event(&on_event, vec![serde_json::json!(payload)]);
+    });
+}
+
+#[tauri::command]
+fn check_all_mod_contents(mods_folder_path: String, on_event: Channel<IpcEvent>) {
+    std::thread::spawn(move || {
+        check_all_mod_contents_sync(&mods_folder_path, &mut |progress| {
+            if let Ok(payload) = serde_json::to_string(&progress) {
+                send_event(&on_event, vec![serde_json::json!(payload)]);
+            }
+        });
+    });
+}
+
+#[tauri::command]
+fn plan_mod_install(
+    mods_folder_path: String,
+    names: String,
+    optional_names: String,
+    on_event: Channel<IpcEvent>,
+) {
+    std::thread::spawn(move || {
+        let names: Vec<String> = serde_json::from_str(&names).unwrap_or_default();
+        let optional_names: Vec<String> = serde_json::from_str(&optional_names).unwrap_or_default();
+        let result: anyhow::Result<_> = try {
+            let mod_data = get_mod_cached_new()?;
+            let graph = resolver::get_dependency_graph()?;
+            resolver::plan_mod_install(
+                &names,
+                &optional_names,
+                &get_installed_mods_sync_with_catalog(
+                    mods_folder_path,
+                    Some(Arc::clone(&mod_data)),
+                ),
+                &mod_data,
+                &graph,
+            )
+        };
+        match result {
+            Ok(plan) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("finished"),
+                    serde_json::json!(serde_json::to_string(&plan).unwrap()),
+                ],
+            ),
+            Err(error) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("failed"),
+                    serde_json::json!(format!("Failed to plan install: {error:#}")),
+                ],
+            ),
+        }
+    });
+}
+
+#[tauri::command]
+fn get_mod_updates(mods_folder_path: String, on_event: Channel<IpcEvent>) {
+    std::thread::spawn(move || {
+        let result = get_mod_cached_new().map(|mod_data| {
+            let mut updates = mod_updates::plan_mod_updates(
+                Path::new(&mods_folder_path),
+                &get_installed_mods_sync_with_catalog(
+                    mods_folder_path.clone(),
+                    Some(Arc::clone(&mod_data)),
+                ),
+                &mod_data,
+            );
+            mod_updates::attach_changelogs(&mut updates);
+            updates
+        });
+        match result {
+            Ok(updates) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("finished"),
+                    serde_json::json!(serde_json::to_string(&updates).unwrap()),
+                ],
+            ),
+            Err(error) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("failed"),
+                    serde_json::json!(format!("Failed to get Mod data: {error:#}")),
+                ],
+            ),
+        }
+    });
+}
+
+/// Emits the usual download snapshots while the queue runs, then one
+/// `[journal, "journal"]` event with the recorded batch.
+#[tauri::command]
+fn update_all_mods(
+    game_path: String,
+    names: String,
+    profile_enabled: bool,
+    current_profile_name: String,
+    always_on_mods: String,
+    multi_thread: bool,
+    on_event: Channel<IpcEvent>,
+) {
+    std::thread::spawn(move || {
+        let names: Vec<String> = serde_json::from_str(&names).unwrap_or_default();
+        let always_on_mods: Vec<String> = serde_json::from_str(&always_on_mods).unwrap_or_default();
+        let game_path = normalize_game_path_impl(&game_path);
+        let mods_dir = Path::new(&game_path).join("Mods");
+        if !is_test_mode() && is_celeste_running(Path::new(&game_path)) {
+            send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("[]"),
+                    serde_json::json!("failed"),
+                    serde_json::json!(
+                        "Celeste is currently running. Exit the game before updating Mods."
+                    ),
+                ],
+            );
+            return;
+        }
+        let installed_before =
+            get_installed_mods_without_catalog_sync(mods_dir.to_string_lossy().into_owned())
+                .into_iter()
+                .map(|item| item.name.to_ascii_lowercase())
+                .collect::<HashSet<_>>();
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        match update_all_mods_sync(
+            Path::new(&game_path),
+            &names,
+            &on_event,
+            multi_thread,
+            &cancel_flag,
+        ) {
+            Ok((failed, tasks, journal)) => {
+                // Dependencies pulled in by newer versions must be enabled, or
+                // the updated Mods fail to load.
+                let installed = get_installed_mods_without_catalog_sync(
+                    mods_dir.to_string_lossy().into_owned(),
+                );
+                let new_names = tasks
+                    .iter()
+                    .filter(|task| task.status == DownloadStatus::Finished)
+                    .filter(|task| is_newly_installed_mod(&task.name, &installed_before))
+                    .map(|task| task.name.clone())
+                    .collect::<Vec<_>>();
+                if let Err(error) = enable_installed_local_mods(
+                    &game_path,
+                    &collect_required_installed_mods(&new_names, &installed),
+                    profile_enabled,
+                    &current_profile_name,
+                    &always_on_mods,
+                ) {
+                    crate::logging::error(format_args!(
+                        "Failed to enable new dependencies after updating Mods: {error:#}"
+                    ));
+                }
+                emit_download_tasks(
+                    &tasks,
+                    &on_event,
+                    if failed { "failed" } else { "finished" },
+                );
+                send_event(
+                    &on_event,
+                    vec![
+                        serde_json::json!(serde_json::to_string(&journal).unwrap()),
+                        serde_json::json!("journal"),
+                    ],
+                );
+            }
+            Err(error) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("[]"),
+                    serde_json::json!("failed"),
+                    serde_json::json!(format!("{error:#}")),
+                ],
+            ),
+        }
+    });
+}
+
+#[tauri::command]
+fn get_mod_update_journals(game_path: String) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    serde_json::to_string(&mod_updates::list_update_journals(Path::new(&game_path)))
+        .unwrap_or_else(|_| "[]".to_string())
+}
+
+#[tauri::command]
+fn rollback_mod_updates(
+    game_path: String,
+    batch_id: String,
+    mod_name: Option<String>,
+) -> Result<String, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    if !is_test_mode() && is_celeste_running(Path::new(&game_path)) {
+        return Err("Celeste is currently running. Exit the game before rolling back.".to_string());
+    }
+    mod_updates::rollback_mod_updates(Path::new(&game_path), &batch_id, mod_name.as_deref())
+        .map(|journal| serde_json::to_string(&journal).unwrap_or_else(|_| "{}".to_string()))
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn watch_game_folder(game_path: String, on_event: Channel<IpcEvent>) -> Result<(), String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    mod_watcher::watch_game_folder(&game_path, on_event).map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn unwatch_game_folder(game_path: String) -> bool {
+    mod_watcher::unwatch_game_folder(&normalize_game_path_impl(&game_path))
+}
+
+/// Streams `log.txt` of the next launches by CeleMod; see `log_tail::subscribe`.
+#[tauri::command]
+fn watch_game_log(game_path: String, on_event: Channel<IpcEvent>) {
+    log_tail::subscribe(&normalize_game_path_impl(&game_path), on_event);
+}
+
+#[tauri::command]
+fn unwatch_game_log(game_path: String) -> bool {
+    log_tail::unsubscribe(&normalize_game_path_impl(&game_path))
+}
+
+#[tauri::command]
+fn get_mod_backups(game_path: String) -> Result<String, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    mod_backups::list_backups(Path::new(&game_path))
+        .map(|entries| serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string()))
+        .map_err(|error| format!("{error:#}"))
 }
 
 #[tauri::command]
-fn get_installed_miaonet(mods_folder_path: String, on_event: Channel<IpcEvent>) {
-    std::thread::spawn(move || {
-        let installed = get_installed_mods_sync(mods_folder_path)
-            .into_iter()
-            .any(|item| item.name == "MiaoNet");
-        send_event(&on_event, vec![serde_json::json!(installed)]);
-    });
+fn restore_mod_backup(game_path: String, backup_id: String) -> Result<String, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    if !is_test_mode() && is_celeste_running(Path::new(&game_path)) {
+        return Err("Celeste is currently running. Exit the game before restoring.".to_string());
+    }
+    mod_backups::restore_backup(Path::new(&game_path), &backup_id)
+        .map(|entry| serde_json::to_string(&entry).unwrap_or_else(|_| "{}".to_string()))
+        .map_err(|error| format!("{error:#}"))
 }
 
 #[tauri::command]
-fn get_installed_mods(mods_folder_path: String, on_event: Channel<IpcEvent>) {
-    std::thread::spawn(move || {
-        let installed = get_installed_mods_sync(mods_folder_path);
-        let payload = serde_json::to_string(&installed).unwrap_or_else(|_| "[]".to_string());
-        send_event(&on_event, vec![serde_json::json!(payload)]);
-    });
+fn purge_mod_backups(game_path: String, backup_ids: Option<Vec<String>>) -> Result<u64, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    mod_backups::purge_backups(Path::new(&game_path), backup_ids.as_deref())
+        .map_err(|error| format!("{error:#}"))
 }
 
 #[tauri::command]
-fn get_invalid_zip_mod_files(mods_folder_path: String, on_event: Channel<IpcEvent>) {
-    std::thread::spawn(move || {
-        let invalid = get_invalid_zip_mod_files_sync(&mods_folder_path);
-        let payload = serde_json::to_string(&invalid).unwrap_or_else(|_| "[]".to_string());
-        send_event(&on_event, vec![serde_json::json!(payload)]);
-    });
+fn configure_mod_backups(game_path: String, max_size_bytes: u64) -> Result<(), String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    mod_backups::set_size_cap(Path::new(&game_path), max_size_bytes)
+        .map_err(|error| format!("{error:#}"))
 }
 
 #[tauri::command]
-fn check_all_mod_contents(mods_folder_path: String, on_event: Channel<IpcEvent>) {
+fn audit_installed_mod_hashes(mods_folder_path: String, on_event: Channel<IpcEvent>) {
     std::thread::spawn(move || {
-        check_all_mod_contents_sync(&mods_folder_path, &mut |progress| {
-            if let Ok(payload) = serde_json::to_string(&progress) {
-                send_event(&on_event, vec![serde_json::json!(payload)]);
-            }
-        });
+        let result = get_mod_cached_new()
+            .map(|mod_data| audit_installed_mod_hashes_sync(&mods_folder_path, &mod_data));
+        match result {
+            Ok(audits) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("finished"),
+                    serde_json::json!(serde_json::to_string(&audits).unwrap()),
+                ],
+            ),
+            Err(error) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("failed"),
+                    serde_json::json!(format!("Failed to get Mod data: {error:#}")),
+                ],
+            ),
+        }
     });
 }
 
@@ -4083,12 +5139,142 @@ fn apply_mod_profiles(game_path: String, profile_names: String, always_on_mods:
         Ok(value) => value,
         Err(error) => return format!("Failed to parse always-on Mods: {error}"),
     };
+    let switched_settings =
+        match profile_settings::switch_profile_settings(Path::new(&game_path), &profile_names) {
+            Ok(switched) => switched,
+            Err(error) => return format!("Failed to apply profile settings: {error:#}"),
+        };
     match blacklist::apply_mod_blacklist_profiles(&game_path, &profile_names, &always_on_mods) {
         Ok(_) => "Success".to_string(),
-        Err(error) => format!("Failed to apply profiles: {error}"),
+        Err(error) => {
+            if switched_settings
+                && let Err(restore_error) =
+                    profile_settings::restore_settings_backup(Path::new(&game_path))
+            {
+                crate::logging::error(format_args!(
+                    "Failed to restore settings after a failed profile switch: {restore_error:#}"
+                ));
+            }
+            format!("Failed to apply profiles: {error}")
+        }
     }
 }
 
+#[tauri::command]
+fn get_profile_settings(
+    game_path: String,
+    profile_name: String,
+) -> Option<profile_settings::ProfileSettings> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_settings::get_profile_settings(Path::new(&game_path), &profile_name)
+}
+
+#[tauri::command]
+fn capture_profile_settings(
+    game_path: String,
+    profile_name: String,
+    legacy_loader: bool,
+    mod_settings: Option<Vec<String>>,
+) -> Result<profile_settings::ProfileSettings, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_settings::capture_profile_settings(
+        Path::new(&game_path),
+        &profile_name,
+        legacy_loader,
+        mod_settings,
+    )
+    .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn clear_profile_settings(game_path: String, profile_name: String) -> Result<(), String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_settings::clear_profile_settings(Path::new(&game_path), &profile_name)
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn get_profile_save_info(
+    game_path: String,
+    profile_name: String,
+) -> Result<profile_saves::ProfileSaveInfo, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_saves::get_profile_save_info(Path::new(&game_path), &profile_name)
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn set_profile_save_isolation(
+    game_path: String,
+    profile_name: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_saves::set_profile_save_isolation(Path::new(&game_path), &profile_name, enabled)
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn copy_profile_saves(
+    game_path: String,
+    from_profile: Option<String>,
+    to_profile: Option<String>,
+) -> Result<profile_saves::SaveBackup, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_saves::copy_profile_saves(
+        Path::new(&game_path),
+        from_profile.as_deref(),
+        to_profile.as_deref(),
+    )
+    .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn backup_profile_saves(
+    game_path: String,
+    profile_name: Option<String>,
+) -> Result<profile_saves::SaveBackup, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_saves::backup_profile_saves(Path::new(&game_path), profile_name.as_deref())
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn list_profile_save_backups(game_path: String) -> Vec<profile_saves::SaveBackup> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_saves::list_save_backups(Path::new(&game_path))
+}
+
+#[tauri::command]
+fn restore_profile_save_backup(
+    game_path: String,
+    backup_id: String,
+) -> Result<profile_saves::SaveBackup, String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_saves::restore_save_backup(Path::new(&game_path), &backup_id)
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn delete_profile_save_backup(game_path: String, backup_id: String) -> Result<(), String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_saves::delete_save_backup(Path::new(&game_path), &backup_id)
+        .map_err(|error| format!("{error:#}"))
+}
+
+#[tauri::command]
+fn get_profile_settings_backup(game_path: String) -> Option<profile_settings::SettingsBackup> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_settings::get_settings_backup(Path::new(&game_path))
+}
+
+#[tauri::command]
+fn restore_profile_settings_backup(game_path: String) -> Result<(), String> {
+    let game_path = normalize_game_path_impl(&game_path);
+    profile_settings::restore_settings_backup(Path::new(&game_path))
+        .map_err(|error| format!("{error:#}"))
+}
+
 #[tauri::command]
 fn get_active_profile_mods(game_path: String, always_on_mods: String) -> String {
     let game_path = normalize_game_path_impl(&game_path);
@@ -4150,6 +5336,29 @@ fn preview_olympus_profiles(game_path: String, profile_names: String) -> String
     }
 }
 
+#[tauri::command]
+fn export_olympus_presets(game_path: String, profile_names: String, destination: String) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    let profile_names: Vec<String> = match serde_json::from_str(&profile_names) {
+        Ok(value) => value,
+        Err(error) => return format!("Failed to parse profile names: {error}"),
+    };
+    let destination = Some(destination.as_str()).filter(|path| !path.is_empty());
+    match blacklist::export_olympus_presets(&game_path, &profile_names, destination) {
+        Ok(result) => serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string()),
+        Err(error) => format!("Failed to export Olympus presets: {error}"),
+    }
+}
+
+#[tauri::command]
+fn sync_olympus_presets(game_path: String) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    match blacklist::sync_olympus_presets(&game_path) {
+        Ok(result) => serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string()),
+        Err(error) => format!("Failed to sync Olympus presets: {error}"),
+    }
+}
+
 #[tauri::command]
 fn preview_mod_profiles_json(game_path: String, contents: String) -> String {
     let game_path = normalize_game_path_impl(&game_path);
@@ -4169,18 +5378,34 @@ fn preview_mod_profiles(game_path: String, source_path: String) -> String {
 }
 
 #[tauri::command]
-fn commit_mod_profiles(game_path: String, profiles: String) -> String {
+fn commit_mod_profiles(game_path: String, profiles: String, merge: Option<bool>) -> String {
     let game_path = normalize_game_path_impl(&game_path);
     let profiles = match serde_json::from_str::<Vec<blacklist::ModBlacklistProfile>>(&profiles) {
         Ok(value) => value,
         Err(error) => return format!("Failed to parse profiles: {error}"),
     };
-    match blacklist::commit_profile_import(&game_path, profiles) {
+    match blacklist::commit_profile_import(&game_path, profiles, merge.unwrap_or(false)) {
         Ok(result) => serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string()),
         Err(error) => format!("Failed to import profiles: {error}"),
     }
 }
 
+#[tauri::command]
+fn diff_mod_profiles(game_path: String, left: String, right: String) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    let parse = |side: &str| serde_json::from_str::<profile_diff::DiffSide>(side);
+    let (left, right) = match (parse(&left), parse(&right)) {
+        (Ok(left), Ok(right)) => (left, right),
+        (Err(error), _) | (_, Err(error)) => {
+            return format!("Failed to parse profile diff sides: {error}");
+        }
+    };
+    match profile_diff::diff_profiles(&game_path, left, right) {
+        Ok(result) => serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string()),
+        Err(error) => format!("Failed to diff profiles: {error}"),
+    }
+}
+
 #[tauri::command]
 fn export_mod_profile(
     game_path: String,
@@ -4209,6 +5434,87 @@ fn export_mod_profile(
     }
 }
 
+#[tauri::command]
+fn export_mod_lockfile(game_path: String, profile_name: String, destination: String) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    match lockfile::export_mod_lockfile(&game_path, &profile_name, &destination) {
+        Ok(_) => "Success".to_string(),
+        Err(error) => format!("Failed to export lockfile: {error:#}"),
+    }
+}
+
+#[tauri::command]
+fn preview_mod_lockfile_sync(
+    game_path: String,
+    source_path: String,
+    always_on_mods: String,
+) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    let always_on_mods: Vec<String> = serde_json::from_str(&always_on_mods).unwrap_or_default();
+    match lockfile::read_lockfile(&source_path) {
+        Ok(lockfile) => serde_json::to_string(&lockfile::plan_lockfile_sync(
+            &game_path,
+            &lockfile,
+            &always_on_mods,
+        ))
+        .unwrap_or_else(|_| "{}".to_string()),
+        Err(error) => format!("Failed to preview lockfile: {error:#}"),
+    }
+}
+
+#[tauri::command]
+fn sync_mod_lockfile(
+    game_path: String,
+    source_path: String,
+    always_on_mods: String,
+    on_event: Channel<IpcEvent>,
+) {
+    std::thread::spawn(move || {
+        let game_path = normalize_game_path_impl(&game_path);
+        let always_on_mods: Vec<String> = serde_json::from_str(&always_on_mods).unwrap_or_default();
+        let result = if !is_test_mode() && is_celeste_running(Path::new(&game_path)) {
+            Err(anyhow::anyhow!(
+                "Celeste is currently running. Exit the game before syncing Mods."
+            ))
+        } else {
+            lockfile::read_lockfile(&source_path).and_then(|lockfile| {
+                lockfile::sync_mod_lockfile(
+                    &game_path,
+                    &lockfile,
+                    &always_on_mods,
+                    &mut |name, progress| {
+                        send_event(
+                            &on_event,
+                            vec![
+                                serde_json::json!("progress"),
+                                serde_json::json!(name),
+                                serde_json::json!(progress),
+                            ],
+                        );
+                    },
+                    &Arc::new(AtomicBool::new(false)),
+                )
+            })
+        };
+        match result {
+            Ok(plan) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("finished"),
+                    serde_json::json!(serde_json::to_string(&plan).unwrap()),
+                ],
+            ),
+            Err(error) => send_event(
+                &on_event,
+                vec![
+                    serde_json::json!("failed"),
+                    serde_json::json!(format!("{error:#}")),
+                ],
+            ),
+        }
+    });
+}
+
 #[tauri::command]
 fn expand_mod_profile_dependencies(game_path: String, profile_name: String) -> String {
     let game_path = normalize_game_path_impl(&game_path);
@@ -4252,6 +5558,49 @@ fn remove_mod_blacklist_profile(game_path: String, profile_name: String) -> Stri
     }
 }
 
+#[tauri::command]
+fn resolve_mod_profile(game_path: String, profile_name: String) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    match blacklist::resolve_mod_profile(&game_path, &profile_name) {
+        Ok(result) => serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string()),
+        Err(error) => format!("Failed to resolve profile: {error}"),
+    }
+}
+
+#[tauri::command]
+fn preview_mod_profile(game_path: String, profile: String) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    let profile = match serde_json::from_str::<blacklist::ModBlacklistProfile>(&profile) {
+        Ok(value) => value,
+        Err(error) => return format!("Failed to parse profile: {error}"),
+    };
+    match blacklist::preview_mod_profile(&game_path, profile) {
+        Ok(result) => serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string()),
+        Err(error) => format!("Failed to preview profile: {error}"),
+    }
+}
+
+#[tauri::command]
+fn set_mod_profile_composition(
+    game_path: String,
+    profile_name: String,
+    parents: Vec<String>,
+    excluded_mods: Vec<String>,
+    include_categories: Vec<String>,
+) -> String {
+    let game_path = normalize_game_path_impl(&game_path);
+    match blacklist::set_mod_profile_composition(
+        &game_path,
+        &profile_name,
+        parents,
+        excluded_mods,
+        include_categories,
+    ) {
+        Ok(()) => "Success".to_string(),
+        Err(error) => format!("Failed to update profile composition: {error}"),
+    }
+}
+
 #[tauri::command]
 fn get_mod_update(name: String, on_event: Channel<IpcEvent>) {
     std::thread::spawn(move || {
@@ -4697,17 +6046,7 @@ fn download_mod(
             }
         };
         let installed = get_installed_mods_sync(mods_dir.clone());
-        let destination_file = installed
-            .iter()
-            .find(|item| {
-                item.name == name
-                    && Path::new(&mods_dir).join(&item.file).is_file()
-                    && Path::new(&item.file)
-                        .extension()
-                        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
-            })
-            .map(|item| item.file.clone())
-            .unwrap_or_else(|| format!("{}.zip", make_path_compatible_name(&name)));
+        let destination_file = download_file_name(&installed, &mods_dir, &name);
         let previous_files = installed
             .iter()
             .filter(|item| item.name == name && item.file != destination_file)
@@ -4726,14 +6065,33 @@ fn download_mod(
             total_bytes: 0,
             speed_bytes_per_sec: 0.0,
         }];
+        // Without the graph, dependencies are still planned from each
+        // downloaded everest.yaml.
+        let graph = resolver::get_dependency_graph().unwrap_or_default();
+        match plan_dependency_downloads(
+            std::slice::from_ref(&name),
+            &installed,
+            &mod_data,
+            &graph,
+            &mods_dir,
+        ) {
+            Ok(dependencies) => tasks.extend(dependencies),
+            Err(error) => {
+                tasks[0].status = DownloadStatus::Failed;
+                tasks[0].data = format!("{error:#}");
+                emit_download_tasks(&tasks, &on_event, "failed");
+                DOWNLOAD_CANCEL_FLAGS.lock().unwrap().remove(&name);
+                return;
+            }
+        }
         let installed_before = installed
             .iter()
             .map(|item| item.name.to_ascii_lowercase())
             .collect::<HashSet<_>>();
         let failed = download_mod_queue(
             &mut tasks,
-            &installed,
             &mod_data,
+            &graph,
             &mods_dir,
             &on_event,
             multi_thread,
@@ -4999,13 +6357,17 @@ mod keyboard_input_tests {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    #[cfg(all(windows, not(debug_assertions)))]
-    initialize_windows_console();
-
     let args = std::env::args().collect::<Vec<_>>();
     if args.iter().any(|arg| arg == "--test-mode") {
         TEST_MODE.store(true, Ordering::Relaxed);
     }
+    if cli::is_cli_invocation(&args) {
+        std::process::exit(cli::run(&args[1..]));
+    }
+
+    #[cfg(all(windows, not(debug_assertions)))]
+    initialize_windows_console();
+
     if args.len() == 3 && args[1] == "/update" {
         std::thread::sleep(std::time::Duration::from_millis(500));
         let replacement = &args[2];
@@ -5103,6 +6465,20 @@ pub fn run() {
             get_installed_mods,
             get_invalid_zip_mod_files,
             check_all_mod_contents,
+            audit_installed_mod_hashes,
+            plan_mod_install,
+            get_mod_updates,
+            update_all_mods,
+            get_mod_update_journals,
+            rollback_mod_updates,
+            get_mod_backups,
+            watch_game_folder,
+            unwatch_game_folder,
+            watch_game_log,
+            unwatch_game_log,
+            restore_mod_backup,
+            purge_mod_backups,
+            configure_mod_backups,
             get_installed_miaonet,
             start_game,
             runtime_platform,
@@ -5117,19 +6493,40 @@ pub fn run() {
             update_blacklist_mod_file,
             apply_mod_profiles,
             get_active_profile_mods,
+            get_profile_settings,
+            capture_profile_settings,
+            clear_profile_settings,
+            get_profile_settings_backup,
+            restore_profile_settings_backup,
+            get_profile_save_info,
+            set_profile_save_isolation,
+            copy_profile_saves,
+            backup_profile_saves,
+            list_profile_save_backups,
+            restore_profile_save_backup,
+            delete_profile_save_backup,
             switch_mod_profile_mods,
             get_current_profiles,
             get_olympus_presets,
             write_text_file,
             preview_olympus_profiles,
+            export_olympus_presets,
+            sync_olympus_presets,
             preview_mod_profiles,
             preview_mod_profiles_json,
             commit_mod_profiles,
+            diff_mod_profiles,
             export_mod_profile,
+            export_mod_lockfile,
+            preview_mod_lockfile_sync,
+            sync_mod_lockfile,
             new_mod_blacklist_profile,
             rename_mod_blacklist_profile,
             get_current_profile,
             remove_mod_blacklist_profile,
+            resolve_mod_profile,
+            preview_mod_profile,
+            set_mod_profile_composition,
             get_mod_update,
             rm_mod,
             expand_mod_profile_dependencies,
@@ -5150,6 +6547,17 @@ pub fn run() {
             stop_game_for_restart,
             restart_game_with_loader,
             reveal_crash_report,
+            get_crash_rules_info,
+            refresh_crash_rules,
+            list_everest_crashes,
+            analyze_everest_crash,
+            get_crash_history,
+            export_crash_bundle,
+            import_crash_bundle,
+            start_crash_bisect,
+            run_crash_bisect_step,
+            get_crash_bisect_status,
+            finish_crash_bisect,
             verify_celeste_install,
             normalize_game_path,
             get_mod_latest_info,
@@ -5157,6 +6565,8 @@ pub fn run() {
             write_frontend_log,
             is_using_cache,
             configure_mod_cache,
+            configure_mod_catalog_sources,
+            get_mod_catalog_sources,
             get_mod_catalog,
             get_mod_cache_status,
             get_database_path,
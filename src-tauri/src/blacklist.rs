@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
     time::{SystemTime, UNIX_EPOCH},
@@ -8,19 +8,43 @@ use std::{
 use anyhow::{Context, bail};
 use serde::{Deserialize, Serialize};
 
-use super::get_installed_mods_sync;
+use super::{everest, get_installed_mods_sync, profile_saves, profile_settings};
 
 const PROFILE_DIRECTORY: &str = "celemod_blacklist_profiles";
+const IMPORT_BASE_DIRECTORY: &str = ".import-base";
 const PROFILE_FORMAT: &str = "celemod-profile";
-const PROFILE_VERSION: u8 = 2;
+const PROFILE_VERSION: u8 = 3;
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct ModBlacklistProfile {
     pub name: String,
     #[serde(default)]
     pub enabled_mods: Vec<String>,
     #[serde(default, skip_serializing_if = "is_false")]
     pub auto_deps: bool,
+    /// Profiles whose resolved Mods this profile starts from, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<String>,
+    /// Removed from what the parents and categories bring in. Mods listed in
+    /// `enabled_mods` stay enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_mods: Vec<String>,
+    /// Catalog categories whose installed Mods are all enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_categories: Vec<String>,
+}
+
+/// A profile after inheritance, as `apply_mod_blacklist_profiles` enables it.
+#[derive(Debug, Serialize)]
+pub struct ResolvedProfile {
+    pub name: String,
+    /// Every profile involved, each after its own parents.
+    pub chain: Vec<String>,
+    pub enabled_mods: Vec<String>,
+    /// Inherited or category Mods that an exclusion along the chain removed.
+    pub excluded_mods: Vec<String>,
+    /// The profile that lists each enabled Mod, or `category:<name>`.
+    pub sources: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -28,6 +52,22 @@ pub struct ProfileImportResult {
     pub profiles: Vec<ModBlacklistProfile>,
     pub missing_mods: Vec<String>,
     pub missing_files: Vec<String>,
+    /// Imported profiles that would replace a different stored profile.
+    pub collisions: Vec<ImportCollision>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportCollision {
+    pub name: String,
+    /// The previous import of this profile is known, so the merge is three-way.
+    pub has_base: bool,
+    /// What `commit_profile_import` writes when asked to merge.
+    pub merged: ModBlacklistProfile,
+    /// `parents` when both sides changed it and the stored value is kept, or
+    /// `field: Mod` for a Mod the merge could not place with certainty: kept
+    /// by one side without a base to tell whether the other removed it, or
+    /// both enabled and excluded after the merge.
+    pub conflicts: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -44,7 +84,11 @@ fn is_false(value: &bool) -> bool {
     !*value
 }
 
-fn validate_profile_name(profile_name: &str) -> anyhow::Result<()> {
+/// Bookkeeping names in the per-profile settings and save stores, where each
+/// profile gets a directory named after it.
+const RESERVED_PROFILE_NAMES: [&str; 3] = [".backup", ".backups", "active.json"];
+
+pub(super) fn validate_profile_name(profile_name: &str) -> anyhow::Result<()> {
     let invalid_windows_chars = ['<', '>', ':', '"', '|', '?', '*'];
     if profile_name.is_empty()
         || profile_name.len() > 128
@@ -60,10 +104,17 @@ fn validate_profile_name(profile_name: &str) -> anyhow::Result<()> {
     {
         bail!("Invalid profile name");
     }
+    let lowercase = profile_name.to_ascii_lowercase();
+    // Staging directories are `.<name>.tmp` next to the profile directories.
+    if RESERVED_PROFILE_NAMES.contains(&lowercase.as_str())
+        || (lowercase.starts_with('.') && lowercase.ends_with(".tmp"))
+    {
+        bail!("The profile name {profile_name} is reserved");
+    }
     Ok(())
 }
 
-fn profiles_directory(game_path: &str) -> PathBuf {
+pub(super) fn profiles_directory(game_path: &str) -> PathBuf {
     Path::new(game_path).join(PROFILE_DIRECTORY)
 }
 
@@ -72,6 +123,20 @@ fn profile_path(game_path: &str, profile_name: &str) -> anyhow::Result<PathBuf>
     Ok(profiles_directory(game_path).join(format!("{profile_name}.json")))
 }
 
+/// The profile as it was last imported, the base of the next merge.
+fn import_base_path(game_path: &str, profile_name: &str) -> anyhow::Result<PathBuf> {
+    validate_profile_name(profile_name)?;
+    Ok(profiles_directory(game_path)
+        .join(IMPORT_BASE_DIRECTORY)
+        .join(format!("{profile_name}.json")))
+}
+
+fn read_import_base(game_path: &str, profile_name: &str) -> Option<ModBlacklistProfile> {
+    fs::read(import_base_path(game_path, profile_name).ok()?)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+}
+
 fn normalize_names(names: impl IntoIterator<Item = String>) -> Vec<String> {
     let mut names = names
         .into_iter()
@@ -83,6 +148,17 @@ fn normalize_names(names: impl IntoIterator<Item = String>) -> Vec<String> {
     names
 }
 
+/// Like `normalize_names`, but keeps the declared order, which decides
+/// precedence between parents.
+fn normalize_ordered_names(names: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    names
+        .into_iter()
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty() && seen.insert(name.to_ascii_lowercase()))
+        .collect()
+}
+
 fn installed_mod_names(game_path: &str) -> Vec<String> {
     normalize_names(
         get_installed_mods_sync(format!("{game_path}/Mods"))
@@ -91,7 +167,7 @@ fn installed_mod_names(game_path: &str) -> Vec<String> {
     )
 }
 
-fn direct_blacklisted_files(game_path: &str) -> HashSet<String> {
+pub(super) fn direct_blacklisted_files(game_path: &str) -> HashSet<String> {
     fs::read_to_string(Path::new(game_path).join("Mods").join("blacklist.txt"))
         .unwrap_or_default()
         .lines()
@@ -118,6 +194,23 @@ fn profile_from_legacy_value(
         .and_then(serde_json::Value::as_bool)
         .unwrap_or(false);
 
+    let string_list = |key: &str| {
+        object
+            .get(key)
+            .and_then(serde_json::Value::as_array)
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+    let parents = normalize_ordered_names(string_list("parents"));
+    let excluded_mods = normalize_names(string_list("excluded_mods"));
+    let include_categories = normalize_names(string_list("include_categories"));
+
     if let Some(enabled_mods) = object
         .get("enabled_mods")
         .or_else(|| object.get("enabledMods"))
@@ -132,6 +225,9 @@ fn profile_from_legacy_value(
                     .map(str::to_owned),
             ),
             auto_deps,
+            parents,
+            excluded_mods,
+            include_categories,
         });
     }
 
@@ -170,6 +266,9 @@ fn profile_from_legacy_value(
                 .map(|mod_info| mod_info.name.clone()),
         ),
         auto_deps,
+        parents,
+        excluded_mods,
+        include_categories,
     })
 }
 
@@ -188,6 +287,9 @@ fn serialize_profile(profile: &ModBlacklistProfile) -> anyhow::Result<String> {
         name: profile.name.clone(),
         enabled_mods: normalize_names(profile.enabled_mods.clone()),
         auto_deps: false,
+        parents: normalize_ordered_names(profile.parents.clone()),
+        excluded_mods: normalize_names(profile.excluded_mods.clone()),
+        include_categories: normalize_names(profile.include_categories.clone()),
     };
     Ok(serde_json::to_string_pretty(&ExportedProfile {
         format: PROFILE_FORMAT,
@@ -219,11 +321,11 @@ fn imported_profile_from_mod_list(
     Ok(ModBlacklistProfile {
         name: default_name.to_owned(),
         enabled_mods,
-        auto_deps: false,
+        ..ModBlacklistProfile::default()
     })
 }
 
-/// Loads profile files and rewrites every pre-v2 JSON profile in the v2 format.
+/// Loads profile files and rewrites every older JSON profile in the current format.
 /// This makes migration happen during the first profile load at startup.
 pub fn get_mod_blacklist_profiles(game_path: &str) -> Vec<ModBlacklistProfile> {
     let directory = profiles_directory(game_path);
@@ -256,10 +358,11 @@ pub fn get_mod_blacklist_profiles(game_path: &str) -> Vec<ModBlacklistProfile> {
         if !names.insert(profile.name.to_ascii_lowercase()) {
             continue;
         }
-        let is_v2 = value.get("format").and_then(serde_json::Value::as_str) == Some(PROFILE_FORMAT)
+        let is_current = value.get("format").and_then(serde_json::Value::as_str)
+            == Some(PROFILE_FORMAT)
             && value.get("version").and_then(serde_json::Value::as_u64)
                 == Some(PROFILE_VERSION.into());
-        if !is_v2 {
+        if !is_current {
             let _ = write_profile(game_path, &profile);
             if path != profile_path(game_path, &profile.name).unwrap_or_default() {
                 let _ = fs::remove_file(path);
@@ -271,7 +374,7 @@ pub fn get_mod_blacklist_profiles(game_path: &str) -> Vec<ModBlacklistProfile> {
     profiles.sort_unstable_by_key(|profile| profile.name.to_ascii_lowercase());
     profiles
 }
-fn expand_installed_dependencies(
+pub(super) fn expand_installed_dependencies(
     installed: &[super::LocalMod],
     selected_names: &[String],
 ) -> Vec<String> {
@@ -309,6 +412,214 @@ fn expand_installed_dependencies(
     expanded
 }
 
+#[derive(Clone, Default)]
+struct Resolution {
+    /// Lowercased name to the stored name and its source.
+    mods: BTreeMap<String, (String, String)>,
+    excluded: BTreeSet<String>,
+}
+
+struct ProfileResolver<'a> {
+    profiles: HashMap<String, &'a ModBlacklistProfile>,
+    installed: &'a [super::LocalMod],
+    /// Lowercased Mod name to catalog category.
+    categories: &'a HashMap<String, String>,
+    resolved: HashMap<String, Resolution>,
+    stack: Vec<String>,
+    chain: Vec<String>,
+}
+
+impl<'a> ProfileResolver<'a> {
+    fn new(
+        profiles: &'a [ModBlacklistProfile],
+        installed: &'a [super::LocalMod],
+        categories: &'a HashMap<String, String>,
+    ) -> Self {
+        Self {
+            profiles: profiles
+                .iter()
+                .map(|profile| (profile.name.to_ascii_lowercase(), profile))
+                .collect(),
+            installed,
+            categories,
+            resolved: HashMap::new(),
+            stack: Vec::new(),
+            chain: Vec::new(),
+        }
+    }
+
+    /// Parents in order, the first one that brings a Mod naming its source,
+    /// then categories and exclusions, then the profile's own Mods.
+    fn resolve(&mut self, name: &str) -> anyhow::Result<Resolution> {
+        let key = name.to_ascii_lowercase();
+        let profile = *self
+            .profiles
+            .get(&key)
+            .with_context(|| match self.stack.last() {
+                Some(child) => format!("Parent profile {name} of {child} was not found"),
+                None => format!("Profile {name} was not found"),
+            })?;
+        if let Some(position) = self
+            .stack
+            .iter()
+            .position(|item| item.eq_ignore_ascii_case(name))
+        {
+            let mut cycle = self.stack[position..].to_vec();
+            cycle.push(profile.name.clone());
+            bail!("Profile inheritance cycle: {}", cycle.join(" → "));
+        }
+        if let Some(resolution) = self.resolved.get(&key) {
+            return Ok(resolution.clone());
+        }
+
+        self.stack.push(profile.name.clone());
+        let mut resolution = Resolution::default();
+        for parent in &profile.parents {
+            let inherited = self.resolve(parent)?;
+            for (key, value) in inherited.mods {
+                resolution.mods.entry(key).or_insert(value);
+            }
+            resolution.excluded.extend(inherited.excluded);
+        }
+        for category in &profile.include_categories {
+            for mod_info in self.installed {
+                if self
+                    .categories
+                    .get(&mod_info.name.to_ascii_lowercase())
+                    .is_some_and(|value| value.eq_ignore_ascii_case(category))
+                {
+                    resolution
+                        .mods
+                        .entry(mod_info.name.to_ascii_lowercase())
+                        .or_insert_with(|| (mod_info.name.clone(), format!("category:{category}")));
+                }
+            }
+        }
+        for excluded in &profile.excluded_mods {
+            if let Some((name, _)) = resolution.mods.remove(&excluded.to_ascii_lowercase()) {
+                resolution.excluded.insert(name);
+            }
+        }
+        for name in &profile.enabled_mods {
+            resolution.mods.insert(
+                name.to_ascii_lowercase(),
+                (name.clone(), profile.name.clone()),
+            );
+        }
+        self.stack.pop();
+
+        if !self.chain.iter().any(|item| item == &profile.name) {
+            self.chain.push(profile.name.clone());
+        }
+        self.resolved.insert(key, resolution.clone());
+        Ok(resolution)
+    }
+
+    fn resolve_profile(&mut self, name: &str) -> anyhow::Result<ResolvedProfile> {
+        self.chain.clear();
+        let resolution = self.resolve(name)?;
+        let name = self.profiles[&name.to_ascii_lowercase()].name.clone();
+        Ok(ResolvedProfile {
+            name,
+            chain: self.chain.clone(),
+            enabled_mods: resolution
+                .mods
+                .values()
+                .map(|(name, _)| name.clone())
+                .collect(),
+            excluded_mods: normalize_names(resolution.excluded),
+            sources: resolution.mods.into_values().collect(),
+        })
+    }
+}
+
+/// Loads the catalog categories once per resolution, keyed by lowercased
+/// Mod name. Nothing is loaded unless a profile includes a category.
+fn catalog_categories(profiles: &[ModBlacklistProfile]) -> HashMap<String, String> {
+    if profiles
+        .iter()
+        .all(|profile| profile.include_categories.is_empty())
+    {
+        return HashMap::new();
+    }
+    everest::get_mod_categories()
+        .map(|categories| {
+            categories
+                .iter()
+                .map(|(name, category)| (name.to_ascii_lowercase(), category.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn resolve_profiles(
+    profiles: &[ModBlacklistProfile],
+    installed: &[super::LocalMod],
+    names: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let categories = catalog_categories(profiles);
+    let mut resolver = ProfileResolver::new(profiles, installed, &categories);
+    let mut enabled = Vec::new();
+    for name in names {
+        enabled.extend(resolver.resolve_profile(name)?.enabled_mods);
+    }
+    Ok(normalize_names(enabled))
+}
+
+/// Checks that parents exist and do not form a cycle, without the catalog.
+fn validate_composition(profiles: &[ModBlacklistProfile]) -> anyhow::Result<()> {
+    let no_categories = HashMap::new();
+    let mut resolver = ProfileResolver::new(profiles, &[], &no_categories);
+    for profile in profiles {
+        resolver.resolve(&profile.name)?;
+    }
+    Ok(())
+}
+
+pub fn resolve_mod_profile(game_path: &str, profile_name: &str) -> anyhow::Result<ResolvedProfile> {
+    let profiles = get_mod_blacklist_profiles(game_path);
+    let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
+    let categories = catalog_categories(&profiles);
+    ProfileResolver::new(&profiles, &installed, &categories).resolve_profile(profile_name)
+}
+
+/// Resolves an unsaved edit of a profile against the stored ones.
+pub fn preview_mod_profile(
+    game_path: &str,
+    profile: ModBlacklistProfile,
+) -> anyhow::Result<ResolvedProfile> {
+    validate_profile_name(&profile.name)?;
+    let mut profiles = get_mod_blacklist_profiles(game_path);
+    profiles.retain(|stored| !stored.name.eq_ignore_ascii_case(&profile.name));
+    let name = profile.name.clone();
+    profiles.push(profile);
+    let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
+    let categories = catalog_categories(&profiles);
+    ProfileResolver::new(&profiles, &installed, &categories).resolve_profile(&name)
+}
+
+/// Replaces how a profile is composed. Nothing is written when the new
+/// parents are missing or would form a cycle.
+pub fn set_mod_profile_composition(
+    game_path: &str,
+    profile_name: &str,
+    parents: Vec<String>,
+    excluded_mods: Vec<String>,
+    include_categories: Vec<String>,
+) -> anyhow::Result<()> {
+    let mut profiles = get_mod_blacklist_profiles(game_path);
+    let profile = profiles
+        .iter_mut()
+        .find(|profile| profile.name.eq_ignore_ascii_case(profile_name))
+        .context("Profile not found")?;
+    profile.parents = normalize_ordered_names(parents);
+    profile.excluded_mods = normalize_names(excluded_mods);
+    profile.include_categories = normalize_names(include_categories);
+    let profile = profile.clone();
+    validate_composition(&profiles)?;
+    write_profile(game_path, &profile)
+}
+
 pub fn get_blacklist_profile_count(game_path: &str) -> usize {
     let directory = profiles_directory(game_path);
     let Ok(entries) = fs::read_dir(directory) else {
@@ -367,13 +678,12 @@ pub fn apply_mod_blacklist_profiles(
     }
 
     let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
-    let profile_enabled = profiles
-        .iter()
-        .filter(|profile| requested.contains(&profile.name.to_ascii_lowercase()))
-        .flat_map(|profile| profile.enabled_mods.iter().cloned());
+    let profile_enabled = resolve_profiles(&profiles, &installed, &requested_names)?;
     let enabled_names = resolve_selected_names(
         &installed,
-        profile_enabled.chain(always_on_mods.iter().cloned()),
+        profile_enabled
+            .into_iter()
+            .chain(always_on_mods.iter().cloned()),
     );
     let enabled_files = installed
         .iter()
@@ -438,14 +748,16 @@ pub fn get_current_profile(game_path: &str) -> anyhow::Result<String> {
 pub fn get_active_profile_mods(game_path: &str, always_on_mods: &[String]) -> Vec<String> {
     let profiles = get_current_profiles(game_path);
     let all_profiles = get_mod_blacklist_profiles(game_path);
-    let selected = all_profiles
-        .iter()
-        .filter(|profile| profiles.contains(&profile.name))
-        .flat_map(|profile| profile.enabled_mods.iter().cloned())
-        .chain(always_on_mods.iter().cloned());
+    let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
+    let selected = resolve_profiles(&all_profiles, &installed, &profiles).unwrap_or_else(|error| {
+        crate::logging::warn(format_args!(
+            "Failed to resolve the active profiles: {error:#}"
+        ));
+        Vec::new()
+    });
     resolve_selected_names(
-        &get_installed_mods_sync(format!("{game_path}/Mods")),
-        selected,
+        &installed,
+        selected.into_iter().chain(always_on_mods.iter().cloned()),
     )
     .into_iter()
     .collect()
@@ -461,7 +773,7 @@ pub fn get_direct_blacklist_profile(game_path: &str) -> anyhow::Result<ModBlackl
                 .filter(|mod_info| !blacklisted.contains(&mod_info.file.to_ascii_lowercase()))
                 .map(|mod_info| mod_info.name),
         ),
-        auto_deps: false,
+        ..ModBlacklistProfile::default()
     })
 }
 
@@ -515,16 +827,49 @@ pub fn switch_mod_profile_mods(
     let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
     let package_names = resolve_selected_names(&installed, mod_names.iter().cloned());
     if enabled {
+        profile
+            .excluded_mods
+            .retain(|name| !package_names.contains(name));
         profile.enabled_mods =
             normalize_names(profile.enabled_mods.into_iter().chain(package_names));
     } else {
         profile
             .enabled_mods
             .retain(|name| !package_names.contains(name));
+        // Otherwise a parent or category would keep the Mod enabled.
+        if !profile.parents.is_empty() || !profile.include_categories.is_empty() {
+            profile.excluded_mods =
+                normalize_names(profile.excluded_mods.into_iter().chain(package_names));
+        }
     }
     write_profile(game_path, &profile)
 }
 
+/// Replaces the enabled Mods of a profile, creating it when it does not exist.
+/// Returns the stored profile name, which keeps the casing of an existing one.
+pub fn set_mod_profile_mods(
+    game_path: &str,
+    profile_name: &str,
+    enabled_mods: Vec<String>,
+) -> anyhow::Result<String> {
+    let profile = get_mod_blacklist_profiles(game_path)
+        .into_iter()
+        .find(|profile| profile.name.eq_ignore_ascii_case(profile_name))
+        .unwrap_or_else(|| ModBlacklistProfile {
+            name: profile_name.to_owned(),
+            ..ModBlacklistProfile::default()
+        });
+    write_profile(
+        game_path,
+        &ModBlacklistProfile {
+            enabled_mods,
+            auto_deps: false,
+            ..profile.clone()
+        },
+    )?;
+    Ok(profile.name)
+}
+
 pub fn expand_mod_profile_dependencies(game_path: &str, profile_name: &str) -> anyhow::Result<()> {
     let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
     let mut profile = get_mod_blacklist_profiles(game_path)
@@ -548,8 +893,7 @@ pub fn new_mod_blacklist_profile(game_path: &str, profile_name: &str) -> anyhow:
         game_path,
         &ModBlacklistProfile {
             name: profile_name.to_owned(),
-            enabled_mods: Vec::new(),
-            auto_deps: false,
+            ..ModBlacklistProfile::default()
         },
     )
 }
@@ -613,8 +957,8 @@ pub fn rename_mod_blacklist_profile(
     let destination_path = profile_path(game_path, new_name)?;
     let renamed = ModBlacklistProfile {
         name: new_name.to_string(),
-        enabled_mods: source.enabled_mods.clone(),
         auto_deps: false,
+        ..source.clone()
     };
     let renamed_contents = serialize_profile(&renamed)?;
     if source.name.eq_ignore_ascii_case(new_name) {
@@ -643,12 +987,52 @@ pub fn rename_mod_blacklist_profile(
             return Err(error.into());
         }
     }
+    for child in &profiles {
+        if child.name != source.name
+            && child
+                .parents
+                .iter()
+                .any(|parent| parent.eq_ignore_ascii_case(&source.name))
+        {
+            let mut child = child.clone();
+            for parent in &mut child.parents {
+                if parent.eq_ignore_ascii_case(&source.name) {
+                    *parent = new_name.to_string();
+                }
+            }
+            write_profile(game_path, &child)?;
+        }
+    }
+    profile_settings::rename_profile(Path::new(game_path), &source.name, new_name)?;
+    profile_saves::rename_profile(Path::new(game_path), &source.name, new_name)?;
+    let base = import_base_path(game_path, &source.name)?;
+    if base.is_file() {
+        fs::rename(base, import_base_path(game_path, new_name)?)?;
+    }
     rename_active_profile_header(game_path, &source.name, new_name)
 }
 
 pub fn remove_mod_blacklist_profile(game_path: &str, profile_name: &str) -> anyhow::Result<()> {
+    let children = get_mod_blacklist_profiles(game_path)
+        .into_iter()
+        .filter(|profile| {
+            profile
+                .parents
+                .iter()
+                .any(|parent| parent.eq_ignore_ascii_case(profile_name))
+        })
+        .map(|profile| profile.name)
+        .collect::<Vec<_>>();
+    if !children.is_empty() {
+        bail!(
+            "Profile {profile_name} is inherited by {}",
+            children.join(", ")
+        );
+    }
     fs::remove_file(profile_path(game_path, profile_name)?)?;
-    Ok(())
+    profile_saves::remove_profile(Path::new(game_path), profile_name)?;
+    fs::remove_file(import_base_path(game_path, profile_name)?).ok();
+    profile_settings::clear_profile_settings(Path::new(game_path), profile_name)
 }
 
 /// Replacing an archive never changes v2 profile files because they store only
@@ -727,7 +1111,7 @@ fn parse_olympus_presets(
         profiles.push(ModBlacklistProfile {
             name,
             enabled_mods: normalize_names(enabled_mods),
-            auto_deps: false,
+            ..ModBlacklistProfile::default()
         });
         Ok(())
     };
@@ -791,6 +1175,127 @@ pub fn preview_olympus_profiles(
     preview_profile_import(game_path, profiles, missing_files)
 }
 
+/// The reverse of `parse_olympus_presets`: the Mods folder entries that hold
+/// `enabled_mods`, and the names no installed file provides. A file with
+/// several everest.yaml entries is listed once.
+fn olympus_preset_files(
+    installed: &[super::LocalMod],
+    enabled_mods: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let enabled = enabled_mods
+        .iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    let mut files = installed
+        .iter()
+        .filter(|mod_info| enabled.contains(&mod_info.name.to_ascii_lowercase()))
+        .filter(|mod_info| seen.insert(mod_info.file.to_ascii_lowercase()))
+        .map(|mod_info| mod_info.file.clone())
+        .collect::<Vec<_>>();
+    files.sort_unstable_by_key(|file| file.to_ascii_lowercase());
+    let provided = installed
+        .iter()
+        .map(|mod_info| mod_info.name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    let missing = enabled_mods
+        .iter()
+        .filter(|name| !provided.contains(&name.to_ascii_lowercase()))
+        .cloned()
+        .collect();
+    (files, missing)
+}
+
+/// Replaces the presets named in `presets` and appends new ones, keeping
+/// every other line of `existing` as it was.
+fn merge_olympus_presets(existing: &str, presets: &[(String, Vec<String>)]) -> String {
+    let mut written = HashSet::new();
+    let mut lines = Vec::new();
+    let mut skipping = false;
+    for line in existing.lines() {
+        if let Some(name) = line.strip_prefix("**") {
+            let replacement = presets
+                .iter()
+                .find(|(preset, _)| preset.eq_ignore_ascii_case(name.trim()));
+            skipping = replacement.is_some();
+            if let Some((preset, files)) = replacement
+                && written.insert(preset.to_ascii_lowercase())
+            {
+                lines.push(format!("**{preset}"));
+                lines.extend(files.iter().cloned());
+            }
+        }
+        if !skipping {
+            lines.push(line.to_owned());
+        }
+    }
+    for (preset, files) in presets {
+        if written.insert(preset.to_ascii_lowercase()) {
+            lines.push(format!("**{preset}"));
+            lines.extend(files.iter().cloned());
+        }
+    }
+    lines.join("\n") + if lines.is_empty() { "" } else { "\n" }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OlympusExportResult {
+    pub presets: Vec<String>,
+    /// Enabled Mods that are not installed and so have no file to list.
+    pub missing_mods: Vec<String>,
+}
+
+/// Writes profiles as Olympus presets into `destination`, or the game's own
+/// `modpresets.txt` so Olympus sees them right away. Inherited Mods are
+/// written out, since Olympus has no inheritance.
+pub fn export_olympus_presets(
+    game_path: &str,
+    profile_names: &[String],
+    destination: Option<&str>,
+) -> anyhow::Result<OlympusExportResult> {
+    if profile_names.is_empty() {
+        bail!("No profiles were selected");
+    }
+    let profiles = get_mod_blacklist_profiles(game_path);
+    let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
+    let categories = catalog_categories(&profiles);
+    let mut resolver = ProfileResolver::new(&profiles, &installed, &categories);
+    let mut presets = Vec::new();
+    let mut missing_mods = Vec::new();
+    for name in profile_names {
+        let resolved = resolver.resolve_profile(name)?;
+        let (files, missing) = olympus_preset_files(&installed, &resolved.enabled_mods);
+        missing_mods.extend(missing);
+        presets.push((resolved.name, files));
+    }
+
+    let path = destination.map_or_else(
+        || Path::new(game_path).join("Mods").join("modpresets.txt"),
+        PathBuf::from,
+    );
+    let existing = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error).context("Failed to read modpresets.txt"),
+    };
+    let temp_path = path.with_extension("txt.tmp");
+    fs::write(&temp_path, merge_olympus_presets(&existing, &presets))?;
+    fs::rename(&temp_path, &path)?;
+    Ok(OlympusExportResult {
+        presets: presets.into_iter().map(|(name, _)| name).collect(),
+        missing_mods: normalize_names(missing_mods),
+    })
+}
+
+/// Mirrors every CeleMod profile into the game's `modpresets.txt`.
+pub fn sync_olympus_presets(game_path: &str) -> anyhow::Result<OlympusExportResult> {
+    let names = get_mod_blacklist_profiles(game_path)
+        .into_iter()
+        .map(|profile| profile.name)
+        .collect::<Vec<_>>();
+    export_olympus_presets(game_path, &names, None)
+}
+
 pub fn preview_mod_profiles(
     game_path: &str,
     source_path: &str,
@@ -829,6 +1334,24 @@ fn preview_profile_import(
         .into_iter()
         .map(|name| name.to_ascii_lowercase())
         .collect::<HashSet<_>>();
+    let stored = get_mod_blacklist_profiles(game_path);
+    let collisions = profiles
+        .iter()
+        .filter_map(|imported| {
+            let ours = stored
+                .iter()
+                .find(|profile| profile.name.eq_ignore_ascii_case(&imported.name))
+                .filter(|ours| !same_profile(ours, imported))?;
+            let base = read_import_base(game_path, &ours.name);
+            let (merged, conflicts) = merge_profiles(base.as_ref(), ours, imported);
+            Some(ImportCollision {
+                name: ours.name.clone(),
+                has_base: base.is_some(),
+                merged,
+                conflicts,
+            })
+        })
+        .collect();
     Ok(ProfileImportResult {
         profiles,
         missing_mods: normalize_names(imported_names)
@@ -836,9 +1359,121 @@ fn preview_profile_import(
             .filter(|name| !installed_names.contains(&name.to_ascii_lowercase()))
             .collect(),
         missing_files,
+        collisions,
     })
 }
 
+fn same_profile(left: &ModBlacklistProfile, right: &ModBlacklistProfile) -> bool {
+    let key = |names: &[String]| {
+        names
+            .iter()
+            .map(|name| name.to_ascii_lowercase())
+            .collect::<BTreeSet<_>>()
+    };
+    key(&left.enabled_mods) == key(&right.enabled_mods)
+        && key(&left.excluded_mods) == key(&right.excluded_mods)
+        && key(&left.include_categories) == key(&right.include_categories)
+        && left.parents == right.parents
+}
+
+/// Keeps a name when either side changed it relative to `base`. Without a
+/// base nothing is known to be removed, so both sides' names are kept and
+/// the ones only one side has are returned as uncertain.
+fn merge_names(
+    base: Option<&[String]>,
+    ours: &[String],
+    theirs: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let key = |names: &[String]| {
+        names
+            .iter()
+            .map(|name| name.to_ascii_lowercase())
+            .collect::<HashSet<_>>()
+    };
+    let (ours_keys, theirs_keys) = (key(ours), key(theirs));
+    let base_keys = base.map(key);
+    let mut uncertain = Vec::new();
+    let merged = normalize_names(
+        ours.iter()
+            .chain(theirs)
+            .filter(|name| {
+                let key = name.to_ascii_lowercase();
+                let (in_ours, in_theirs) = (ours_keys.contains(&key), theirs_keys.contains(&key));
+                match &base_keys {
+                    _ if in_ours == in_theirs => in_ours,
+                    Some(base_keys) if base_keys.contains(&key) == in_ours => in_theirs,
+                    Some(_) => in_ours,
+                    None => {
+                        uncertain.push((*name).clone());
+                        true
+                    }
+                }
+            })
+            .cloned(),
+    );
+    (merged, normalize_names(uncertain))
+}
+
+/// Three-way merge of a stored profile (`ours`) with an import of the same
+/// name (`theirs`).
+fn merge_profiles(
+    base: Option<&ModBlacklistProfile>,
+    ours: &ModBlacklistProfile,
+    theirs: &ModBlacklistProfile,
+) -> (ModBlacklistProfile, Vec<String>) {
+    let mut conflicts = Vec::new();
+    let parents = if ours.parents == theirs.parents {
+        ours.parents.clone()
+    } else if base.is_some_and(|base| base.parents == ours.parents) {
+        theirs.parents.clone()
+    } else {
+        if base.is_none_or(|base| base.parents != theirs.parents) {
+            conflicts.push("parents".to_string());
+        }
+        ours.parents.clone()
+    };
+    let mut merge_field = |field: &str, select: fn(&ModBlacklistProfile) -> &[String]| {
+        let (merged, uncertain) = merge_names(base.map(select), select(ours), select(theirs));
+        conflicts.extend(uncertain.iter().map(|name| format!("{field}: {name}")));
+        merged
+    };
+    let enabled_mods = merge_field("enabled_mods", |profile| &profile.enabled_mods);
+    let excluded_mods = merge_field("excluded_mods", |profile| &profile.excluded_mods);
+    let include_categories =
+        merge_field("include_categories", |profile| &profile.include_categories);
+    // A Mod one side enables and the other excludes stays enabled, which
+    // silently drops the exclusion unless the stored profile already had both.
+    let already_both = |name: &str| {
+        ours.enabled_mods
+            .iter()
+            .any(|enabled| enabled.eq_ignore_ascii_case(name))
+            && ours
+                .excluded_mods
+                .iter()
+                .any(|excluded| excluded.eq_ignore_ascii_case(name))
+    };
+    conflicts.extend(
+        excluded_mods
+            .iter()
+            .filter(|name| {
+                enabled_mods
+                    .iter()
+                    .any(|enabled| enabled.eq_ignore_ascii_case(name))
+                    && !already_both(name)
+            })
+            .map(|name| format!("enabled_mods/excluded_mods: {name}")),
+    );
+    let merged = ModBlacklistProfile {
+        name: ours.name.clone(),
+        enabled_mods,
+        auto_deps: false,
+        parents,
+        excluded_mods,
+        include_categories,
+    };
+    (merged, conflicts)
+}
+
 pub fn preview_mod_profiles_json(
     game_path: &str,
     contents: &str,
@@ -867,9 +1502,12 @@ pub fn preview_mod_profiles_json(
     preview_profile_import(game_path, profiles, Vec::new())
 }
 
+/// Writes imported profiles. With `merge`, one whose name is taken is merged
+/// into the stored profile instead of replacing it.
 pub fn commit_profile_import(
     game_path: &str,
     profiles: Vec<ModBlacklistProfile>,
+    merge: bool,
 ) -> anyhow::Result<ProfileImportResult> {
     let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
     let profiles = profiles
@@ -881,29 +1519,74 @@ pub fn commit_profile_import(
                     expand_installed_dependencies(&installed, &profile.enabled_mods);
             }
             profile.enabled_mods = normalize_names(profile.enabled_mods);
+            profile.parents = normalize_ordered_names(profile.parents);
+            profile.excluded_mods = normalize_names(profile.excluded_mods);
+            profile.include_categories = normalize_names(profile.include_categories);
             profile.auto_deps = false;
             Ok(profile)
         })
         .collect::<anyhow::Result<Vec<_>>>()?;
+    let stored = get_mod_blacklist_profiles(game_path);
+    let imported = profiles.clone();
+    let profiles = profiles
+        .into_iter()
+        .map(|profile| {
+            let ours = stored
+                .iter()
+                .find(|ours| merge && ours.name.eq_ignore_ascii_case(&profile.name));
+            match ours {
+                Some(ours) => {
+                    let base = read_import_base(game_path, &ours.name);
+                    merge_profiles(base.as_ref(), ours, &profile).0
+                }
+                None => profile,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut combined = stored;
+    combined.retain(|stored| {
+        !profiles
+            .iter()
+            .any(|profile| profile.name.eq_ignore_ascii_case(&stored.name))
+    });
+    combined.extend(profiles.iter().cloned());
+    validate_composition(&combined)?;
     let directory = profiles_directory(game_path);
     fs::create_dir_all(&directory)?;
     let transaction = directory.join(format!(".import-{}", std::process::id()));
+    let transaction_bases = transaction.join(IMPORT_BASE_DIRECTORY);
     fs::remove_dir_all(&transaction).ok();
-    fs::create_dir_all(&transaction)?;
+    fs::create_dir_all(&transaction_bases)?;
+    fs::create_dir_all(directory.join(IMPORT_BASE_DIRECTORY))?;
     let result: anyhow::Result<()> = (|| {
-        for profile in &profiles {
+        for (profile, imported) in profiles.iter().zip(&imported) {
+            let file_name = format!("{}.json", profile.name);
             let contents = serde_json::to_string_pretty(&ExportedProfile {
                 format: PROFILE_FORMAT,
                 version: PROFILE_VERSION,
                 auto_deps: false,
                 profile,
             })?;
-            fs::write(transaction.join(format!("{}.json", profile.name)), contents)?;
+            fs::write(transaction.join(&file_name), contents)?;
+            let base = ModBlacklistProfile {
+                name: profile.name.clone(),
+                ..imported.clone()
+            };
+            fs::write(
+                transaction_bases.join(&file_name),
+                serde_json::to_vec_pretty(&base)?,
+            )?;
         }
         for profile in &profiles {
-            let source = transaction.join(format!("{}.json", profile.name));
-            let destination = profile_path(game_path, &profile.name)?;
-            fs::rename(source, destination)?;
+            let file_name = format!("{}.json", profile.name);
+            fs::rename(
+                transaction.join(&file_name),
+                profile_path(game_path, &profile.name)?,
+            )?;
+            fs::rename(
+                transaction_bases.join(&file_name),
+                import_base_path(game_path, &profile.name)?,
+            )?;
         }
         Ok(())
     })();
@@ -974,6 +1657,9 @@ mod tests {
             "folder\\name",
             " Cfg",
             "Cfg ",
+            ".backup",
+            "Active.json",
+            ".Speedrun.tmp",
         ] {
             assert!(validate_profile_name(name).is_err(), "accepted {name:?}");
         }
@@ -1055,12 +1741,12 @@ mod tests {
         let first = ModBlacklistProfile {
             name: "First".to_string(),
             enabled_mods: vec!["One".to_string()],
-            auto_deps: false,
+            ..ModBlacklistProfile::default()
         };
         let second = ModBlacklistProfile {
             name: "Second".to_string(),
             enabled_mods: vec!["Two".to_string()],
-            auto_deps: false,
+            ..ModBlacklistProfile::default()
         };
         write_profile(&game_path, &first).unwrap();
         write_profile(&game_path, &second).unwrap();
@@ -1077,6 +1763,92 @@ mod tests {
         fs::remove_dir_all(game_path).unwrap();
     }
 
+    #[test]
+    fn resolves_parents_categories_and_exclusions_in_order() {
+        let profile = |name: &str, enabled: &[&str], parents: &[&str], excluded: &[&str]| {
+            ModBlacklistProfile {
+                name: name.to_string(),
+                enabled_mods: enabled.iter().map(|name| name.to_string()).collect(),
+                parents: parents.iter().map(|name| name.to_string()).collect(),
+                excluded_mods: excluded.iter().map(|name| name.to_string()).collect(),
+                ..ModBlacklistProfile::default()
+            }
+        };
+        let installed = ["Helper.A", "Helper.B"]
+            .map(|name| super::super::LocalMod {
+                game_banana_id: -1,
+                name: name.to_string(),
+                deps: Vec::new(),
+                version: "1.0.0".to_string(),
+                file: format!("{name}.zip"),
+                size: 0,
+                modified_at: 0,
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+        let base = profile("Base", &["Shared", "Base.Only"], &[], &[]);
+        let extra = profile("Extra", &["Shared", "Extra.Only"], &["Base"], &[]);
+        let mut child = profile(
+            "Child",
+            &["Own"],
+            &["Extra", "Base"],
+            &["base.only", "Helper.B"],
+        );
+        child.include_categories = vec!["Helpers".to_string()];
+        let profiles = vec![base, extra, child];
+        let categories = HashMap::from([
+            ("helper.a".to_string(), "Helpers".to_string()),
+            ("helper.b".to_string(), "Helpers".to_string()),
+        ]);
+
+        let resolved = ProfileResolver::new(&profiles, &installed, &categories)
+            .resolve_profile("child")
+            .unwrap();
+        assert_eq!(resolved.name, "Child");
+        assert_eq!(resolved.chain, ["Base", "Extra", "Child"]);
+        assert_eq!(
+            resolved.enabled_mods,
+            ["Extra.Only", "Helper.A", "Own", "Shared"]
+        );
+        assert_eq!(resolved.excluded_mods, ["Base.Only", "Helper.B"]);
+        assert_eq!(resolved.sources["Shared"], "Extra");
+        assert_eq!(resolved.sources["Helper.A"], "category:Helpers");
+        assert_eq!(resolved.sources["Own"], "Child");
+    }
+
+    #[test]
+    fn rejects_inheritance_cycles_without_writing() {
+        let game_path = test_game_path("inheritance-cycle");
+        for (name, parents) in [("A", vec![]), ("B", vec!["A".to_string()])] {
+            write_profile(
+                &game_path,
+                &ModBlacklistProfile {
+                    name: name.to_string(),
+                    parents,
+                    ..ModBlacklistProfile::default()
+                },
+            )
+            .unwrap();
+        }
+        let error =
+            set_mod_profile_composition(&game_path, "A", vec!["B".to_string()], vec![], vec![])
+                .unwrap_err();
+        assert_eq!(error.to_string(), "Profile inheritance cycle: A → B → A");
+        assert!(
+            get_mod_blacklist_profiles(&game_path)
+                .iter()
+                .all(|profile| profile.name != "A" || profile.parents.is_empty())
+        );
+        assert!(remove_mod_blacklist_profile(&game_path, "A").is_err());
+        rename_mod_blacklist_profile(&game_path, "A", "Root").unwrap();
+        let child = get_mod_blacklist_profiles(&game_path)
+            .into_iter()
+            .find(|profile| profile.name == "B")
+            .unwrap();
+        assert_eq!(child.parents, ["Root"]);
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
     #[test]
     fn previews_then_commits_v2_profiles() {
         let game_path = test_game_path("transfer");
@@ -1094,7 +1866,7 @@ mod tests {
                 .join("Imported.json")
                 .exists()
         );
-        commit_profile_import(&game_path, preview.profiles).unwrap();
+        commit_profile_import(&game_path, preview.profiles, false).unwrap();
 
         let destination = Path::new(&game_path).join("exported.json");
         export_mod_profile(
@@ -1220,7 +1992,7 @@ mod tests {
         .unwrap();
         assert!(preview.profiles[0].auto_deps);
         assert!(!profiles_directory(&game_path).join("Linked.json").exists());
-        commit_profile_import(&game_path, preview.profiles).unwrap();
+        commit_profile_import(&game_path, preview.profiles, false).unwrap();
         let stored: serde_json::Value = serde_json::from_str(
             &fs::read_to_string(profiles_directory(&game_path).join("Linked.json")).unwrap(),
         )
@@ -1238,14 +2010,15 @@ mod tests {
                 ModBlacklistProfile {
                     name: "Valid".to_string(),
                     enabled_mods: vec!["One".to_string()],
-                    auto_deps: false,
+                    ..ModBlacklistProfile::default()
                 },
                 ModBlacklistProfile {
                     name: "../Invalid".to_string(),
                     enabled_mods: vec!["Two".to_string()],
-                    auto_deps: false,
+                    ..ModBlacklistProfile::default()
                 },
             ],
+            false,
         );
         assert!(result.is_err());
         assert!(!profiles_directory(&game_path).join("Valid.json").exists());
@@ -1260,7 +2033,7 @@ mod tests {
             &ModBlacklistProfile {
                 name: "Existing".to_string(),
                 enabled_mods: vec!["Old.Mod".to_string()],
-                auto_deps: false,
+                ..ModBlacklistProfile::default()
             },
         )
         .unwrap();
@@ -1278,6 +2051,93 @@ mod tests {
         fs::remove_dir_all(game_path).unwrap();
     }
 
+    #[test]
+    fn olympus_export_lists_each_file_once_and_keeps_other_presets() {
+        let installed = [
+            ("Pack.A", "Pack.zip"),
+            ("Pack.B", "Pack.zip"),
+            ("Folder.Mod", "FolderMod"),
+            ("Unused", "Unused.zip"),
+        ]
+        .map(|(name, file)| super::super::LocalMod {
+            game_banana_id: -1,
+            name: name.to_string(),
+            deps: Vec::new(),
+            version: "1.0.0".to_string(),
+            file: file.to_string(),
+            size: 0,
+            modified_at: 0,
+        });
+        let (files, missing) = olympus_preset_files(
+            &installed,
+            &[
+                "pack.a".to_string(),
+                "Pack.B".to_string(),
+                "Folder.Mod".to_string(),
+                "Gone".to_string(),
+            ],
+        );
+        assert_eq!(files, ["FolderMod", "Pack.zip"]);
+        assert_eq!(missing, ["Gone"]);
+
+        let merged = merge_olympus_presets(
+            "**Olympus Only\nUnused.zip\n**speedrun\nOld.zip\n**Last\nPack.zip\n",
+            &[
+                ("Speedrun".to_string(), files),
+                ("New".to_string(), vec!["Unused.zip".to_string()]),
+            ],
+        );
+        assert_eq!(
+            merged,
+            "**Olympus Only\nUnused.zip\n**Speedrun\nFolderMod\nPack.zip\n**Last\nPack.zip\n**New\nUnused.zip\n"
+        );
+    }
+
+    #[test]
+    fn merges_a_reimported_profile_against_the_last_import() {
+        let profile = |enabled: &[&str], parents: &[&str]| ModBlacklistProfile {
+            name: "Shared".to_string(),
+            enabled_mods: enabled.iter().map(|name| name.to_string()).collect(),
+            parents: parents.iter().map(|name| name.to_string()).collect(),
+            ..ModBlacklistProfile::default()
+        };
+        let base = profile(&["Kept", "DroppedLocally", "DroppedUpstream"], &["Base"]);
+        let ours = profile(&["Kept", "DroppedUpstream", "AddedLocally"], &["Local"]);
+        let theirs = profile(&["Kept", "DroppedLocally", "AddedUpstream"], &["Upstream"]);
+
+        let (merged, conflicts) = merge_profiles(Some(&base), &ours, &theirs);
+        assert_eq!(
+            merged.enabled_mods,
+            ["AddedLocally", "AddedUpstream", "Kept"]
+        );
+        assert_eq!(merged.parents, ["Local"]);
+        assert_eq!(conflicts, ["parents"]);
+
+        let (merged, conflicts) = merge_profiles(None, &ours, &theirs);
+        assert_eq!(merged.enabled_mods.len(), 5);
+        assert_eq!(
+            conflicts,
+            [
+                "parents",
+                "enabled_mods: AddedLocally",
+                "enabled_mods: AddedUpstream",
+                "enabled_mods: DroppedLocally",
+                "enabled_mods: DroppedUpstream",
+            ]
+        );
+
+        let theirs = ModBlacklistProfile {
+            excluded_mods: vec!["AddedLocally".to_string()],
+            ..theirs
+        };
+        let (merged, conflicts) = merge_profiles(Some(&base), &ours, &theirs);
+        assert_eq!(merged.excluded_mods, ["AddedLocally"]);
+        assert_eq!(
+            conflicts,
+            ["parents", "enabled_mods/excluded_mods: AddedLocally"]
+        );
+    }
+
     #[test]
     fn olympus_parser_reports_unknown_files() {
         let game_path = test_game_path("olympus");
@@ -0,0 +1,809 @@
+use super::wegfan;
+
+use ::ureq::get;
+use anyhow::{Context, bail};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::UNIX_EPOCH,
+};
+
+const WEGFAN_CATALOG_URL: &str = "https://celeste.weg.fan/api/v2/mod/list";
+const EVEREST_CATALOG_BASE: &str = "https://maddie480.ovh/celeste";
+const EVEREST_UPDATE_FILE: &str = "everest_update.yaml";
+const SEARCH_DATABASE_FILE: &str = "mod_search_database.yaml";
+/// The raw cache file name, so a copied CeleMod cache works as a mirror.
+const MIRROR_CATALOG_FILE: &str = "mod_list.json";
+/// Optional file next to a mirrored `mod_list.json`; see `CatalogDelta`.
+const MIRROR_DELTA_FILE: &str = "mod_list.delta.json";
+const SOURCES_ENV: &str = "CELEMOD_CATALOG_SOURCES";
+
+/// Every source produces the wegfan list document (`{"data": [...]}`): the
+/// frontend reads it as is and `everest::compact_catalog` turns it into
+/// `ModInfoCached`, so the rest of the app never sees where it came from.
+pub trait CatalogSource: Send + Sync {
+    /// Short label reported in `ModCacheStatus`, e.g. `wegfan` or
+    /// `local:/srv/celeste`.
+    fn label(&self) -> String;
+    /// `validators` are the ones this source returned for the cached
+    /// catalog, or `None` when there is no cached copy from this source.
+    fn fetch(
+        &self,
+        validators: Option<&CatalogValidators>,
+    ) -> anyhow::Result<(CatalogFetch, CatalogValidators)>;
+}
+
+/// What a source remembers about the copy it last delivered.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogValidators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Mirror time the cached catalog is complete up to, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_until: Option<u64>,
+}
+
+/// Changes a mirror publishes between two full lists. It applies to a cached
+/// catalog synced up to any time in `since..until`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogDelta {
+    pub since: u64,
+    pub until: u64,
+    /// Added or changed Mods, matched by name.
+    #[serde(default)]
+    pub data: Vec<serde_json::Value>,
+    /// Names of Mods that left the catalog.
+    #[serde(default)]
+    pub removed: Vec<String>,
+}
+
+pub enum CatalogFetch {
+    NotModified,
+    Full(String),
+    Delta(CatalogDelta),
+}
+
+struct WegfanSource;
+
+struct EverestSource {
+    base_url: String,
+}
+
+struct LocalSource {
+    path: PathBuf,
+}
+
+lazy_static! {
+    static ref CATALOG_SOURCES: Mutex<Vec<String>> = Mutex::new(
+        std::env::var(SOURCES_ENV)
+            .ok()
+            .map(|value| value
+                .split(',')
+                .map(|spec| spec.trim().to_string())
+                .filter(|spec| !spec.is_empty())
+                .collect::<Vec<_>>())
+            .filter(|specs| specs.iter().all(|spec| parse_source(spec).is_ok()))
+            .filter(|specs| !specs.is_empty())
+            .unwrap_or_else(default_sources)
+    );
+}
+
+fn default_sources() -> Vec<String> {
+    vec!["wegfan".to_string(), "everest".to_string()]
+}
+
+/// Parses `wegfan`, `everest`, `everest:<base url>` or `local:<file or
+/// directory>`.
+pub fn parse_source(spec: &str) -> anyhow::Result<Box<dyn CatalogSource>> {
+    let (kind, argument) = match spec.split_once(':') {
+        Some((kind, argument)) => (kind, Some(argument.trim())),
+        None => (spec, None),
+    };
+    Ok(
+        match (kind.trim().to_ascii_lowercase().as_str(), argument) {
+            ("wegfan", None) => Box::new(WegfanSource),
+            ("everest", None) => Box::new(EverestSource {
+                base_url: EVEREST_CATALOG_BASE.to_string(),
+            }),
+            ("everest", Some(base_url))
+                if base_url.starts_with("https://") || base_url.starts_with("http://") =>
+            {
+                Box::new(EverestSource {
+                    base_url: base_url.trim_end_matches('/').to_string(),
+                })
+            }
+            ("local", Some(path)) if !path.is_empty() => Box::new(LocalSource {
+                path: PathBuf::from(path),
+            }),
+            _ => bail!("Unknown Mod catalog source {spec:?}"),
+        },
+    )
+}
+
+/// Replaces the ordered source list. Nothing changes when any entry is
+/// invalid. Returns whether the list differs from the previous one.
+pub fn set_catalog_sources(specs: &[String]) -> anyhow::Result<bool> {
+    for spec in specs {
+        parse_source(spec)?;
+    }
+    let specs = if specs.is_empty() {
+        default_sources()
+    } else {
+        specs.to_vec()
+    };
+    let mut current = CATALOG_SOURCES.lock().unwrap();
+    let changed = *current != specs;
+    *current = specs;
+    Ok(changed)
+}
+
+pub fn catalog_sources() -> Vec<String> {
+    CATALOG_SOURCES.lock().unwrap().clone()
+}
+
+/// Tries the configured sources in order and returns the first answer with
+/// the label of the source that gave it. `cached` names the source the cached
+/// catalog came from; only that source is asked conditionally.
+pub fn fetch_catalog(
+    cached: Option<(&str, &CatalogValidators)>,
+) -> anyhow::Result<(CatalogFetch, CatalogValidators, String)> {
+    let sources = catalog_sources()
+        .iter()
+        .map(|spec| parse_source(spec))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    fetch_from_sources(&sources, cached)
+}
+
+fn fetch_from_sources(
+    sources: &[Box<dyn CatalogSource>],
+    cached: Option<(&str, &CatalogValidators)>,
+) -> anyhow::Result<(CatalogFetch, CatalogValidators, String)> {
+    let mut errors = Vec::new();
+    for source in sources {
+        let label = source.label();
+        let validators = cached
+            .filter(|(cached_label, _)| *cached_label == label)
+            .map(|(_, validators)| validators);
+        match source.fetch(validators).and_then(|(fetch, validators)| {
+            if let CatalogFetch::Full(raw) = &fetch {
+                validate_catalog(raw)?;
+            }
+            Ok((fetch, validators))
+        }) {
+            Ok((fetch, validators)) => return Ok((fetch, validators, label)),
+            Err(error) => {
+                crate::logging::warn(format_args!("Mod catalog source {label} failed: {error:#}"));
+                errors.push(format!("{label}: {error:#}"));
+            }
+        }
+    }
+    bail!("No Mod catalog source is available ({})", errors.join("; "))
+}
+
+/// Merges a delta into a cached catalog document, keeping every field the
+/// delta does not mention.
+pub fn apply_delta(raw: &str, delta: &CatalogDelta) -> anyhow::Result<String> {
+    let mut document: serde_json::Value = serde_json::from_str(raw)?;
+    let mods = document["data"]
+        .as_array_mut()
+        .context("Cached catalog has no Mod list")?;
+    let removed = delta.removed.iter().collect::<HashSet<_>>();
+    mods.retain(|item| {
+        item["name"]
+            .as_str()
+            .is_none_or(|name| !removed.contains(&name.to_string()))
+    });
+    for changed in &delta.data {
+        let name = changed["name"]
+            .as_str()
+            .context("Catalog delta entry has no name")?;
+        match mods.iter_mut().find(|item| item["name"] == name) {
+            Some(item) => *item = changed.clone(),
+            None => mods.push(changed.clone()),
+        }
+    }
+    let raw = document.to_string();
+    validate_catalog(&raw)?;
+    Ok(raw)
+}
+
+fn validate_catalog(raw: &str) -> anyhow::Result<()> {
+    #[derive(Deserialize)]
+    struct Document {
+        data: Vec<wegfan::Mod>,
+    }
+    let document: Document = serde_json::from_str(raw).context("Invalid catalog document")?;
+    if document.data.is_empty() {
+        bail!("The catalog is empty");
+    }
+    Ok(())
+}
+
+/// Returns `None` when the server answers 304 to the stored validators.
+fn fetch_text(
+    url: &str,
+    validators: Option<&CatalogValidators>,
+) -> anyhow::Result<Option<(String, CatalogValidators)>> {
+    let mut request = get(url)
+        .set(
+            "User-Agent",
+            &format!("CeleMod/{}-{}", env!("VERSION"), &env!("GIT_HASH")[..6]),
+        )
+        .timeout(std::time::Duration::from_secs(20))
+        .set("Accept-Encoding", "gzip, deflate, br");
+    if let Some(etag) = validators.and_then(|validators| validators.etag.as_deref()) {
+        request = request.set("If-None-Match", etag);
+    }
+    if let Some(modified) = validators.and_then(|validators| validators.last_modified.as_deref()) {
+        request = request.set("If-Modified-Since", modified);
+    }
+    let response = request.call()?;
+    if response.status() == 304 {
+        return Ok(None);
+    }
+    let validators = CatalogValidators {
+        etag: response.header("ETag").map(str::to_string),
+        last_modified: response.header("Last-Modified").map(str::to_string),
+        synced_until: None,
+    };
+    Ok(Some((response.into_string()?, validators)))
+}
+
+impl CatalogSource for WegfanSource {
+    fn label(&self) -> String {
+        "wegfan".to_string()
+    }
+
+    fn fetch(
+        &self,
+        validators: Option<&CatalogValidators>,
+    ) -> anyhow::Result<(CatalogFetch, CatalogValidators)> {
+        Ok(match fetch_text(WEGFAN_CATALOG_URL, validators)? {
+            Some((raw, validators)) => (CatalogFetch::Full(raw), validators),
+            None => (
+                CatalogFetch::NotModified,
+                validators.cloned().unwrap_or_default(),
+            ),
+        })
+    }
+}
+
+impl CatalogSource for EverestSource {
+    fn label(&self) -> String {
+        if self.base_url == EVEREST_CATALOG_BASE {
+            "everest".to_string()
+        } else {
+            format!("everest:{}", self.base_url)
+        }
+    }
+
+    fn fetch(
+        &self,
+        validators: Option<&CatalogValidators>,
+    ) -> anyhow::Result<(CatalogFetch, CatalogValidators)> {
+        // Only the update list is revalidated. It changes whenever a Mod is
+        // published, which is also when the search database matters.
+        let Some((updates, validators)) = fetch_text(
+            &format!("{}/{EVEREST_UPDATE_FILE}", self.base_url),
+            validators,
+        )?
+        else {
+            return Ok((
+                CatalogFetch::NotModified,
+                validators.cloned().unwrap_or_default(),
+            ));
+        };
+        // The search database only adds categories and descriptions; the
+        // update list alone is enough to install and update Mods.
+        let search = fetch_text(&format!("{}/{SEARCH_DATABASE_FILE}", self.base_url), None)
+            .inspect_err(|error| {
+                crate::logging::warn(format_args!(
+                    "Failed to fetch the Mod search database: {error:#}"
+                ));
+            })
+            .ok()
+            .flatten()
+            .map(|(search, _)| search);
+        Ok((
+            CatalogFetch::Full(everest_catalog_document(&updates, search.as_deref())?),
+            validators,
+        ))
+    }
+}
+
+/// Local files are validated by modification time instead of HTTP headers.
+fn file_validator(path: &Path) -> Option<String> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(
+        modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .to_string(),
+    )
+}
+
+impl LocalSource {
+    fn catalog_file(&self) -> anyhow::Result<PathBuf> {
+        if !self.path.is_dir() {
+            return Ok(self.path.clone());
+        }
+        for name in [MIRROR_CATALOG_FILE, EVEREST_UPDATE_FILE] {
+            let path = self.path.join(name);
+            if path.is_file() {
+                return Ok(path);
+            }
+        }
+        bail!(
+            "{} contains neither {MIRROR_CATALOG_FILE} nor {EVEREST_UPDATE_FILE}",
+            self.path.display()
+        )
+    }
+
+    fn read_delta(&self, synced_until: u64) -> Option<CatalogDelta> {
+        let contents = fs::read_to_string(self.path.join(MIRROR_DELTA_FILE)).ok()?;
+        let delta = serde_json::from_str::<CatalogDelta>(&contents)
+            .inspect_err(|error| {
+                crate::logging::warn(format_args!("Ignoring invalid catalog delta: {error}"));
+            })
+            .ok()?;
+        (delta.since <= synced_until && synced_until < delta.until).then_some(delta)
+    }
+}
+
+impl CatalogSource for LocalSource {
+    fn label(&self) -> String {
+        format!("local:{}", self.path.display())
+    }
+
+    fn fetch(
+        &self,
+        validators: Option<&CatalogValidators>,
+    ) -> anyhow::Result<(CatalogFetch, CatalogValidators)> {
+        let path = self.catalog_file()?;
+        let last_modified = file_validator(&path);
+        if let Some(validators) = validators {
+            if let Some(delta) = validators
+                .synced_until
+                .filter(|_| self.path.is_dir())
+                .and_then(|synced_until| self.read_delta(synced_until))
+            {
+                let synced_until = delta.until;
+                return Ok((
+                    CatalogFetch::Delta(delta),
+                    CatalogValidators {
+                        synced_until: Some(synced_until),
+                        ..validators.clone()
+                    },
+                ));
+            }
+            if last_modified.is_some() && validators.last_modified == last_modified {
+                return Ok((CatalogFetch::NotModified, validators.clone()));
+            }
+        }
+        let raw = read_local_catalog(&path)?;
+        let synced_until = serde_json::from_str::<serde_json::Value>(&raw)
+            .ok()
+            .and_then(|document| document["until"].as_u64());
+        Ok((
+            CatalogFetch::Full(raw),
+            CatalogValidators {
+                etag: None,
+                last_modified,
+                synced_until,
+            },
+        ))
+    }
+}
+
+fn read_local_catalog(path: &Path) -> anyhow::Result<String> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let is_json = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    if is_json {
+        // Accept both the API response and a bare list of Mods.
+        let value: serde_json::Value = serde_json::from_str(&contents)?;
+        return Ok(if value.is_array() {
+            serde_json::json!({ "data": value }).to_string()
+        } else {
+            contents
+        });
+    }
+    let search = path
+        .parent()
+        .map(|parent| parent.join(SEARCH_DATABASE_FILE))
+        .and_then(|search| fs::read_to_string(search).ok());
+    everest_catalog_document(&contents, search.as_deref())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EverestUpdateEntry {
+    version: String,
+    #[serde(default)]
+    last_update: i64,
+    #[serde(default)]
+    size: i64,
+    #[serde(default)]
+    game_banana_type: Option<String>,
+    #[serde(default)]
+    game_banana_id: Option<i64>,
+    #[serde(default)]
+    game_banana_file_id: Option<i64>,
+    #[serde(rename = "xxHash", default)]
+    xx_hash: Vec<String>,
+    #[serde(rename = "URL")]
+    url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SearchDatabaseEntry {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    game_banana_type: String,
+    #[serde(default)]
+    game_banana_id: i64,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    category_id: Option<i64>,
+    #[serde(default)]
+    category_name: Option<String>,
+    #[serde(default)]
+    subcategory_id: Option<i64>,
+    #[serde(default)]
+    subcategory_name: Option<String>,
+    #[serde(rename = "PageURL", default)]
+    page_url: Option<String>,
+    #[serde(default)]
+    created_date: i64,
+    #[serde(default)]
+    files: Vec<SearchDatabaseFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SearchDatabaseFile {
+    #[serde(rename = "URL")]
+    url: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    downloads: i64,
+}
+
+/// GameBanana file ids are the last path segment of `/dl/<id>` and
+/// `/mmdl/<id>` links.
+fn game_banana_file_id(url: &str) -> Option<i64> {
+    url.trim_end_matches('/').rsplit('/').next()?.parse().ok()
+}
+
+/// Formats Unix seconds as the ISO 8601 strings the wegfan API returns.
+fn iso_timestamp(seconds: i64) -> String {
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    // Howard Hinnant's civil_from_days.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.000Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Converts the Everest update list, optionally enriched with the GameBanana
+/// search database, into the wegfan list document.
+fn everest_catalog_document(updates: &str, search: Option<&str>) -> anyhow::Result<String> {
+    let updates: HashMap<String, EverestUpdateEntry> =
+        serde_yaml::from_str(updates).context("Invalid everest_update.yaml")?;
+    let submissions = search
+        .and_then(|search| {
+            serde_yaml::from_str::<Vec<SearchDatabaseEntry>>(search)
+                .inspect_err(|error| {
+                    crate::logging::warn(format_args!(
+                        "Ignoring invalid Mod search database: {error}"
+                    ));
+                })
+                .ok()
+        })
+        .unwrap_or_default();
+    let submissions = submissions
+        .iter()
+        .map(|entry| {
+            (
+                (entry.game_banana_type.as_str(), entry.game_banana_id),
+                entry,
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut mods = updates
+        .into_iter()
+        .map(|(name, entry)| {
+            let game_banana_type = entry.game_banana_type.unwrap_or_else(|| "Mod".to_string());
+            let file_id = entry
+                .game_banana_file_id
+                .or_else(|| game_banana_file_id(&entry.url));
+            let submission = entry
+                .game_banana_id
+                .and_then(|id| submissions.get(&(game_banana_type.as_str(), id)).copied());
+            let file = submission.and_then(|submission| {
+                submission
+                    .files
+                    .iter()
+                    .find(|file| file_id.is_some() && game_banana_file_id(&file.url) == file_id)
+            });
+            let updated = iso_timestamp(entry.last_update);
+            let created = submission
+                .map(|submission| iso_timestamp(submission.created_date))
+                .unwrap_or_else(|| updated.clone());
+            wegfan::Mod {
+                id: name.clone(),
+                create_time: updated.clone(),
+                update_time: updated.clone(),
+                delete_time: None,
+                version: entry.version,
+                xx_hash: entry.xx_hash,
+                submission_file: wegfan::SubmissionFile {
+                    id: file_id.map(|id| id.to_string()).unwrap_or_default(),
+                    create_time: updated.clone(),
+                    update_time: updated.clone(),
+                    delete_time: None,
+                    url: entry.url,
+                    description: file
+                        .map(|file| file.description.clone())
+                        .unwrap_or_default(),
+                    downloads: file.map(|file| file.downloads).unwrap_or_default(),
+                    size: entry.size,
+                    game_banana_id: file_id,
+                    submission: wegfan::Submission {
+                        id: entry
+                            .game_banana_id
+                            .map(|id| id.to_string())
+                            .unwrap_or_default(),
+                        create_time: created,
+                        update_time: updated.clone(),
+                        delete_time: None,
+                        name: submission
+                            .map(|submission| submission.name.clone())
+                            .filter(|name| !name.is_empty())
+                            .unwrap_or_else(|| name.clone()),
+                        submitter: submission
+                            .map(|submission| submission.author.clone())
+                            .unwrap_or_default(),
+                        page_url: submission
+                            .and_then(|submission| submission.page_url.clone())
+                            .or_else(|| {
+                                entry.game_banana_id.map(|id| {
+                                    format!(
+                                        "https://gamebanana.com/{}s/{id}",
+                                        game_banana_type.to_ascii_lowercase()
+                                    )
+                                })
+                            }),
+                        game_banana_section: Some(game_banana_type.clone()),
+                        game_banana_id: entry.game_banana_id,
+                        category_id: submission.and_then(|submission| submission.category_id),
+                        category_name: submission
+                            .and_then(|submission| submission.category_name.clone()),
+                        sub_category_id: submission
+                            .and_then(|submission| submission.subcategory_id),
+                        sub_category_name: submission
+                            .and_then(|submission| submission.subcategory_name.clone()),
+                        submission_type: game_banana_type,
+                        latest_update_added_time: updated,
+                    },
+                },
+                name,
+            }
+        })
+        .collect::<Vec<_>>();
+    mods.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(serde_json::json!({ "data": mods }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UPDATES: &str = "\
+ExampleMod:
+  GameBananaType: Mod
+  Version: 1.2.0
+  LastUpdate: 1700000000
+  Size: 2048
+  GameBananaId: 12345
+  GameBananaFileId: 67890
+  xxHash:
+  - 0123456789abcdef
+  URL: https://gamebanana.com/mmdl/67890
+  MirrorURL: https://celestemodupdater.0x0a.de/banana-mirror/67890.zip
+LooseTool:
+  Version: 0.1.0
+  URL: https://example.com/dl/5
+";
+
+    const SEARCH: &str = "\
+- Name: Example Submission
+  GameBananaType: Mod
+  GameBananaId: 12345
+  Author: Someone
+  CategoryId: 6800
+  CategoryName: Maps
+  PageURL: https://gamebanana.com/mods/12345
+  CreatedDate: 1600000000
+  Files:
+  - URL: https://gamebanana.com/dl/67890
+    Description: Latest build
+    Downloads: 42
+";
+
+    struct FailingSource;
+
+    impl CatalogSource for FailingSource {
+        fn label(&self) -> String {
+            "failing".to_string()
+        }
+
+        fn fetch(
+            &self,
+            _validators: Option<&CatalogValidators>,
+        ) -> anyhow::Result<(CatalogFetch, CatalogValidators)> {
+            bail!("offline")
+        }
+    }
+
+    fn full(fetch: CatalogFetch) -> String {
+        match fetch {
+            CatalogFetch::Full(raw) => raw,
+            _ => panic!("expected a full catalog"),
+        }
+    }
+
+    fn test_directory(name: &str) -> PathBuf {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "celemod-catalog-{name}-{}-{unique}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn everest_catalog_is_normalized_into_the_wegfan_shape() {
+        let raw = everest_catalog_document(UPDATES, Some(SEARCH)).unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        let mods: Vec<wegfan::Mod> = serde_json::from_value(value["data"].take()).unwrap();
+        assert_eq!(mods.len(), 2);
+
+        let example = mods.iter().find(|item| item.name == "ExampleMod").unwrap();
+        assert_eq!(example.version, "1.2.0");
+        assert_eq!(example.xx_hash, ["0123456789abcdef"]);
+        assert_eq!(example.update_time, "2023-11-14T22:13:20.000Z");
+        assert_eq!(example.submission_file.game_banana_id, Some(67890));
+        assert_eq!(example.submission_file.downloads, 42);
+        let submission = &example.submission_file.submission;
+        assert_eq!(submission.name, "Example Submission");
+        assert_eq!(submission.category_name.as_deref(), Some("Maps"));
+        assert_eq!(submission.create_time, "2020-09-13T12:26:40.000Z");
+
+        let loose = mods.iter().find(|item| item.name == "LooseTool").unwrap();
+        assert_eq!(loose.submission_file.game_banana_id, Some(5));
+        assert_eq!(loose.submission_file.submission.game_banana_id, None);
+    }
+
+    #[test]
+    fn falls_back_to_a_local_mirror_directory() {
+        let directory = test_directory("mirror");
+        fs::write(directory.join(EVEREST_UPDATE_FILE), UPDATES).unwrap();
+        fs::write(directory.join(SEARCH_DATABASE_FILE), SEARCH).unwrap();
+        let local = parse_source(&format!("local:{}", directory.display())).unwrap();
+
+        let (fetch, _, label) =
+            fetch_from_sources(&[Box::new(FailingSource), local], None).unwrap();
+        assert_eq!(label, format!("local:{}", directory.display()));
+        assert!(full(fetch).contains("Example Submission"));
+
+        // A copied CeleMod cache takes precedence over the Everest files.
+        fs::write(
+            directory.join(MIRROR_CATALOG_FILE),
+            serde_json::json!(
+                serde_json::from_str::<serde_json::Value>(
+                    &everest_catalog_document(UPDATES, Some(SEARCH)).unwrap()
+                )
+                .unwrap()["data"]
+            )
+            .to_string(),
+        )
+        .unwrap();
+        let local = parse_source(&format!("local:{}", directory.display())).unwrap();
+        let (mirrored, _, _) = fetch_from_sources(&[local], None).unwrap();
+        validate_catalog(&full(mirrored)).unwrap();
+
+        assert!(fetch_from_sources(&[Box::new(FailingSource)], None).is_err());
+        assert!(parse_source("ftp:somewhere").is_err());
+        assert!(parse_source("everest:not-a-url").is_err());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn mirrors_revalidate_and_apply_deltas() {
+        let directory = test_directory("delta");
+        let catalog = directory.join(MIRROR_CATALOG_FILE);
+        let mut document: serde_json::Value =
+            serde_json::from_str(&everest_catalog_document(UPDATES, None).unwrap()).unwrap();
+        document["until"] = serde_json::json!(1000);
+        fs::write(&catalog, document.to_string()).unwrap();
+        let source = LocalSource {
+            path: directory.clone(),
+        };
+
+        let (fetch, validators) = source.fetch(None).unwrap();
+        let raw = full(fetch);
+        assert_eq!(validators.synced_until, Some(1000));
+        let (fetch, unchanged) = source.fetch(Some(&validators)).unwrap();
+        assert!(matches!(fetch, CatalogFetch::NotModified));
+        assert_eq!(unchanged, validators);
+
+        let mut changed = document["data"][0].clone();
+        changed["version"] = serde_json::json!("9.9.9");
+        fs::write(
+            directory.join(MIRROR_DELTA_FILE),
+            serde_json::json!({
+                "since": 500,
+                "until": 2000,
+                "data": [changed],
+                "removed": ["LooseTool"],
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let (fetch, validators) = source.fetch(Some(&validators)).unwrap();
+        let CatalogFetch::Delta(delta) = fetch else {
+            panic!("expected a delta");
+        };
+        assert_eq!(validators.synced_until, Some(2000));
+        let merged: serde_json::Value =
+            serde_json::from_str(&apply_delta(&raw, &delta).unwrap()).unwrap();
+        let mods = merged["data"].as_array().unwrap();
+        assert_eq!(mods.len(), 1);
+        assert_eq!(mods[0]["name"], "ExampleMod");
+        assert_eq!(mods[0]["version"], "9.9.9");
+
+        // The delta no longer applies once the cache is past it.
+        let (fetch, _) = source.fetch(Some(&validators)).unwrap();
+        assert!(matches!(fetch, CatalogFetch::NotModified));
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,572 @@
+use super::{
+    DownloadInfo, DownloadStatus, IpcEvent, ModHashAuditStatus, audit_installed_mod_hashes_sync,
+    blacklist, collect_required_installed_mods, crash_analysis, crash_rules, download_file_name,
+    download_mod_queue, enable_installed_local_mods, everest, get_celestes,
+    get_installed_mods_sync, get_installed_mods_without_catalog_sync, get_test_game_path,
+    is_newly_installed_mod, is_test_mode, lockfile, mod_backups, mod_updates,
+    normalize_game_path_buf, plan_dependency_downloads, resolver, update_all_mods_sync,
+    verify_celeste_install,
+};
+use anyhow::{Context, bail};
+use serde_json::json;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::AtomicBool},
+};
+use tauri::ipc::Channel;
+
+/// The command succeeded.
+pub(super) const EXIT_OK: i32 = 0;
+/// The command ran but the operation failed (download, install, I/O ...).
+pub(super) const EXIT_FAILURE: i32 = 1;
+/// The command line could not be parsed.
+pub(super) const EXIT_USAGE: i32 = 2;
+/// No Celeste installation could be resolved for the command.
+pub(super) const EXIT_GAME_NOT_FOUND: i32 = 3;
+/// `analyze-crash` found a crash that is still worth reporting, `audit` found
+/// an archive that differs from every published build, or `download --dry-run`
+/// found dependency problems.
+pub(super) const EXIT_ISSUES_FOUND: i32 = 4;
+
+const COMMANDS: &[&str] = &[
+    "help",
+    "list",
+    "audit",
+    "profiles",
+    "download",
+    "update-all",
+    "rollback",
+    "backups",
+    "restore-backup",
+    "purge-backups",
+    "apply-profiles",
+    "export-lock",
+    "sync-lock",
+    "install-everest",
+    "analyze-crash",
+    "crash-rules",
+];
+
+const HELP: &str = "\
+Usage: cele-mod <command> [options]
+
+Commands:
+  list                         List installed Mods
+  audit                        Compare installed zips with catalog hashes
+  profiles                     List blacklist profiles and the active selection
+  download <name>...           Download Mods and their missing dependencies
+  update-all [name...]         Update outdated Mods, keeping a rollback journal
+  rollback <batch> [name]      Restore the archives replaced by an update batch
+  backups                      List deleted and replaced Mods kept as backups
+  restore-backup <id>          Put a backed-up Mod back into the Mods folder
+  purge-backups [id...]        Delete the given backups, or all of them
+  apply-profiles <name>...     Rewrite Mods/blacklist.txt from profiles
+  export-lock <profile> <file> Pin a profile's Mods to exact builds
+  sync-lock <file>             Make the Mods folder match a lockfile
+  install-everest <url|zip>    Download or install an Everest package
+  analyze-crash                Analyse the most recent Everest crash
+  crash-rules [file|url]       Show the crash rules, or load new ones
+                               (builtin restores the bundled rules)
+  help                         Show this message
+
+Options:
+  --game <path>                Celeste folder (defaults to the detected install)
+  --offline                    list: do not load the Mod catalog
+  --multi-thread               download, update-all: use multi-threaded downloads
+  --always-on <a,b,...>        apply-profiles, sync-lock: Mods that stay enabled
+  --dry-run                    download, sync-lock: only print the plan
+  --language <locale>          analyze-crash: zh-CN, ru-RU, pt-BR, fr-FR,
+                               de-DE or en (default)
+
+The Mod catalog is read from the sources in CELEMOD_CATALOG_SOURCES, a
+comma-separated list of wegfan, everest[:<base url>] and local:<file or
+directory>, tried in order (default: wegfan,everest).
+
+Every command prints one JSON document to stdout. Exit codes:
+  0 success, 1 operation failed, 2 invalid usage, 3 Celeste not found,
+  4 analyze-crash found a crash, audit found a modified archive or
+    download --dry-run found dependency problems
+";
+
+#[derive(Debug, Default, PartialEq)]
+struct CliArgs {
+    command: String,
+    positional: Vec<String>,
+    game: Option<String>,
+    offline: bool,
+    multi_thread: bool,
+    dry_run: bool,
+    always_on: Vec<String>,
+    language: Option<String>,
+}
+
+/// Subcommands are matched on the first argument only, so deep links, the
+/// `/update` helper and `--test-mode` keep starting the GUI as before.
+pub(super) fn is_cli_invocation(args: &[String]) -> bool {
+    args.get(1)
+        .is_some_and(|command| COMMANDS.contains(&command.as_str()) || command == "--help")
+}
+
+fn parse_args(args: &[String]) -> anyhow::Result<CliArgs> {
+    let mut iter = args.iter();
+    let command = iter.next().context("Missing command")?;
+    let mut parsed = CliArgs {
+        command: if command == "--help" {
+            "help".to_string()
+        } else {
+            command.clone()
+        },
+        ..Default::default()
+    };
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--game" => {
+                parsed.game = Some(iter.next().context("--game requires a path")?.clone());
+            }
+            "--always-on" => {
+                let value = iter.next().context("--always-on requires a value")?;
+                parsed.always_on.extend(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_owned),
+                );
+            }
+            "--language" => {
+                parsed.language =
+                    Some(iter.next().context("--language requires a locale")?.clone());
+            }
+            "--offline" => parsed.offline = true,
+            "--multi-thread" => parsed.multi_thread = true,
+            "--dry-run" => parsed.dry_run = true,
+            "--test-mode" => {}
+            flag if flag.starts_with("--") => bail!("Unknown option {flag}"),
+            value => parsed.positional.push(value.to_string()),
+        }
+    }
+    Ok(parsed)
+}
+
+fn resolve_game_path(requested: Option<&str>) -> anyhow::Result<PathBuf> {
+    let path = match requested {
+        Some(path) => normalize_game_path_buf(Path::new(path)),
+        None if is_test_mode() => get_test_game_path(),
+        None => get_celestes()
+            .iter()
+            .filter_map(|game| game.path.as_ref())
+            .map(|path| normalize_game_path_buf(path))
+            .next()
+            .context("No Celeste installation was detected; pass --game <path>")?,
+    };
+    if !verify_celeste_install(path.to_string_lossy().into_owned()) {
+        bail!("{} is not a Celeste installation", path.display());
+    }
+    Ok(path)
+}
+
+/// Download progress events are only meaningful to the webview, so the CLI
+/// drains them through a channel that discards every message.
+fn silent_channel() -> Channel<IpcEvent> {
+    Channel::new(|_| Ok(()))
+}
+
+fn download_mods(
+    game_path: &Path,
+    names: &[String],
+    multi_thread: bool,
+) -> anyhow::Result<(bool, Vec<DownloadInfo>)> {
+    if names.is_empty() {
+        bail!("download requires at least one Mod name");
+    }
+    let mods_dir = game_path.join("Mods");
+    fs::create_dir_all(&mods_dir)?;
+    let mods_dir = mods_dir.to_string_lossy().into_owned();
+    let mod_data = everest::get_mod_cached_new().context("Failed to load the Mod catalog")?;
+    let graph =
+        resolver::get_dependency_graph().context("Failed to load the Mod dependency graph")?;
+    let installed = get_installed_mods_sync(mods_dir.clone());
+    let mut tasks = Vec::new();
+    for name in names {
+        let data = mod_data
+            .get(name)
+            .with_context(|| format!("{name} was not found in the Mod catalog"))?;
+        tasks.push(DownloadInfo {
+            name: name.clone(),
+            url: data.download_url.clone(),
+            dest: Path::new(&mods_dir)
+                .join(download_file_name(&installed, &mods_dir, name))
+                .to_string_lossy()
+                .to_string(),
+            status: DownloadStatus::Waiting,
+            data: String::new(),
+            downloaded_bytes: 0,
+            total_bytes: 0,
+            speed_bytes_per_sec: 0.0,
+        });
+    }
+    tasks.extend(plan_dependency_downloads(
+        names, &installed, &mod_data, &graph, &mods_dir,
+    )?);
+    let installed_before = installed
+        .iter()
+        .map(|item| item.name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    let failed = download_mod_queue(
+        &mut tasks,
+        &mod_data,
+        &graph,
+        &mods_dir,
+        &silent_channel(),
+        multi_thread,
+        &Arc::new(AtomicBool::new(false)),
+    );
+
+    // Match the GUI default: fresh downloads are enabled together with their
+    // required dependencies, in the active profiles when profiles are in use.
+    let installed = get_installed_mods_sync(mods_dir);
+    let new_names = tasks
+        .iter()
+        .filter(|task| task.status == DownloadStatus::Finished)
+        .filter(|task| is_newly_installed_mod(&task.name, &installed_before))
+        .map(|task| task.name.clone())
+        .collect::<Vec<_>>();
+    let game_path = game_path.to_string_lossy();
+    enable_installed_local_mods(
+        &game_path,
+        &collect_required_installed_mods(&new_names, &installed),
+        blacklist::get_blacklist_profile_count(&game_path) > 0,
+        "",
+        &[],
+    )?;
+    Ok((failed, tasks))
+}
+
+fn install_everest(game_path: &Path, source: &str) -> anyhow::Result<()> {
+    let game_path = game_path.to_string_lossy();
+    let mut report = |message: String, progress: f32| {
+        eprintln!("[{progress:>5.1}%] {message}");
+    };
+    let local = Path::new(source);
+    if local.is_file() {
+        everest::install_everest_archive(&game_path, local, &mut report)
+    } else {
+        everest::download_and_install_everest(&game_path, source, &mut report)
+    }
+}
+
+fn execute(args: &CliArgs) -> (i32, serde_json::Value) {
+    if args.command == "help" {
+        return (EXIT_OK, serde_json::Value::Null);
+    }
+    if args.command == "crash-rules" {
+        // Rules are shared by every install, so no game folder is needed.
+        let info = match args.positional.first() {
+            Some(source) => crash_rules::refresh_rules(Some(source)),
+            None => Ok(crash_rules::rules_info()),
+        };
+        return match info.and_then(|info| Ok(serde_json::to_value(info)?)) {
+            Ok(info) => (EXIT_OK, info),
+            Err(error) => (EXIT_FAILURE, json!(format!("{error:#}"))),
+        };
+    }
+    let game_path = match resolve_game_path(args.game.as_deref()) {
+        Ok(path) => path,
+        Err(error) => return (EXIT_GAME_NOT_FOUND, json!(format!("{error:#}"))),
+    };
+    let game = game_path.to_string_lossy().into_owned();
+    let mods_dir = game_path.join("Mods").to_string_lossy().into_owned();
+
+    let result = (|| -> anyhow::Result<(i32, serde_json::Value)> {
+        Ok(match args.command.as_str() {
+            "list" => {
+                let mods = if args.offline {
+                    get_installed_mods_without_catalog_sync(mods_dir)
+                } else {
+                    get_installed_mods_sync(mods_dir)
+                };
+                (EXIT_OK, serde_json::to_value(mods)?)
+            }
+            "audit" => {
+                let mod_data =
+                    everest::get_mod_cached_new().context("Failed to load the Mod catalog")?;
+                let audits = audit_installed_mod_hashes_sync(&mods_dir, &mod_data);
+                let modified = audits
+                    .iter()
+                    .any(|audit| audit.status == ModHashAuditStatus::Modified);
+                (
+                    if modified { EXIT_ISSUES_FOUND } else { EXIT_OK },
+                    serde_json::to_value(audits)?,
+                )
+            }
+            "profiles" => (
+                EXIT_OK,
+                json!({
+                    "profiles": blacklist::get_mod_blacklist_profiles(&game),
+                    "current": blacklist::get_current_profiles(&game),
+                }),
+            ),
+            "download" if args.dry_run => {
+                if args.positional.is_empty() {
+                    bail!("download requires at least one Mod name");
+                }
+                let mod_data =
+                    everest::get_mod_cached_new().context("Failed to load the Mod catalog")?;
+                let graph = resolver::get_dependency_graph()
+                    .context("Failed to load the Mod dependency graph")?;
+                let plan = resolver::plan_mod_install(
+                    &args.positional,
+                    &[],
+                    &get_installed_mods_sync(mods_dir),
+                    &mod_data,
+                    &graph,
+                );
+                (
+                    if plan.is_installable() {
+                        EXIT_OK
+                    } else {
+                        EXIT_ISSUES_FOUND
+                    },
+                    serde_json::to_value(plan)?,
+                )
+            }
+            "download" => {
+                let (failed, tasks) =
+                    download_mods(&game_path, &args.positional, args.multi_thread)?;
+                (
+                    if failed { EXIT_FAILURE } else { EXIT_OK },
+                    serde_json::to_value(tasks)?,
+                )
+            }
+            "update-all" => {
+                let (failed, tasks, journal) = update_all_mods_sync(
+                    &game_path,
+                    &args.positional,
+                    &silent_channel(),
+                    args.multi_thread,
+                    &Arc::new(AtomicBool::new(false)),
+                )?;
+                (
+                    if failed { EXIT_FAILURE } else { EXIT_OK },
+                    json!({ "tasks": tasks, "journal": journal }),
+                )
+            }
+            "rollback" => {
+                let (batch, name) = match args.positional.as_slice() {
+                    [batch] => (batch, None),
+                    [batch, name] => (batch, Some(name.as_str())),
+                    _ => bail!("rollback requires a batch id and optionally one Mod name"),
+                };
+                (
+                    EXIT_OK,
+                    serde_json::to_value(mod_updates::rollback_mod_updates(
+                        &game_path, batch, name,
+                    )?)?,
+                )
+            }
+            "backups" => (
+                EXIT_OK,
+                serde_json::to_value(mod_backups::list_backups(&game_path)?)?,
+            ),
+            "restore-backup" => {
+                let [id] = args.positional.as_slice() else {
+                    bail!("restore-backup requires exactly one backup id");
+                };
+                (
+                    EXIT_OK,
+                    serde_json::to_value(mod_backups::restore_backup(&game_path, id)?)?,
+                )
+            }
+            "purge-backups" => {
+                let ids = (!args.positional.is_empty()).then_some(args.positional.as_slice());
+                let freed = mod_backups::purge_backups(&game_path, ids)?;
+                (EXIT_OK, json!({ "freedBytes": freed }))
+            }
+            "apply-profiles" => {
+                if args.positional.is_empty() {
+                    bail!("apply-profiles requires at least one profile");
+                }
+                let enabled = blacklist::apply_mod_blacklist_profiles(
+                    &game,
+                    &args.positional,
+                    &args.always_on,
+                )?;
+                (EXIT_OK, json!({ "enabled": enabled }))
+            }
+            "export-lock" => {
+                let [profile, destination] = args.positional.as_slice() else {
+                    bail!("export-lock requires a profile name and a destination file");
+                };
+                (
+                    EXIT_OK,
+                    serde_json::to_value(lockfile::export_mod_lockfile(
+                        &game,
+                        profile,
+                        destination,
+                    )?)?,
+                )
+            }
+            "sync-lock" => {
+                let [source] = args.positional.as_slice() else {
+                    bail!("sync-lock requires exactly one lockfile");
+                };
+                let lockfile = lockfile::read_lockfile(source)?;
+                let plan = if args.dry_run {
+                    lockfile::plan_lockfile_sync(&game, &lockfile, &args.always_on)
+                } else {
+                    lockfile::sync_mod_lockfile(
+                        &game,
+                        &lockfile,
+                        &args.always_on,
+                        &mut |name, progress| eprintln!("[{progress:>5.1}%] {name}"),
+                        &Arc::new(AtomicBool::new(false)),
+                    )?
+                };
+                (EXIT_OK, serde_json::to_value(plan)?)
+            }
+            "install-everest" => {
+                let [source] = args.positional.as_slice() else {
+                    bail!("install-everest requires exactly one URL or zip path");
+                };
+                install_everest(&game_path, source)?;
+                (
+                    EXIT_OK,
+                    json!({ "everestVersion": everest::get_everest_version(&game) }),
+                )
+            }
+            "analyze-crash" => match crash_analysis::analyze_latest_crash(
+                &game,
+                args.language.as_deref().unwrap_or_default(),
+            )? {
+                Some(analysis) => (EXIT_ISSUES_FOUND, serde_json::to_value(analysis)?),
+                None => (EXIT_OK, serde_json::Value::Null),
+            },
+            command => (EXIT_USAGE, json!(format!("Unknown command {command}"))),
+        })
+    })();
+    result.unwrap_or_else(|error| (EXIT_FAILURE, json!(format!("{error:#}"))))
+}
+
+#[cfg(all(windows, not(debug_assertions)))]
+fn attach_parent_console() {
+    // Release builds use the GUI subsystem; reuse the terminal that started us
+    // so the JSON output is visible to scripts.
+    unsafe {
+        winapi::um::wincon::AttachConsole(winapi::um::wincon::ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Runs a headless subcommand and returns the process exit code. The result is
+/// printed as `{"ok": bool, "command": ..., "data" | "error": ...}`.
+pub(super) fn run(args: &[String]) -> i32 {
+    #[cfg(all(windows, not(debug_assertions)))]
+    attach_parent_console();
+    crate::logging::route_console_to_stderr();
+
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(error) => {
+            eprint!("{HELP}");
+            println!("{}", json!({ "ok": false, "error": format!("{error:#}") }));
+            return EXIT_USAGE;
+        }
+    };
+    if args.command == "help" {
+        print!("{HELP}");
+        return EXIT_OK;
+    }
+
+    let (code, value) = execute(&args);
+    let output = if code == EXIT_OK || code == EXIT_ISSUES_FOUND {
+        json!({ "ok": true, "command": args.command, "data": value })
+    } else {
+        json!({ "ok": false, "command": args.command, "error": value })
+    };
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&output).unwrap_or_else(|_| output.to_string())
+    );
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn only_known_subcommands_start_the_cli() {
+        assert!(is_cli_invocation(&args(&["cele-mod", "list"])));
+        assert!(is_cli_invocation(&args(&["cele-mod", "--help"])));
+        assert!(!is_cli_invocation(&args(&["cele-mod"])));
+        assert!(!is_cli_invocation(&args(&["cele-mod", "--test-mode"])));
+        assert!(!is_cli_invocation(&args(&["cele-mod", "/update", "x"])));
+        assert!(!is_cli_invocation(&args(&[
+            "cele-mod",
+            "celemod://install"
+        ])));
+    }
+
+    #[test]
+    fn parses_options_and_positional_arguments() {
+        let parsed = parse_args(&args(&[
+            "apply-profiles",
+            "Base",
+            "--game",
+            "/games/Celeste",
+            "Maps",
+            "--always-on",
+            "CelesteNet.Client, MiaoNet",
+            "--language",
+            "ru-RU",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.command, "apply-profiles");
+        assert_eq!(parsed.positional, ["Base", "Maps"]);
+        assert_eq!(parsed.game.as_deref(), Some("/games/Celeste"));
+        assert_eq!(parsed.always_on, ["CelesteNet.Client", "MiaoNet"]);
+        assert_eq!(parsed.language.as_deref(), Some("ru-RU"));
+
+        assert!(parse_args(&args(&["list", "--game"])).is_err());
+        assert!(parse_args(&args(&["list", "--unknown"])).is_err());
+    }
+
+    #[test]
+    fn reports_missing_game_and_lists_offline_mods() {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("celemod-cli-{unique}"));
+        fs::create_dir_all(root.join("Mods").join("Example")).unwrap();
+        fs::write(
+            root.join("Mods").join("Example").join("everest.yaml"),
+            "- Name: Example\n  Version: 1.2.3\n",
+        )
+        .unwrap();
+
+        let missing = parse_args(&args(&["list", "--game", &root.to_string_lossy()])).unwrap();
+        assert_eq!(execute(&missing).0, EXIT_GAME_NOT_FOUND);
+
+        fs::write(root.join("Celeste.dll"), b"").unwrap();
+        let list = parse_args(&args(&[
+            "list",
+            "--offline",
+            "--game",
+            &root.to_string_lossy(),
+        ]))
+        .unwrap();
+        let (code, value) = execute(&list);
+        assert_eq!(code, EXIT_OK);
+        assert_eq!(value[0]["name"], "Example");
+        assert_eq!(value[0]["version"], "1.2.3");
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
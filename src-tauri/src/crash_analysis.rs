@@ -1,17 +1,18 @@
 use super::{
-    EverestModMetadata, LocalMod, everest, get_installed_mods_without_catalog_sync, parse_mod_yaml,
-    parse_mod_yaml_document,
+    LocalMod, crash_history, crash_rules, dependency_check, everest,
+    get_installed_mods_without_catalog_sync, launch_snapshot, mod_index, stack_trace,
 };
 use anyhow::{Context, bail};
 use lazy_static::lazy_static;
 use parking_lot::Mutex;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     fs,
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
+    sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 
@@ -20,8 +21,12 @@ const MAX_CRASH_LINES: usize = 1200;
 const MAX_ERROR_LOG_BYTES: usize = 2_000_000;
 const LOG_SETTLE_MILLIS: u64 = 3_000;
 const MAX_CRASH_AGE_MILLIS: u64 = 60 * 60 * 1000;
+/// Added to suspects that changed since the last launch that did not crash.
+const CHANGED_SUSPECT_BOOST: u8 = 10;
+/// Further dependency issues stay in `dependency_issues` and the popup.
+const MAX_DEPENDENCY_REASONS: usize = 5;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CrashSuspect {
     pub name: String,
@@ -34,20 +39,24 @@ pub struct CrashSuspect {
     pub confidence: u8,
     pub evidence: String,
     pub dependents: Vec<CrashDependent>,
+    /// Added, updated or re-enabled since the last good launch.
+    pub changed_since_last_good: bool,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CrashDependent {
     pub name: String,
     pub optional: bool,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CrashAnalysis {
     pub fingerprint: String,
     pub event_id: String,
+    /// Same for the same crash in later game runs; groups crash history.
+    pub signature: String,
     pub crash_index: u64,
     pub log_modified_at: u64,
     pub source_log: String,
@@ -58,9 +67,18 @@ pub struct CrashAnalysis {
     pub reasons: Vec<String>,
     pub suggestions: Vec<String>,
     pub suspects: Vec<CrashSuspect>,
+    /// Broken dependencies of the enabled Mods, each with a fix if one is known.
+    #[serde(default)]
+    pub dependency_issues: Vec<dependency_check::DependencyIssue>,
+    /// Exceptions and frames parsed from the crash, in log order.
+    pub stack: Vec<stack_trace::ExceptionChain>,
+    /// `None` until CeleMod has seen a launch exit without crashing.
+    pub changes_since_last_good: Option<launch_snapshot::LaunchDiff>,
     pub everest_version: Option<i32>,
     pub is_everest_ultra: bool,
     pub excerpt: String,
+    /// One of `crash_rules::SUPPORTED_LOCALES`.
+    pub locale: String,
 }
 
 #[derive(Clone)]
@@ -77,6 +95,7 @@ struct CachedAnalysis {
     game_path: String,
     fingerprint: String,
     error_log_signature: Option<String>,
+    rules: Arc<crash_rules::CrashRuleSet>,
     analysis: CrashAnalysis,
 }
 
@@ -93,7 +112,7 @@ fn modified_millis(path: &Path) -> u64 {
         .unwrap_or_default()
 }
 
-fn now_millis() -> u64 {
+pub(super) fn now_millis() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
@@ -107,7 +126,7 @@ fn should_analyze_crash(modified_at: u64, now: u64) -> bool {
     (LOG_SETTLE_MILLIS..MAX_CRASH_AGE_MILLIS).contains(&age)
 }
 
-fn is_crash_marker(line: &str) -> bool {
+pub(super) fn is_crash_marker(line: &str) -> bool {
     let lower = line.to_ascii_lowercase();
     lower.contains("encountered a critical error")
         || lower.contains(">>> critical error:")
@@ -123,8 +142,8 @@ fn starts_unrelated_log_entry(line: &str) -> bool {
         && !lower.contains("crit-error-handler")
 }
 
-fn crash_signature(record: &CrashRecord) -> String {
-    let lines = record.excerpt.lines().collect::<Vec<_>>();
+fn stable_crash_lines(excerpt: &str) -> Vec<&str> {
+    let lines = excerpt.lines().collect::<Vec<_>>();
     let marker = lines
         .iter()
         .rposition(|line| is_crash_marker(line))
@@ -137,13 +156,33 @@ fn crash_signature(record: &CrashRecord) -> String {
         }
         stable_lines.push(*line);
     }
-    let stable_part = stable_lines.join("\n");
+    stable_lines
+}
+
+fn crash_signature(record: &CrashRecord) -> String {
+    let stable_part = stable_crash_lines(&record.excerpt).join("\n");
     let mut hasher = Sha256::new();
     hasher.update(record.crash_index.to_le_bytes());
     hasher.update(stable_part.as_bytes());
     format!("{:x}", hasher.finalize())[..16].to_string()
 }
 
+/// Like `crash_signature`, but the same crash in a later game run matches:
+/// log line timestamps and the crash sequence are left out.
+fn run_crash_signature(record: &CrashRecord) -> String {
+    let mut hasher = Sha256::new();
+    for line in stable_crash_lines(&record.excerpt) {
+        let line = line
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(") "))
+            .map(|(_, rest)| rest)
+            .unwrap_or(line);
+        hasher.update(line.trim().as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
 fn crash_event_id(record: &CrashRecord) -> String {
     let body = latest_crash_body(&record.excerpt);
     let lines = body.lines().collect::<Vec<_>>();
@@ -152,7 +191,8 @@ fn crash_event_id(record: &CrashRecord) -> String {
         .find(|line| is_crash_marker(line))
         .map(|line| line.trim())
         .unwrap_or_default();
-    let exception = exception_line(&body);
+    // Event ids predate localized reports; keep hashing the original text.
+    let exception = exception_line(&body).unwrap_or(ZH_CN.unknown_exception);
     let timestamp = error_section_time_key(&lines);
 
     let mut hasher = Sha256::new();
@@ -170,22 +210,23 @@ fn crash_event_id(record: &CrashRecord) -> String {
     format!("{:x}", hasher.finalize())[..16].to_string()
 }
 
-fn scan_log(path: &Path, inherent_crash_log: bool) -> Option<CrashRecord> {
-    let file = fs::File::open(path).ok()?;
+/// Every crash section in one log, oldest first.
+fn scan_log_crashes(path: &Path, inherent_crash_log: bool) -> Vec<CrashRecord> {
+    let Ok(file) = fs::File::open(path) else {
+        return Vec::new();
+    };
     let reader = BufReader::new(file);
     let mut before = VecDeque::with_capacity(PRE_CRASH_LINES);
-    let mut excerpt = Vec::new();
-    let mut crash_index = 0_u64;
+    let mut excerpts: Vec<Vec<String>> = Vec::new();
     let mut capturing = false;
 
     for line in reader.lines().map_while(Result::ok) {
         if is_crash_marker(&line) {
-            crash_index += 1;
             // A log can contain multiple handled crashes. Do not carry context
-            // from the previous crash into the newest crash section.
-            excerpt = vec![line.clone()];
+            // from the previous crash into the next crash section.
+            excerpts.push(vec![line.clone()]);
             capturing = true;
-        } else if crash_index > 0 && capturing {
+        } else if capturing && let Some(excerpt) = excerpts.last_mut() {
             if excerpt.len() > 2 && starts_unrelated_log_entry(&line) {
                 capturing = false;
             } else if excerpt.len() < PRE_CRASH_LINES + MAX_CRASH_LINES {
@@ -199,30 +240,52 @@ fn scan_log(path: &Path, inherent_crash_log: bool) -> Option<CrashRecord> {
         before.push_back(line);
     }
 
-    if crash_index == 0 {
+    if excerpts.is_empty() {
         if !inherent_crash_log {
-            return None;
+            return Vec::new();
         }
-        crash_index = 1;
-        excerpt = before.into_iter().collect();
+        excerpts.push(before.into_iter().collect());
     }
 
-    Some(CrashRecord {
-        path: path.to_path_buf(),
-        modified_at: modified_millis(path),
-        crash_index,
-        excerpt: excerpt.join("\n"),
-        inherent_crash_log,
-    })
+    let modified_at = modified_millis(path);
+    excerpts
+        .into_iter()
+        .enumerate()
+        .map(|(position, excerpt)| CrashRecord {
+            path: path.to_path_buf(),
+            modified_at,
+            crash_index: position as u64 + 1,
+            excerpt: excerpt.join("\n"),
+            inherent_crash_log,
+        })
+        .collect()
 }
 
-fn collect_log_candidates(game_path: &Path) -> Vec<CrashRecord> {
-    let mut records = Vec::new();
-    if let Some(record) = scan_log(&game_path.join("log.txt"), false) {
-        records.push(record);
-    }
+fn scan_log(path: &Path, inherent_crash_log: bool) -> Option<CrashRecord> {
+    scan_log_crashes(path, inherent_crash_log).pop()
+}
+
+pub(super) struct CrashProbe {
+    pub modified_at: u64,
+    pub signature: String,
+}
 
+/// The newest crash in `log.txt`, `LogHistory` or `CrashLogs`, for telling
+/// whether a crash came back after a relaunch.
+pub(super) fn latest_crash_probe(game_path: &Path) -> Option<CrashProbe> {
+    collect_log_candidates(game_path)
+        .into_iter()
+        .max_by_key(|record| (record.modified_at, record.crash_index))
+        .map(|record| CrashProbe {
+            modified_at: record.modified_at,
+            signature: run_crash_signature(&record),
+        })
+}
+
+fn log_files(game_path: &Path) -> Vec<(PathBuf, bool)> {
+    let mut files = vec![(game_path.join("log.txt"), false)];
     for (directory, inherent) in [
+        (game_path.join("Logs"), false),
         (game_path.join("LogHistory"), false),
         (game_path.join("CrashLogs"), true),
     ] {
@@ -234,12 +297,72 @@ fn collect_log_candidates(game_path: &Path) -> Vec<CrashRecord> {
             if path
                 .extension()
                 .is_some_and(|extension| extension.eq_ignore_ascii_case("txt"))
-                && let Some(record) = scan_log(&path, inherent)
             {
-                records.push(record);
+                files.push((path, inherent));
             }
         }
     }
+    files
+}
+
+fn collect_log_candidates(game_path: &Path) -> Vec<CrashRecord> {
+    log_files(game_path)
+        .into_iter()
+        .filter_map(|(path, inherent)| scan_log(&path, inherent))
+        .collect()
+}
+
+/// One record per error log section. Sections are stored newest first, so
+/// the sequence is reversed to match `scan_log_crashes`.
+fn error_log_crashes(path: &Path) -> Vec<CrashRecord> {
+    let text = read_limited_text(path, MAX_ERROR_LOG_BYTES);
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut starts = error_section_starts(&lines);
+    if starts.is_empty() {
+        starts.push(0);
+    }
+    let modified_at = modified_millis(path);
+    let count = starts.len() as u64;
+    starts
+        .iter()
+        .enumerate()
+        .map(|(position, start)| {
+            let end = starts.get(position + 1).copied().unwrap_or(lines.len());
+            CrashRecord {
+                path: path.to_path_buf(),
+                modified_at,
+                crash_index: count - position as u64,
+                excerpt: lines[*start..end]
+                    .join("\n")
+                    .trim_matches(['\u{feff}', '\r', '\n'])
+                    .to_string(),
+                inherent_crash_log: true,
+            }
+        })
+        .filter(|record| !record.excerpt.is_empty())
+        .collect()
+}
+
+/// Every crash CeleMod can find, newest first. A crash that was copied to
+/// several logs is listed once, from the first log that has it.
+fn collect_all_crashes(game_path: &Path) -> Vec<CrashRecord> {
+    let mut seen = HashSet::new();
+    let mut records = log_files(game_path)
+        .into_iter()
+        .flat_map(|(path, inherent)| scan_log_crashes(&path, inherent))
+        .chain(
+            error_log_candidates(game_path)
+                .iter()
+                .flat_map(|path| error_log_crashes(path)),
+        )
+        .filter(|record| seen.insert(crash_event_id(record)))
+        .collect::<Vec<_>>();
+    records.sort_by(|left, right| {
+        right
+            .modified_at
+            .cmp(&left.modified_at)
+            .then_with(|| right.crash_index.cmp(&left.crash_index))
+    });
     records
 }
 
@@ -287,7 +410,7 @@ fn error_log_candidates(game_path: &Path) -> Vec<PathBuf> {
         .collect()
 }
 
-fn read_limited_text(path: &Path, maximum: usize) -> String {
+pub(super) fn read_limited_text(path: &Path, maximum: usize) -> String {
     let Ok(bytes) = fs::read(path) else {
         return String::new();
     };
@@ -405,18 +528,20 @@ fn extract_latest_error_section(text: &str) -> String {
     extract_error_section(text, None)
 }
 
+fn read_error_log(path: PathBuf) -> (PathBuf, u64, String) {
+    let modified = modified_millis(&path);
+    let text = read_limited_text(&path, MAX_ERROR_LOG_BYTES);
+    (path, modified, text)
+}
+
 fn latest_error_log(game_path: &Path) -> Option<(PathBuf, u64, String)> {
     error_log_candidates(game_path)
         .into_iter()
-        .map(|path| {
-            let modified = modified_millis(&path);
-            let text = read_limited_text(&path, MAX_ERROR_LOG_BYTES);
-            (path, modified, text)
-        })
+        .map(read_error_log)
         .max_by_key(|(_, modified, _)| *modified)
 }
 
-fn normalize_token(value: &str) -> String {
+pub(super) fn normalize_token(value: &str) -> String {
     value
         .chars()
         .filter(|character| character.is_ascii_alphanumeric())
@@ -424,7 +549,7 @@ fn normalize_token(value: &str) -> String {
         .collect()
 }
 
-fn version_numbers(value: &str) -> Vec<u64> {
+pub(super) fn version_numbers(value: &str) -> Vec<u64> {
     value
         .split(|character: char| !character.is_ascii_digit())
         .filter(|part| !part.is_empty())
@@ -447,22 +572,9 @@ fn version_is_newer(latest: &str, installed: &str) -> bool {
     })
 }
 
-fn yaml_for_directory(path: &Path) -> Option<Vec<EverestModMetadata>> {
-    ["everest.yaml", "everest.yml"]
-        .iter()
-        .map(|name| path.join(name))
-        .find(|candidate| candidate.is_file())
-        .and_then(|candidate| fs::read_to_string(candidate).ok())
-        .and_then(|text| parse_mod_yaml_document(&text).ok())
-}
-
 fn mod_tokens(game_path: &Path, local_mod: &LocalMod) -> Vec<String> {
-    let mod_path = game_path.join("Mods").join(&local_mod.file);
-    let yaml = if mod_path.is_dir() {
-        yaml_for_directory(&mod_path)
-    } else {
-        parse_mod_yaml(&mod_path).ok()
-    };
+    let yaml = mod_index::indexed_mod(&game_path.join("Mods"), &local_mod.file)
+        .map(|indexed| indexed.metadata.clone());
     let mut tokens = vec![local_mod.name.clone()];
     if let Some(entries) = yaml {
         for entry in entries {
@@ -480,6 +592,60 @@ fn mod_tokens(game_path: &Path, local_mod: &LocalMod) -> Vec<String> {
     tokens
 }
 
+/// Assemblies whose frames belong to `local_mod`: its name, the `DLL` of its
+/// everest.yaml and every DLL it ships.
+fn mod_assemblies(game_path: &Path, local_mod: &LocalMod) -> Vec<String> {
+    let mut assemblies = mod_tokens(game_path, local_mod);
+    if let Some(indexed) = mod_index::indexed_mod(&game_path.join("Mods"), &local_mod.file) {
+        assemblies.extend(indexed.assemblies.iter().cloned());
+    }
+    let mut assemblies = assemblies
+        .iter()
+        .map(|assembly| normalize_token(assembly))
+        .filter(|assembly| assembly.len() >= 4 && !stack_trace::is_framework_assembly(assembly))
+        .collect::<Vec<_>>();
+    assemblies.sort();
+    assemblies.dedup();
+    assemblies
+}
+
+/// The strongest frame of `assemblies` in the parsed stack trace. A frame in
+/// an assembly that several enabled Mods ship counts for less.
+fn frame_evidence(
+    chains: &[stack_trace::ExceptionChain],
+    assemblies: &[String],
+    owners: &HashMap<String, usize>,
+) -> Option<(u8, String)> {
+    let mut best: Option<(u8, String)> = None;
+    for chain in chains {
+        for (level, exception) in chain.exceptions.iter().enumerate() {
+            let outer_levels = chain.exceptions.len() - 1 - level;
+            for (position, frame) in exception.frames.iter().enumerate() {
+                let Some(assembly) = assemblies
+                    .iter()
+                    .find(|assembly| stack_trace::frame_in_assembly(frame, assembly))
+                else {
+                    continue;
+                };
+                let mut confidence = stack_trace::frame_confidence(position, outer_levels);
+                if owners.get(assembly).copied().unwrap_or_default() > 1 {
+                    confidence = confidence.saturating_sub(10);
+                }
+                if best
+                    .as_ref()
+                    .is_none_or(|(current, _)| confidence > *current)
+                {
+                    best = Some((
+                        confidence,
+                        format!("at {}.{}", frame.type_name, frame.method),
+                    ));
+                }
+            }
+        }
+    }
+    best
+}
+
 fn disabled_mod_files(game_path: &Path) -> HashSet<String> {
     let path = game_path.join("Mods").join("blacklist.txt");
     fs::read_to_string(path)
@@ -491,8 +657,13 @@ fn disabled_mod_files(game_path: &Path) -> HashSet<String> {
         .collect()
 }
 
+/// Evidence outside stack frames, which `frame_evidence` handles.
 fn find_evidence(text: &str, tokens: &[String]) -> Option<(u8, String)> {
     text.lines()
+        .filter(|line| {
+            let trimmed = line.trim();
+            !trimmed.starts_with("at ") && !trimmed.starts_with("在 ")
+        })
         .filter_map(|line| {
             let lower = line.to_ascii_lowercase();
             let matched_tokens = tokens
@@ -511,8 +682,6 @@ fn find_evidence(text: &str, tokens: &[String]) -> Option<(u8, String)> {
                 // Everest explicitly names the startup Mod whose Load method failed.
                 // This is stronger evidence than later framework/interceptor frames.
                 100
-            } else if trimmed.starts_with("at ") || trimmed.starts_with("在 ") {
-                96
             } else {
                 72
             };
@@ -521,62 +690,13 @@ fn find_evidence(text: &str, tokens: &[String]) -> Option<(u8, String)> {
         .max_by_key(|(confidence, _)| *confidence)
 }
 
-fn known_alias_score(mod_name: &str, crash_compact: &str) -> Option<(u8, &'static str)> {
-    let name = normalize_token(mod_name);
-    let aliases: &[(&[&str], &[&str], &str)] = &[
-        (
-            &["maxhelpinghand", "maddieshelpinghand"],
-            &["maxhelpinghand"],
-            "stacktrace mentions MaxHelpingHand",
-        ),
-        (
-            &["strawberryjam"],
-            &["strawberryjam"],
-            "stacktrace mentions Strawberry Jam",
-        ),
-        (
-            &["isagrabbag"],
-            &["isagrabbag", "isagrab"],
-            "stacktrace mentions Isa's Grab Bag",
-        ),
-        (
-            &["factoryhelper"],
-            &["factoryhelper"],
-            "stacktrace mentions Factory Helper",
-        ),
-        (
-            &["bouncehelper"],
-            &["bouncehelper"],
-            "stacktrace mentions Bounce Helper",
-        ),
-        (
-            &["celestenet", "miaonet", "miaocelestenetclient"],
-            &["celestenetclient", "miaocelestenet"],
-            "stacktrace mentions CelesteNet/MiaoNet",
-        ),
-        (
-            &["randomizer"],
-            &["randomizerrandomodule"],
-            "stacktrace mentions Randomizer",
-        ),
-        (
-            &["celestetas", "tas"],
-            &["taseverestinterop", "celestetas"],
-            "stacktrace mentions CelesteTAS",
-        ),
-    ];
-    aliases
-        .iter()
-        .find_map(|(mod_aliases, trace_aliases, reason)| {
-            (mod_aliases.iter().any(|alias| name.contains(alias))
-                && trace_aliases
-                    .iter()
-                    .any(|alias| crash_compact.contains(alias)))
-            .then_some((78, *reason))
-        })
-}
-
-fn analyze_suspects(game_path: &Path, text: &str) -> Vec<CrashSuspect> {
+/// `changes` boosts Mods that changed since the last good launch before the
+/// list is ranked and trimmed, so a changed Mod can make the cut.
+fn analyze_suspects(
+    game_path: &Path,
+    text: &str,
+    changes: Option<&launch_snapshot::LaunchDiff>,
+) -> Vec<CrashSuspect> {
     let installed = get_installed_mods_without_catalog_sync(
         game_path.join("Mods").to_string_lossy().into_owned(),
     );
@@ -597,31 +717,39 @@ fn analyze_suspects(game_path: &Path, text: &str) -> Vec<CrashSuspect> {
         }
     }
     let compact_crash = normalize_token(text);
+    let chains = stack_trace::parse(text);
+    let rules = crash_rules::active_rules();
     let catalog = everest::get_mod_cached_if_loaded();
     let mut suspects = Vec::new();
 
-    for local_mod in installed {
-        if disabled.contains(&local_mod.file.to_ascii_lowercase()) {
-            continue;
+    let enabled = installed
+        .into_iter()
+        .filter(|local_mod| !disabled.contains(&local_mod.file.to_ascii_lowercase()))
+        .map(|local_mod| {
+            let assemblies = mod_assemblies(game_path, &local_mod);
+            (local_mod, assemblies)
+        })
+        .collect::<Vec<_>>();
+    let mut owners = HashMap::<String, usize>::new();
+    for (_, assemblies) in &enabled {
+        for assembly in assemblies {
+            *owners.entry(assembly.clone()).or_default() += 1;
         }
+    }
+
+    for (local_mod, assemblies) in enabled {
         let tokens = mod_tokens(game_path, &local_mod);
-        let normalized_tokens = tokens
-            .iter()
-            .map(|token| normalize_token(token))
-            .filter(|token| token.len() >= 4)
-            .collect::<Vec<_>>();
-        let evidence = find_evidence(text, &tokens);
-        let direct_match = normalized_tokens
-            .iter()
-            .filter(|token| !["celeste", "everest", "coremodule"].contains(&token.as_str()))
-            .max_by_key(|token| token.len())
-            .filter(|token| compact_crash.contains(token.as_str()));
+        let evidence = [
+            find_evidence(text, &tokens),
+            frame_evidence(&chains, &assemblies, &owners),
+        ]
+        .into_iter()
+        .flatten()
+        .max_by_key(|(confidence, _)| *confidence);
 
         let (confidence, reason) = if let Some((confidence, line)) = evidence {
             (confidence, line)
-        } else if let Some(token) = direct_match {
-            (82, format!("stacktrace contains identifier {token}"))
-        } else if let Some((score, reason)) = known_alias_score(&local_mod.name, &compact_crash) {
+        } else if let Some((score, reason)) = rules.alias_score(&local_mod.name, &compact_crash) {
             (score, reason.to_string())
         } else {
             continue;
@@ -644,9 +772,13 @@ fn analyze_suspects(game_path: &Path, text: &str) -> Vec<CrashSuspect> {
             confidence,
             evidence: reason,
             dependents: mod_dependents,
+            changed_since_last_good: false,
         });
     }
 
+    if let Some(changes) = changes {
+        boost_changed_suspects(&mut suspects, changes);
+    }
     suspects.sort_by(|left, right| {
         right
             .confidence
@@ -660,7 +792,22 @@ fn analyze_suspects(game_path: &Path, text: &str) -> Vec<CrashSuspect> {
     suspects
 }
 
-fn exception_line(text: &str) -> String {
+fn boost_changed_suspects(suspects: &mut [CrashSuspect], changes: &launch_snapshot::LaunchDiff) {
+    for suspect in suspects.iter_mut() {
+        if changes
+            .changed_mods()
+            .any(|name| name.eq_ignore_ascii_case(&suspect.name))
+        {
+            suspect.changed_since_last_good = true;
+            suspect.confidence = suspect
+                .confidence
+                .saturating_add(CHANGED_SUSPECT_BOOST)
+                .min(100);
+        }
+    }
+}
+
+fn exception_line(text: &str) -> Option<&str> {
     let lines = text.lines().map(str::trim).collect::<Vec<_>>();
     lines
         .iter()
@@ -681,8 +828,6 @@ fn exception_line(text: &str) -> String {
                     && !line.starts_with("在 ")
             })
         })
-        .unwrap_or("未能识别异常类型")
-        .to_string()
 }
 
 fn latest_crash_body(text: &str) -> String {
@@ -694,165 +839,50 @@ fn latest_crash_body(text: &str) -> String {
     lines[start..].join("\n")
 }
 
-fn reason_analysis(text: &str, suspects: &[CrashSuspect]) -> (String, Vec<String>, Vec<String>) {
-    let lower = text.to_ascii_lowercase();
-    let mut reasons = Vec::new();
-    let mut suggestions = Vec::new();
-    let summary = if lower.contains("outofmemoryexception")
-        || lower.contains("image loading failed: outofmem")
-    {
-        reasons.push("内存或显存不足，通常是同时启用的地图、贴图或大型 Mod 过多。".to_string());
-        suggestions
-            .push("先禁用不需要的地图和大型内容 Mod，并关闭占用内存/显存的程序。".to_string());
-        "资源不足导致崩溃".to_string()
-    } else if lower.contains("badcrcexception")
-        || lower.contains("end of central directory")
-        || lower.contains("invalid block type")
-        || lower.contains("could not read") && lower.contains("zip file")
-    {
-        reasons.push("某个 Mod 压缩包不完整或已经损坏。".to_string());
-        suggestions.push(
-            "重新下载日志中最后加载或点名的 Mod；若没有文件名，可用二分法禁用 Mod 排查。"
-                .to_string(),
-        );
-        "Mod 压缩包可能损坏".to_string()
-    } else if lower.contains("err_event_already_loaded") {
-        reasons.push("两个 FMOD 音频库定义了相同事件，常见原因是重复安装同一个 Mod。".to_string());
-        suggestions
-            .push("检查 Mods 文件夹中的重复 Mod，或禁用最近安装的音频相关 Mod。".to_string());
-        "Mod 音频资源发生冲突".to_string()
-    } else if lower.contains("err_output_init") || lower.contains("err_notready") {
-        reasons.push("Celeste 的音频设备或 FMOD 初始化失败。".to_string());
-        suggestions.push(
-            "重新插拔/切换音频设备，关闭 FMOD Live Update，必要时重启电脑或更新声卡驱动。"
-                .to_string(),
-        );
-        "音频设备初始化失败".to_string()
-    } else if lower.contains("0x887a0005")
-        || lower.contains("0x887a0006")
-        || lower.contains("0x887a0007")
-        || lower.contains("0x887a0020")
-        || lower.contains("present failed")
-    {
-        reasons.push("图形驱动、显存或渲染 API 进入了异常状态。".to_string());
-        suggestions.push("更新显卡驱动、关闭占用显存的程序，并尝试在 everest-launch.txt 中启用 --graphics OpenGL。".to_string());
-        "图形驱动或显存异常".to_string()
-    } else if lower.contains("argumentnullexception") && lower.contains("parameter 'method'") {
-        reasons.push("常见于旧版 Strawberry Jam 与新版 Factory Helper 的兼容问题。".to_string());
-        suggestions.push("优先更新 Strawberry Jam，并同时更新相关 Helper。".to_string());
-        "已知的 Mod 版本兼容问题".to_string()
-    } else if lower.contains("derived method 'onsquish'")
-        && lower.contains("respawningbouncejellyfish")
-    {
-        reasons.push("旧版 Maddie's Helping Hand 与新版 Bounce Helper 不兼容。".to_string());
-        suggestions.push("更新 Maddie's Helping Hand。".to_string());
-        "Helping Hand / Bounce Helper 版本冲突".to_string()
-    } else if lower.contains("source method is generic")
-        && lower.contains("generic hooks are not supported")
-    {
-        reasons.push("已知可能由旧版 Isa's Grab Bag 与新版 Everest 组合触发。".to_string());
-        suggestions.push("更新 Isa's Grab Bag。".to_string());
-        "旧版 Mod 与 Everest 不兼容".to_string()
-    } else if lower.contains("nullableattribute") {
-        reasons.push(
-            "某个 Mod 使用了较新 Everest/.NET 构建，但没有正确声明最低 Everest 版本。".to_string(),
-        );
-        suggestions
-            .push("更新所有相关 Mod 和 Everest；若均为最新版，请把报告发给 Mod 作者。".to_string());
-        "Mod 需要更新的 Everest 运行环境".to_string()
-    } else if lower.contains("rushhelper.playerextensions.player_beforedowntransition_il")
-        && lower.contains("ilcursor.gotonext")
-    {
-        reasons.push(
-            "RushHelper 1.1.x 在 Player.BeforeDownTransition 中只接受精确的 ldc.i4.5 指令编码；当前 Celeste/Everest IL 使用了不同但等价的整数加载形式，或已被其他 Hook 改写，因此 GotoNext 找不到目标并直接抛出异常。"
-                .to_string(),
-        );
-        suggestions.push(
-            "更新到包含 MatchLdcI4(Player.StRedDash) 修复的 RushHelper 1.2.0 或更高版本；如果暂时没有发布包，只能禁用 RushHelper，或使用作者源码构建新版。"
-                .to_string(),
-        );
-        "RushHelper 1.1.x 使用了过于严格的 IL 匹配".to_string()
-    } else if lower.contains("ilcursor.gotonext")
-        && (lower.contains("ilcontext.invoke")
-            || lower.contains("addilhook")
-            || lower.contains("ilhooktransaction")
-            || lower.contains("addilhooksbatch"))
-    {
-        let suspect = suspects
-            .first()
-            .map(|item| item.name.as_str())
-            .unwrap_or("某个 Mod");
-        reasons.push(format!(
-            "{suspect} 的 IL Hook 没有在当前 Everest 代码中找到预期指令，通常是 Mod 与当前 Everest/Loader 版本不兼容。"
-        ));
-        suggestions.push(format!(
-            "优先更新或暂时禁用 {suspect}；如果使用 EverestUltra，也可以先用 Legacy Loader 验证。"
-        ));
-        "Mod IL Hook 与当前 Loader 不兼容".to_string()
-    } else if lower.contains("invalidprogramexception") {
-        reasons.push("多个 Helper/Hook 之间可能发生了运行时补丁冲突。".to_string());
-        suggestions.push("更新所有 Mod；若仍崩溃，优先禁用 stacktrace 中出现的 Mod。".to_string());
-        "Mod Hook 或 Helper 冲突".to_string()
-    } else if lower.contains("dllnotfoundexception") {
-        reasons.push("Everest、Mod 或 Celeste 本体需要的原生库没有找到或无法加载。".to_string());
-        suggestions.push(
-            "校验/重装 Celeste 文件后重新安装 Everest；Linux 用户还应检查日志中点名的 so 依赖。"
-                .to_string(),
-        );
-        "缺少或无法加载原生库".to_string()
-    } else if lower.contains("unauthorizedaccessexception") {
-        reasons.push("游戏目录或其中某个文件没有足够的读写权限。".to_string());
-        suggestions.push(
-            "关闭占用文件的程序，检查目录权限；必要时校验游戏并重新安装 Everest。".to_string(),
-        );
-        "文件权限不足".to_string()
-    } else if lower.contains("too many open files") {
-        reasons
-            .push("系统允许 Celeste 同时打开的文件数已耗尽，常见于解压安装大量 Mod。".to_string());
-        suggestions
-            .push("尽量保留 Mod 的 zip 包而不是解压目录，并减少同时启用的 Mod。".to_string());
-        "打开的文件过多".to_string()
-    } else if lower.contains("disk space") || lower.contains("磁盘空间不足") {
-        reasons.push("Celeste、系统临时目录或游戏所在磁盘空间不足。".to_string());
-        suggestions.push("清理游戏盘和系统临时目录后重试。".to_string());
-        "磁盘空间不足".to_string()
-    } else if lower.contains("socketexception") || lower.contains("httprequestexception") {
-        reasons.push("网络连接、代理、防火墙或目标站点访问失败。".to_string());
-        suggestions.push("检查网络连接和防火墙，确认能访问 Mod 下载与更新站点。".to_string());
-        "网络连接异常".to_string()
-    } else if lower.contains("aggregateexception")
-        || lower.contains("destination is too short")
-        || lower.contains("yo, i heard you like everest")
-    {
-        reasons.push("该错误常见于过旧的 Everest 或 MonoMod 运行时。".to_string());
-        suggestions.push("更新 Everest 到当前分支的最新版。".to_string());
-        "Everest 版本可能过旧".to_string()
-    } else if lower.contains("pe image does not have metadata") {
-        reasons.push("orig/Celeste.exe 或 Celeste 本体文件可能已经损坏。".to_string());
-        suggestions.push("校验或重装 Celeste 本体，然后重新安装 Everest。".to_string());
-        "Celeste 本体文件可能损坏".to_string()
-    } else if lower.contains("can't find sprite") || lower.contains("celeste.parallax..ctor") {
-        reasons.push("地图引用的贴图/精灵不存在，或缺少提供该资源的依赖 Mod。".to_string());
-        suggestions.push("检查地图依赖是否完整，并更新日志中出现的地图或 Helper。".to_string());
-        "地图资源或依赖缺失".to_string()
-    } else if !suspects.is_empty() {
-        reasons.push("stacktrace 中出现了已安装 Mod 的程序集或命名空间。".to_string());
-        suggestions.push("优先更新或暂时禁用高置信度的可疑 Mod，再观察是否复现。".to_string());
-        "可能是 Mod 代码抛出的异常".to_string()
-    } else {
-        reasons
-            .push("没有匹配到已知错误模式，也没有从 stacktrace 中可靠定位到某个 Mod。".to_string());
-        suggestions.push(
-            "先更新 Everest 和全部 Mod；仍然崩溃时，把生成的 TXT 报告发给他人协助分析。"
-                .to_string(),
-        );
-        "暂时无法自动确定根因".to_string()
-    };
-
+fn reason_analysis(
+    text: &str,
+    suspects: &[CrashSuspect],
+    locale: &str,
+) -> (String, Vec<String>, Vec<String>) {
+    let rules = crash_rules::active_rules();
+    let outcome = rules.evaluate(text, suspects, locale);
+    let mut suggestions = outcome.suggestions;
     if suspects.iter().any(|suspect| suspect.update_available) {
-        suggestions.insert(0, "检测到可疑 Mod 有更新，建议先更新后重试。".to_string());
+        suggestions.insert(0, rules.messages(locale).update_available.clone());
     }
-    (summary, reasons, suggestions)
+    (outcome.summary, outcome.reasons, suggestions)
+}
+
+/// Puts broken dependencies ahead of the rule's reasons: Everest skips the
+/// dependent Mod, which is a more direct cause than any matched pattern.
+fn add_dependency_reasons(
+    issues: &[dependency_check::DependencyIssue],
+    locale: &str,
+    reasons: &mut Vec<String>,
+    suggestions: &mut Vec<String>,
+) {
+    if issues.is_empty() {
+        return;
+    }
+    let text = report_strings(locale);
+    let dependency_reasons = issues.iter().take(MAX_DEPENDENCY_REASONS).map(|issue| {
+        let template = match issue.problem {
+            dependency_check::DependencyProblem::Missing => text.dependency_missing,
+            dependency_check::DependencyProblem::Disabled => text.dependency_disabled,
+            dependency_check::DependencyProblem::TooOld => text.dependency_too_old,
+            dependency_check::DependencyProblem::WrongMajor => text.dependency_wrong_major,
+        };
+        template
+            .replace("{dependent}", &issue.dependent)
+            .replace("{dependency}", &issue.dependency)
+            .replace("{version}", &issue.required_version)
+            .replace(
+                "{installed}",
+                issue.installed_version.as_deref().unwrap_or(text.unknown),
+            )
+    });
+    reasons.splice(0..0, dependency_reasons);
+    suggestions.insert(0, text.dependency_fix.to_string());
 }
 
 fn is_everest_ultra(game_path: &Path, text: &str) -> bool {
@@ -865,83 +895,371 @@ fn is_everest_ultra(game_path: &Path, text: &str) -> bool {
     everest::is_everest_ultra(game_path)
 }
 
-fn report_directory() -> anyhow::Result<PathBuf> {
+pub(super) fn report_directory() -> anyhow::Result<PathBuf> {
     let root = dirs::cache_dir()
         .or_else(dirs::data_local_dir)
         .context("Failed to find a cache directory")?;
     Ok(root.join("CeleMod").join("crash-reports"))
 }
 
+/// Labels of the TXT report. Rule texts are localized by the rule file.
+struct ReportStrings {
+    title: &'static str,
+    fingerprint: &'static str,
+    log_modified: &'static str,
+    everest_version: &'static str,
+    unknown: &'static str,
+    yes: &'static str,
+    no: &'static str,
+    exception: &'static str,
+    summary: &'static str,
+    reasons: &'static str,
+    suggestions: &'static str,
+    suspects: &'static str,
+    no_suspects: &'static str,
+    update_to: &'static str,
+    latest: &'static str,
+    confidence: &'static str,
+    file: &'static str,
+    evidence: &'static str,
+    dependents: &'static str,
+    optional: &'static str,
+    log_sources: &'static str,
+    main_log: &'static str,
+    error_log: &'static str,
+    not_found: &'static str,
+    ask_for_help: &'static str,
+    log_excerpt: &'static str,
+    error_excerpt: &'static str,
+    no_error_log: &'static str,
+    unknown_exception: &'static str,
+    comma: &'static str,
+    /// Templates with `{dependent}`, `{dependency}`, `{version}` and `{installed}`.
+    dependency_missing: &'static str,
+    dependency_disabled: &'static str,
+    dependency_too_old: &'static str,
+    dependency_wrong_major: &'static str,
+    dependency_fix: &'static str,
+}
+
+const ZH_CN: ReportStrings = ReportStrings {
+    title: "CeleMod Everest 崩溃自动分析报告",
+    fingerprint: "崩溃标识",
+    log_modified: "日志更新时间 (Unix ms)",
+    everest_version: "Everest 版本",
+    unknown: "未知",
+    yes: "是",
+    no: "否",
+    exception: "异常",
+    summary: "结论",
+    reasons: "可能原因",
+    suggestions: "建议",
+    suspects: "可能相关的 Mod",
+    no_suspects: "未从 stacktrace 中可靠识别到具体 Mod",
+    update_to: "可更新到",
+    latest: "最新版",
+    confidence: "置信度",
+    file: "文件",
+    evidence: "依据",
+    dependents: "被这些 Mod 依赖",
+    optional: "可选",
+    log_sources: "日志来源",
+    main_log: "主日志",
+    error_log: "错误日志",
+    not_found: "未找到",
+    ask_for_help: "如果要向别人求助，请直接发送本 TXT 文件，并补充崩溃前正在做什么。",
+    log_excerpt: "主日志：最近一次崩溃片段",
+    error_excerpt: "errorLog：当次崩溃片段",
+    no_error_log: "未找到 errorLog.txt / error_log.txt。",
+    unknown_exception: "未能识别异常类型",
+    comma: "，",
+    dependency_missing: "{dependent} 依赖的 {dependency} {version} 未安装。",
+    dependency_disabled: "{dependent} 依赖的 {dependency} 已安装但被禁用。",
+    dependency_too_old: "{dependent} 需要 {dependency} {version} 或更新版本，当前安装的是 {installed}。",
+    dependency_wrong_major: "{dependent} 需要 {dependency} {version}，但当前安装的 {installed} 是更新的主版本，{dependent} 无法使用。",
+    dependency_fix: "先修复上述 Mod 依赖：启用或下载缺少的依赖后重启。",
+};
+
+const EN: ReportStrings = ReportStrings {
+    title: "CeleMod Everest crash analysis report",
+    fingerprint: "Crash ID",
+    log_modified: "Log updated at (Unix ms)",
+    everest_version: "Everest version",
+    unknown: "unknown",
+    yes: "yes",
+    no: "no",
+    exception: "Exception",
+    summary: "Conclusion",
+    reasons: "Possible causes",
+    suggestions: "Suggestions",
+    suspects: "Possibly related Mods",
+    no_suspects: "No specific Mod could be reliably identified from the stacktrace",
+    update_to: "update to",
+    latest: "the latest version",
+    confidence: "confidence",
+    file: "file",
+    evidence: "Evidence",
+    dependents: "Required by",
+    optional: "optional",
+    log_sources: "Log sources",
+    main_log: "Main log",
+    error_log: "Error log",
+    not_found: "not found",
+    ask_for_help: "If you ask someone for help, send this TXT file as is and describe what you were doing before the crash.",
+    log_excerpt: "Main log: latest crash excerpt",
+    error_excerpt: "errorLog: excerpt for this crash",
+    no_error_log: "No errorLog.txt / error_log.txt found.",
+    unknown_exception: "Exception type not recognized",
+    comma: ", ",
+    dependency_missing: "{dependent} requires {dependency} {version}, which is not installed.",
+    dependency_disabled: "{dependent} requires {dependency}, which is installed but disabled.",
+    dependency_too_old: "{dependent} requires {dependency} {version} or newer, but {installed} is installed.",
+    dependency_wrong_major: "{dependent} requires {dependency} {version}, but the installed {installed} is a newer major version that {dependent} cannot use.",
+    dependency_fix: "Fix the Mod dependencies above first: enable or download what is missing, then restart.",
+};
+
+const RU_RU: ReportStrings = ReportStrings {
+    title: "Автоматический анализ сбоя Everest от CeleMod",
+    fingerprint: "Идентификатор сбоя",
+    log_modified: "Время изменения лога (Unix ms)",
+    everest_version: "Версия Everest",
+    unknown: "неизвестно",
+    yes: "да",
+    no: "нет",
+    exception: "Исключение",
+    summary: "Вывод",
+    reasons: "Возможные причины",
+    suggestions: "Рекомендации",
+    suspects: "Возможно связанные моды",
+    no_suspects: "По stacktrace не удалось надёжно определить конкретный мод",
+    update_to: "можно обновить до",
+    latest: "последней версии",
+    confidence: "уверенность",
+    file: "файл",
+    evidence: "Основание",
+    dependents: "От него зависят",
+    optional: "необязательно",
+    log_sources: "Источники логов",
+    main_log: "Основной лог",
+    error_log: "Лог ошибок",
+    not_found: "не найден",
+    ask_for_help: "Если просите помощи, отправьте этот TXT-файл целиком и опишите, что вы делали перед сбоем.",
+    log_excerpt: "Основной лог: фрагмент последнего сбоя",
+    error_excerpt: "errorLog: фрагмент этого сбоя",
+    no_error_log: "errorLog.txt / error_log.txt не найден.",
+    unknown_exception: "Тип исключения не распознан",
+    comma: ", ",
+    dependency_missing: "{dependent} требует {dependency} {version}, но он не установлен.",
+    dependency_disabled: "{dependent} требует {dependency}, но он установлен и отключён.",
+    dependency_too_old: "{dependent} требует {dependency} {version} или новее, а установлена версия {installed}.",
+    dependency_wrong_major: "{dependent} требует {dependency} {version}, но установленная версия {installed} относится к более новой основной версии, которую {dependent} не может использовать.",
+    dependency_fix: "Сначала исправьте зависимости выше: включите или скачайте недостающие моды и перезапустите игру.",
+};
+
+const PT_BR: ReportStrings = ReportStrings {
+    title: "Relatório automático de falha do Everest do CeleMod",
+    fingerprint: "ID da falha",
+    log_modified: "Log atualizado em (Unix ms)",
+    everest_version: "Versão do Everest",
+    unknown: "desconhecida",
+    yes: "sim",
+    no: "não",
+    exception: "Exceção",
+    summary: "Conclusão",
+    reasons: "Possíveis causas",
+    suggestions: "Sugestões",
+    suspects: "Mods possivelmente relacionados",
+    no_suspects: "Nenhum Mod específico pôde ser identificado com segurança pelo stacktrace",
+    update_to: "pode ser atualizado para",
+    latest: "a versão mais recente",
+    confidence: "confiança",
+    file: "arquivo",
+    evidence: "Evidência",
+    dependents: "Necessário para",
+    optional: "opcional",
+    log_sources: "Origem dos logs",
+    main_log: "Log principal",
+    error_log: "Log de erros",
+    not_found: "não encontrado",
+    ask_for_help: "Se for pedir ajuda, envie este arquivo TXT como está e conte o que você estava fazendo antes da falha.",
+    log_excerpt: "Log principal: trecho da falha mais recente",
+    error_excerpt: "errorLog: trecho desta falha",
+    no_error_log: "errorLog.txt / error_log.txt não encontrado.",
+    unknown_exception: "Tipo de exceção não reconhecido",
+    comma: ", ",
+    dependency_missing: "{dependent} requer {dependency} {version}, que não está instalado.",
+    dependency_disabled: "{dependent} requer {dependency}, que está instalado mas desativado.",
+    dependency_too_old: "{dependent} requer {dependency} {version} ou mais recente, mas {installed} está instalado.",
+    dependency_wrong_major: "{dependent} requer {dependency} {version}, mas o {installed} instalado é uma versão principal mais nova que {dependent} não consegue usar.",
+    dependency_fix: "Corrija primeiro as dependências acima: ative ou baixe o que falta e reinicie.",
+};
+
+const FR_FR: ReportStrings = ReportStrings {
+    title: "Rapport d'analyse automatique de plantage Everest par CeleMod",
+    fingerprint: "Identifiant du plantage",
+    log_modified: "Journal mis à jour le (Unix ms)",
+    everest_version: "Version d'Everest",
+    unknown: "inconnue",
+    yes: "oui",
+    no: "non",
+    exception: "Exception",
+    summary: "Conclusion",
+    reasons: "Causes possibles",
+    suggestions: "Suggestions",
+    suspects: "Mods potentiellement concernés",
+    no_suspects: "Aucun Mod précis n'a pu être identifié de façon fiable dans la stacktrace",
+    update_to: "mise à jour disponible vers",
+    latest: "la dernière version",
+    confidence: "confiance",
+    file: "fichier",
+    evidence: "Indice",
+    dependents: "Requis par",
+    optional: "facultatif",
+    log_sources: "Sources des journaux",
+    main_log: "Journal principal",
+    error_log: "Journal d'erreurs",
+    not_found: "introuvable",
+    ask_for_help: "Pour demander de l'aide, envoyez ce fichier TXT tel quel et décrivez ce que vous faisiez avant le plantage.",
+    log_excerpt: "Journal principal : extrait du dernier plantage",
+    error_excerpt: "errorLog : extrait de ce plantage",
+    no_error_log: "errorLog.txt / error_log.txt introuvable.",
+    unknown_exception: "Type d'exception non reconnu",
+    comma: ", ",
+    dependency_missing: "{dependent} nécessite {dependency} {version}, qui n'est pas installé.",
+    dependency_disabled: "{dependent} nécessite {dependency}, qui est installé mais désactivé.",
+    dependency_too_old: "{dependent} nécessite {dependency} {version} ou plus récent, mais {installed} est installé.",
+    dependency_wrong_major: "{dependent} nécessite {dependency} {version}, mais la version {installed} installée est une version majeure plus récente que {dependent} ne peut pas utiliser.",
+    dependency_fix: "Corrigez d'abord les dépendances ci-dessus : activez ou téléchargez ce qui manque, puis redémarrez.",
+};
+
+const DE_DE: ReportStrings = ReportStrings {
+    title: "CeleMod: automatische Analyse des Everest-Absturzes",
+    fingerprint: "Absturzkennung",
+    log_modified: "Log aktualisiert (Unix ms)",
+    everest_version: "Everest-Version",
+    unknown: "unbekannt",
+    yes: "ja",
+    no: "nein",
+    exception: "Ausnahme",
+    summary: "Ergebnis",
+    reasons: "Mögliche Ursachen",
+    suggestions: "Empfehlungen",
+    suspects: "Möglicherweise beteiligte Mods",
+    no_suspects: "Aus dem Stacktrace ließ sich kein bestimmter Mod zuverlässig ermitteln",
+    update_to: "aktualisierbar auf",
+    latest: "die neueste Version",
+    confidence: "Wahrscheinlichkeit",
+    file: "Datei",
+    evidence: "Hinweis",
+    dependents: "Benötigt von",
+    optional: "optional",
+    log_sources: "Log-Quellen",
+    main_log: "Haupt-Log",
+    error_log: "Fehler-Log",
+    not_found: "nicht gefunden",
+    ask_for_help: "Wenn du um Hilfe bittest, sende diese TXT-Datei unverändert und beschreibe, was du vor dem Absturz getan hast.",
+    log_excerpt: "Haupt-Log: Ausschnitt des letzten Absturzes",
+    error_excerpt: "errorLog: Ausschnitt dieses Absturzes",
+    no_error_log: "errorLog.txt / error_log.txt nicht gefunden.",
+    unknown_exception: "Ausnahmetyp nicht erkannt",
+    comma: ", ",
+    dependency_missing: "{dependent} benötigt {dependency} {version}, das nicht installiert ist.",
+    dependency_disabled: "{dependent} benötigt {dependency}, das installiert, aber deaktiviert ist.",
+    dependency_too_old: "{dependent} benötigt {dependency} {version} oder neuer, installiert ist aber {installed}.",
+    dependency_wrong_major: "{dependent} benötigt {dependency} {version}, installiert ist aber {installed}, eine neuere Hauptversion, die {dependent} nicht verwenden kann.",
+    dependency_fix: "Behebe zuerst die obigen Abhängigkeiten: aktiviere oder lade Fehlendes herunter und starte neu.",
+};
+
+fn report_strings(locale: &str) -> &'static ReportStrings {
+    match locale {
+        "zh-CN" => &ZH_CN,
+        "ru-RU" => &RU_RU,
+        "pt-BR" => &PT_BR,
+        "fr-FR" => &FR_FR,
+        "de-DE" => &DE_DE,
+        _ => &EN,
+    }
+}
+
 fn report_text(
     analysis: &CrashAnalysis,
     log_excerpt: &str,
     error_log_text: Option<&str>,
 ) -> String {
+    let text = report_strings(&analysis.locale);
     let mut output = String::new();
-    output.push_str("CeleMod Everest 崩溃自动分析报告\n");
+    output.push_str(&format!("{}\n", text.title));
     output.push_str("================================\n\n");
-    output.push_str(&format!("崩溃标识: {}\n", analysis.fingerprint));
+    output.push_str(&format!("{}: {}\n", text.fingerprint, analysis.fingerprint));
     output.push_str(&format!(
-        "日志更新时间 (Unix ms): {}\n",
-        analysis.log_modified_at
+        "{}: {}\n",
+        text.log_modified, analysis.log_modified_at
     ));
     output.push_str(&format!(
-        "Everest 版本: {}\n",
+        "{}: {}\n",
+        text.everest_version,
         analysis
             .everest_version
             .map(|value| value.to_string())
-            .unwrap_or_else(|| "未知".to_string())
+            .unwrap_or_else(|| text.unknown.to_string())
     ));
     output.push_str(&format!(
         "EverestUltra: {}\n",
         if analysis.is_everest_ultra {
-            "是"
+            text.yes
         } else {
-            "否"
+            text.no
         }
     ));
-    output.push_str(&format!("异常: {}\n", analysis.exception));
-    output.push_str(&format!("结论: {}\n\n", analysis.summary));
+    output.push_str(&format!("{}: {}\n", text.exception, analysis.exception));
+    output.push_str(&format!("{}: {}\n\n", text.summary, analysis.summary));
 
-    output.push_str("可能原因:\n");
+    output.push_str(&format!("{}:\n", text.reasons));
     for reason in &analysis.reasons {
         output.push_str(&format!("- {reason}\n"));
     }
-    output.push_str("\n建议:\n");
+    output.push_str(&format!("\n{}:\n", text.suggestions));
     for suggestion in &analysis.suggestions {
         output.push_str(&format!("- {suggestion}\n"));
     }
 
-    output.push_str("\n可能相关的 Mod:\n");
+    output.push_str(&format!("\n{}:\n", text.suspects));
     if analysis.suspects.is_empty() {
-        output.push_str("- 未从 stacktrace 中可靠识别到具体 Mod\n");
+        output.push_str(&format!("- {}\n", text.no_suspects));
     } else {
         for suspect in &analysis.suspects {
             let update = if suspect.update_available {
                 format!(
-                    "，可更新到 {}",
-                    suspect.latest_version.as_deref().unwrap_or("最新版")
+                    "{}{} {}",
+                    text.comma,
+                    text.update_to,
+                    suspect.latest_version.as_deref().unwrap_or(text.latest)
                 )
             } else {
                 String::new()
             };
             output.push_str(&format!(
-                "- {} {} (置信度 {}%，文件 {}{})\n  依据: {}\n",
+                "- {} {} ({} {}%{}{} {}{})\n  {}: {}\n",
                 suspect.name,
                 suspect.installed_version,
+                text.confidence,
                 suspect.confidence,
+                text.comma,
+                text.file,
                 suspect.file,
                 update,
+                text.evidence,
                 suspect.evidence
             ));
             if !suspect.dependents.is_empty() {
                 output.push_str(&format!(
-                    "  被这些 Mod 依赖: {}\n",
+                    "  {}: {}\n",
+                    text.dependents,
                     suspect
                         .dependents
                         .iter()
                         .map(|item| if item.optional {
-                            format!("{} (可选)", item.name)
+                            format!("{} ({})", item.name, text.optional)
                         } else {
                             item.name.clone()
                         })
@@ -952,22 +1270,34 @@ fn report_text(
         }
     }
 
-    output.push_str("\n日志来源:\n");
-    output.push_str(&format!("- 主日志: {}\n", analysis.source_log));
+    output.push_str(&format!("\n{}:\n", text.log_sources));
+    output.push_str(&format!("- {}: {}\n", text.main_log, analysis.source_log));
+    output.push_str(&format!(
+        "- {}: {}\n",
+        text.error_log,
+        analysis.error_log.as_deref().unwrap_or(text.not_found)
+    ));
+    output.push_str(&format!("\n{}\n", text.ask_for_help));
     output.push_str(&format!(
-        "- 错误日志: {}\n",
-        analysis.error_log.as_deref().unwrap_or("未找到")
+        "\n\n================ {} ================\n\n",
+        text.log_excerpt
     ));
-    output.push_str("\n如果要向别人求助，请直接发送本 TXT 文件，并补充崩溃前正在做什么。\n");
-    output.push_str("\n\n================ 主日志：最近一次崩溃片段 ================\n\n");
     output.push_str(log_excerpt);
-    output.push_str("\n\n================ errorLog：当次崩溃片段 ================\n\n");
-    output.push_str(error_log_text.unwrap_or("未找到 errorLog.txt / error_log.txt。"));
+    output.push_str(&format!(
+        "\n\n================ {} ================\n\n",
+        text.error_excerpt
+    ));
+    output.push_str(error_log_text.unwrap_or(text.no_error_log));
     output
 }
 
-pub fn analyze_latest_crash(game_path: &str) -> anyhow::Result<Option<CrashAnalysis>> {
+/// `language` is a UI language such as `ru-RU`; see `crash_rules::crash_locale`.
+pub fn analyze_latest_crash(
+    game_path: &str,
+    language: &str,
+) -> anyhow::Result<Option<CrashAnalysis>> {
     let game_path = Path::new(game_path);
+    let locale = crash_rules::crash_locale(language);
     let records = collect_log_candidates(game_path);
     let error_log = latest_error_log(game_path);
     let latest_log = records.into_iter().max_by(|left, right| {
@@ -1011,10 +1341,24 @@ pub fn analyze_latest_crash(game_path: &str) -> anyhow::Result<Option<CrashAnaly
         return Ok(None);
     }
 
+    analyze_record(game_path, latest, error_log.as_ref(), locale, false).map(Some)
+}
+
+/// `historical` is set for a crash picked from the list: the Mods folder and
+/// launch snapshot describe the present, not that crash, so neither is
+/// compared against it, and the result is kept out of the crash history and
+/// the cache of the latest analysis.
+fn analyze_record(
+    game_path: &Path,
+    latest: CrashRecord,
+    error_log: Option<&(PathBuf, u64, String)>,
+    locale: &str,
+    historical: bool,
+) -> anyhow::Result<CrashAnalysis> {
     let fingerprint = format!("{}:{}", latest.crash_index, crash_signature(&latest),);
     let event_id = crash_event_id(&latest);
     let normalized_game_path = game_path.to_string_lossy().to_string();
-    let relevant_error_log = error_log.as_ref().filter(|(path, modified, _)| {
+    let relevant_error_log = error_log.filter(|(path, modified, _)| {
         latest.path == *path || latest.modified_at.abs_diff(*modified) <= 20 * 60 * 1000
     });
     let selected_error_text =
@@ -1028,22 +1372,37 @@ pub fn analyze_latest_crash(game_path: &str) -> anyhow::Result<Option<CrashAnaly
             format!("{:x}", hasher.finalize())[..16].to_string()
         },
     );
-    if let Some(cached) = ANALYSIS_CACHE.lock().as_ref()
+    let rules = crash_rules::active_rules();
+    if !historical
+        && let Some(cached) = ANALYSIS_CACHE.lock().as_ref()
         && cached.game_path == normalized_game_path
+        && Arc::ptr_eq(&cached.rules, &rules)
         && cached.fingerprint == fingerprint
         && cached.error_log_signature == error_log_signature
+        && cached.analysis.locale == locale
         && Path::new(&cached.analysis.report_path).is_file()
     {
-        return Ok(Some(cached.analysis.clone()));
+        return Ok(cached.analysis.clone());
     }
 
     let error_path = relevant_error_log.map(|(path, _, _)| path.to_string_lossy().to_string());
     let error_text = selected_error_text.as_deref();
     let combined_for_analysis = format!("{}\n{}", latest.excerpt, error_text.unwrap_or_default());
     let crash_body = latest_crash_body(&combined_for_analysis);
-    let suspects = analyze_suspects(game_path, &crash_body);
+    let changes_since_last_good = if historical {
+        None
+    } else {
+        launch_snapshot::diff_since_last_good(game_path)
+    };
+    let suspects = analyze_suspects(game_path, &crash_body, changes_since_last_good.as_ref());
+    let dependency_issues = if historical {
+        Vec::new()
+    } else {
+        dependency_check::check_game(game_path, &disabled_mod_files(game_path))
+    };
     let ultra = is_everest_ultra(game_path, &combined_for_analysis);
-    let (summary, reasons, mut suggestions) = reason_analysis(&crash_body, &suspects);
+    let (summary, mut reasons, mut suggestions) = reason_analysis(&crash_body, &suspects, locale);
+    add_dependency_reasons(&dependency_issues, locale, &mut reasons, &mut suggestions);
     if ultra
         && (crash_body.contains("ILHookTransaction")
             || crash_body.contains("Parallel startup scheduler"))
@@ -1051,35 +1410,43 @@ pub fn analyze_latest_crash(game_path: &str) -> anyhow::Result<Option<CrashAnaly
             .iter()
             .any(|suggestion| suggestion.contains("Legacy Loader"))
     {
-        suggestions.push(
-            "可使用 Legacy Loader 重启一次，排除 EverestUltra 加速加载器的兼容问题。".to_string(),
-        );
+        suggestions.push(rules.messages(locale).legacy_loader.clone());
     }
     let everest_version = everest::get_everest_version(&normalized_game_path);
     let source_log = latest.path.to_string_lossy().to_string();
     let mut analysis = CrashAnalysis {
         fingerprint: fingerprint.clone(),
         event_id,
+        signature: run_crash_signature(&latest),
         crash_index: latest.crash_index,
         log_modified_at: latest.modified_at,
         source_log,
         error_log: error_path,
         report_path: String::new(),
-        exception: exception_line(&crash_body),
+        exception: exception_line(&crash_body)
+            .unwrap_or(report_strings(locale).unknown_exception)
+            .to_string(),
         summary,
         reasons,
         suggestions,
         suspects,
+        dependency_issues,
+        stack: stack_trace::parse(&crash_body),
+        changes_since_last_good,
         everest_version,
         is_everest_ultra: ultra,
         excerpt: latest.excerpt.chars().take(40_000).collect(),
+        locale: locale.to_string(),
     };
 
     let report_dir = report_directory()?;
     fs::create_dir_all(&report_dir)?;
+    // A historical report must not replace the live one the user may have open.
     let report_path = report_dir.join(format!(
-        "CeleMod-Crash-{}-{}.txt",
-        latest.modified_at, latest.crash_index
+        "CeleMod-Crash-{}-{}{}.txt",
+        latest.modified_at,
+        latest.crash_index,
+        if historical { "-history" } else { "" }
     ));
     analysis.report_path = report_path.to_string_lossy().to_string();
     fs::write(
@@ -1087,14 +1454,128 @@ pub fn analyze_latest_crash(game_path: &str) -> anyhow::Result<Option<CrashAnaly
         report_text(&analysis, &latest.excerpt, error_text),
     )
     .with_context(|| format!("Failed to write {}", report_path.display()))?;
+    if historical {
+        return Ok(analysis);
+    }
+    crash_history::record_analysis(game_path, &analysis);
 
     *ANALYSIS_CACHE.lock() = Some(CachedAnalysis {
         game_path: normalized_game_path,
         fingerprint,
         error_log_signature,
+        rules,
         analysis: analysis.clone(),
     });
-    Ok(Some(analysis))
+    Ok(analysis)
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashListing {
+    /// Position in the list; pass it to `analyze_crash`.
+    pub index: usize,
+    pub fingerprint: String,
+    pub event_id: String,
+    pub source_log: String,
+    /// Sequence of the crash within its log.
+    pub crash_index: u64,
+    pub log_modified_at: u64,
+    /// `YYYY-MM-DD HH:MM:SS` as printed in the log, when it has a timestamp.
+    pub occurred_at: Option<String>,
+    pub exception: String,
+}
+
+/// Every crash in `log.txt`, the rotated logs, `CrashLogs` and the error
+/// logs, newest first and regardless of age.
+pub fn list_crashes(game_path: &str) -> Vec<CrashListing> {
+    collect_all_crashes(Path::new(game_path))
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let body = latest_crash_body(&record.excerpt);
+            let lines = body.lines().collect::<Vec<_>>();
+            CrashListing {
+                index,
+                fingerprint: format!("{}:{}", record.crash_index, crash_signature(record)),
+                event_id: crash_event_id(record),
+                source_log: record.path.to_string_lossy().to_string(),
+                crash_index: record.crash_index,
+                log_modified_at: record.modified_at,
+                occurred_at: error_section_time_key(&lines).map(
+                    |(year, month, day, hour, minute, second)| {
+                        format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02}")
+                    },
+                ),
+                exception: exception_line(&body).unwrap_or_default().to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Analyses entry `index` of `list_crashes`. `fingerprint`, when given, must
+/// still match, so a crash logged in between does not shift the selection.
+pub fn analyze_crash(
+    game_path: &str,
+    index: usize,
+    fingerprint: Option<&str>,
+    language: &str,
+) -> anyhow::Result<CrashAnalysis> {
+    let game_path = Path::new(game_path);
+    let record = collect_all_crashes(game_path)
+        .into_iter()
+        .nth(index)
+        .with_context(|| format!("Crash {index} was not found"))?;
+    if let Some(expected) = fingerprint
+        && format!("{}:{}", record.crash_index, crash_signature(&record)) != expected
+    {
+        bail!("The crash list has changed; refresh it and try again");
+    }
+    let error_log = if error_log_candidates(game_path).contains(&record.path) {
+        Some(read_error_log(record.path.clone()))
+    } else {
+        latest_error_log(game_path)
+    };
+    analyze_record(
+        game_path,
+        record,
+        error_log.as_ref(),
+        crash_rules::crash_locale(language),
+        true,
+    )
+}
+
+/// The analysis last returned by `analyze_latest_crash` for this game, which
+/// stays available after the crash is too old to be analysed again.
+pub fn last_analysis(game_path: &str) -> Option<CrashAnalysis> {
+    ANALYSIS_CACHE
+        .lock()
+        .as_ref()
+        .filter(|cached| cached.game_path == game_path)
+        .map(|cached| cached.analysis.clone())
+}
+
+/// Re-reads an analysis made elsewhere with the local rules and language,
+/// together with the full `error_log` that came with it, when there is one.
+/// Suspects are kept: the Mods they were matched against are not here.
+pub fn reinterpret(analysis: &mut CrashAnalysis, language: &str, error_log: Option<&str>) {
+    let locale = crash_rules::crash_locale(language);
+    let error_text = error_log
+        .map(|text| extract_error_section(text, Some(&analysis.excerpt)))
+        .unwrap_or_default();
+    let crash_body = latest_crash_body(&format!("{}\n{error_text}", analysis.excerpt));
+    let (summary, mut reasons, mut suggestions) =
+        reason_analysis(&crash_body, &analysis.suspects, locale);
+    add_dependency_reasons(
+        &analysis.dependency_issues,
+        locale,
+        &mut reasons,
+        &mut suggestions,
+    );
+    analysis.summary = summary;
+    analysis.reasons = reasons;
+    analysis.suggestions = suggestions;
+    analysis.stack = stack_trace::parse(&crash_body);
+    analysis.locale = locale.to_string();
 }
 
 pub fn reveal_report(path: &str) -> anyhow::Result<()> {
@@ -1154,6 +1635,36 @@ mod tests {
         fs::remove_dir_all(root).unwrap();
     }
 
+    #[test]
+    fn lists_every_crash_once_newest_first() {
+        let root = test_dir("list");
+        let first = "(08/04/2026 11:00:00) [Everest] [Error] [crit-error-handler] ENCOUNTERED A CRITICAL ERROR\nSystem.Exception: first\n  at Test.Mod.Run()";
+        let second = "(08/04/2026 12:00:00) [Everest] [Error] [crit-error-handler] ENCOUNTERED A CRITICAL ERROR\nSystem.OutOfMemoryException: second\n  at Test.Mod.Load()";
+        fs::write(root.join("log.txt"), format!("before\n{first}\n{second}\n")).unwrap();
+        fs::create_dir_all(root.join("CrashLogs")).unwrap();
+        fs::write(
+            root.join("CrashLogs").join("crash.txt"),
+            format!("{second}\n"),
+        )
+        .unwrap();
+
+        let crashes = list_crashes(&root.to_string_lossy());
+        assert_eq!(
+            crashes
+                .iter()
+                .map(|crash| (crash.index, crash.crash_index))
+                .collect::<Vec<_>>(),
+            [(0, 2), (1, 1)]
+        );
+        assert!(crashes[0].source_log.ends_with("log.txt"));
+        assert_eq!(
+            crashes[0].occurred_at.as_deref(),
+            Some("2026-08-04 12:00:00")
+        );
+        assert!(crashes[1].exception.contains("System.Exception: first"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
     #[test]
     fn only_analyzes_recent_settled_crashes() {
         let now = 10 * MAX_CRASH_AGE_MILLIS;
@@ -1165,7 +1676,8 @@ mod tests {
 
     #[test]
     fn recognizes_common_memory_failure() {
-        let (summary, reasons, suggestions) = reason_analysis("System.OutOfMemoryException", &[]);
+        let (summary, reasons, suggestions) =
+            reason_analysis("System.OutOfMemoryException", &[], "zh-CN");
         assert_eq!(summary, "资源不足导致崩溃");
         assert!(!reasons.is_empty());
         assert!(!suggestions.is_empty());
@@ -1197,6 +1709,14 @@ mod tests {
         };
         assert_eq!(crash_signature(&first), crash_signature(&rotated));
         assert_eq!(crash_event_id(&first), crash_event_id(&rotated));
+
+        let next_run = CrashRecord {
+            crash_index: 2,
+            excerpt: first.excerpt.replace("01/01/2026", "02/01/2026 10:00:00"),
+            ..first.clone()
+        };
+        assert_ne!(crash_signature(&first), crash_signature(&next_run));
+        assert_eq!(run_crash_signature(&first), run_crash_signature(&next_run));
     }
 
     #[test]
@@ -1228,10 +1748,19 @@ mod tests {
             at Celeste.Mod.MaxHelpingHand.Module.MaxHelpingHandModule.onModRegister()\n\
             at Celeste.Mod.RushHelper.PlayerExtensions.Load()";
         let rush = find_evidence(text, &["RushHelper".to_string()]).unwrap();
-        let helping_hand = find_evidence(text, &["MaxHelpingHand".to_string()]).unwrap();
         assert_eq!(rush.0, 100);
-        assert_eq!(helping_hand.0, 96);
         assert!(rush.1.contains("Failed loading startup mod RushHelper"));
+
+        let chains = stack_trace::parse(text);
+        let owners = HashMap::new();
+        let helping_hand =
+            frame_evidence(&chains, &["maxhelpinghand".to_string()], &owners).unwrap();
+        assert!(helping_hand.0 < rush.0);
+        assert_eq!(
+            helping_hand.1,
+            "at Celeste.Mod.MaxHelpingHand.Module.MaxHelpingHandModule.onModRegister"
+        );
+        assert!(find_evidence(text, &["MaxHelpingHand".to_string()]).is_none());
     }
 
     #[test]
@@ -1249,7 +1778,7 @@ mod tests {
         let text = "System.Exception: Failed loading startup mod RushHelper 1.1.1\n\
             at Celeste.Mod.MaxHelpingHand.Module.MaxHelpingHandModule.onModRegister()\n\
             at Celeste.Mod.RushHelper.PlayerExtensions.Load()";
-        let suspects = analyze_suspects(&root, text);
+        let suspects = analyze_suspects(&root, text, None);
         assert_eq!(
             suspects.first().map(|suspect| suspect.name.as_str()),
             Some("RushHelper")
@@ -1258,10 +1787,39 @@ mod tests {
             suspects.first().map(|suspect| suspect.confidence),
             Some(100)
         );
+        let helping_hand = suspects
+            .iter()
+            .find(|suspect| suspect.name == "MaxHelpingHand")
+            .map(|suspect| suspect.confidence)
+            .unwrap();
+
+        let changes = launch_snapshot::LaunchDiff {
+            updated: ["RushHelper", "MaxHelpingHand"]
+                .map(|name| launch_snapshot::ChangedMod {
+                    name: name.to_string(),
+                    from: "1.0.0".to_string(),
+                    to: "1.1.1".to_string(),
+                    rebuilt: false,
+                })
+                .to_vec(),
+            ..Default::default()
+        };
+        let boosted = analyze_suspects(&root, text, Some(&changes));
+        let confidence = |name: &str| {
+            boosted
+                .iter()
+                .find(|suspect| suspect.name == name)
+                .map(|suspect| suspect.confidence)
+        };
+        assert_eq!(confidence("RushHelper"), Some(100));
+        assert_eq!(
+            confidence("MaxHelpingHand"),
+            Some((helping_hand + CHANGED_SUSPECT_BOOST).min(100))
+        );
         assert!(
-            suspects
+            boosted
                 .iter()
-                .any(|suspect| suspect.name == "MaxHelpingHand")
+                .all(|suspect| suspect.changed_since_last_good)
         );
         fs::remove_dir_all(root).unwrap();
     }
@@ -1279,10 +1837,12 @@ mod tests {
             confidence: 100,
             evidence: "Failed loading startup mod RushHelper".to_string(),
             dependents: Vec::new(),
+            changed_since_last_good: false,
         };
         let (summary, reasons, suggestions) = reason_analysis(
             "MonoMod.Cil.ILCursor.GotoNext\nMonoMod.Cil.ILContext.Invoke\nCeleste.Mod.RushHelper.PlayerExtensions.Load",
             &[suspect],
+            "zh-CN",
         );
         assert_eq!(summary, "Mod IL Hook 与当前 Loader 不兼容");
         assert!(reasons.iter().any(|reason| reason.contains("RushHelper")));
@@ -1306,10 +1866,12 @@ mod tests {
             confidence: 100,
             evidence: "Failed loading startup mod RushHelper 1.1.1".to_string(),
             dependents: Vec::new(),
+            changed_since_last_good: false,
         };
         let (summary, reasons, suggestions) = reason_analysis(
             "MonoMod.Cil.ILCursor.GotoNext\nCeleste.Mod.RushHelper.PlayerExtensions.Player_BeforeDownTransition_il",
             &[suspect],
+            "zh-CN",
         );
         assert_eq!(summary, "RushHelper 1.1.x 使用了过于严格的 IL 匹配");
         assert!(reasons.iter().any(|reason| reason.contains("ldc.i4.5")));
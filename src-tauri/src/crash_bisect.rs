@@ -0,0 +1,465 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+
+use super::{
+    IpcEvent, LocalMod, blacklist, crash_analysis, get_installed_mods_sync, is_celeste_running,
+    send_event, start_game_directly_with_loader_impl, stop_celeste_for_restart,
+};
+
+const SESSION_FILE: &str = "celemod_bisect.json";
+/// Written like any other profile so the blacklist header and dependency
+/// handling stay the same as a normal profile switch.
+const BISECT_PROFILE: &str = "CeleMod Bisect";
+
+lazy_static! {
+    static ref RUNNING: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectRound {
+    pub tested: Vec<String>,
+    pub reproduced: bool,
+    /// The game crashed, but with a different signature. Such a run counts as
+    /// not reproduced.
+    #[serde(default)]
+    pub other_crash: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BisectSession {
+    /// `None` when there was no blacklist.txt before the session started.
+    original_blacklist: Option<String>,
+    always_on: Vec<String>,
+    signature: String,
+    /// Ordered so that every Mod comes after its dependencies.
+    candidates: Vec<String>,
+    history: Vec<BisectRound>,
+    /// Set while a test run is in progress, so a run interrupted by closing
+    /// CeleMod is still evaluated on the next step.
+    launched_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectCulprit {
+    pub name: String,
+    pub file: Option<String>,
+    /// False when no test run with this Mod enabled ever reproduced the crash.
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BisectStatus {
+    pub round: usize,
+    pub candidates: Vec<String>,
+    pub remaining_rounds: u32,
+    pub running: bool,
+    pub history: Vec<BisectRound>,
+    pub culprit: Option<BisectCulprit>,
+}
+
+fn session_path(game_path: &str) -> PathBuf {
+    Path::new(game_path).join(SESSION_FILE)
+}
+
+fn blacklist_path(game_path: &str) -> PathBuf {
+    Path::new(game_path).join("Mods").join("blacklist.txt")
+}
+
+fn load_session(game_path: &str) -> anyhow::Result<Option<BisectSession>> {
+    match fs::read_to_string(session_path(game_path)) {
+        Ok(contents) => Ok(Some(
+            serde_json::from_str(&contents).context("Failed to read the bisection session")?,
+        )),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn save_session(game_path: &str, session: &BisectSession) -> anyhow::Result<()> {
+    let path = session_path(game_path);
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_vec_pretty(session)?)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+/// Orders Mods so that each one follows the Mods it requires. Any prefix of
+/// the result can then be enabled without pulling in a later candidate.
+fn dependency_order(installed: &[LocalMod], names: &[String]) -> Vec<String> {
+    fn visit(
+        key: &str,
+        by_name: &HashMap<String, &LocalMod>,
+        wanted: &HashSet<String>,
+        visited: &mut HashSet<String>,
+        ordered: &mut Vec<String>,
+    ) {
+        if !visited.insert(key.to_owned()) {
+            return;
+        }
+        let Some(mod_info) = by_name.get(key) else {
+            return;
+        };
+        let mut deps = mod_info
+            .deps
+            .iter()
+            .filter(|dep| !dep.optional)
+            .map(|dep| dep.name.to_ascii_lowercase())
+            .filter(|dep| wanted.contains(dep))
+            .collect::<Vec<_>>();
+        deps.sort();
+        for dep in deps {
+            visit(&dep, by_name, wanted, visited, ordered);
+        }
+        ordered.push(mod_info.name.clone());
+    }
+
+    let by_name = installed
+        .iter()
+        .map(|mod_info| (mod_info.name.to_ascii_lowercase(), mod_info))
+        .collect::<HashMap<_, _>>();
+    let mut keys = names
+        .iter()
+        .map(|name| name.to_ascii_lowercase())
+        .filter(|name| by_name.contains_key(name))
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+    let wanted = keys.iter().cloned().collect::<HashSet<_>>();
+    let mut visited = HashSet::new();
+    let mut ordered = Vec::new();
+    for key in &keys {
+        visit(key, &by_name, &wanted, &mut visited, &mut ordered);
+    }
+    ordered
+}
+
+fn tested_half(candidates: &[String]) -> &[String] {
+    &candidates[..candidates.len() / 2]
+}
+
+/// Keeps the tested half when the crash came back and the other half when it
+/// did not.
+fn narrow(session: &mut BisectSession, reproduced: bool, other_crash: bool) {
+    let half = session.candidates.len() / 2;
+    let tested = session.candidates[..half].to_vec();
+    if reproduced {
+        session.candidates.truncate(half);
+    } else {
+        session.candidates.drain(..half);
+    }
+    session.history.push(BisectRound {
+        tested,
+        reproduced,
+        other_crash,
+    });
+}
+
+fn status(game_path: &str, session: &BisectSession) -> BisectStatus {
+    let remaining = session.candidates.len();
+    let culprit = (remaining == 1).then(|| {
+        let name = session.candidates[0].clone();
+        let file = get_installed_mods_sync(format!("{game_path}/Mods"))
+            .into_iter()
+            .find(|mod_info| mod_info.name.eq_ignore_ascii_case(&name))
+            .map(|mod_info| mod_info.file);
+        let confirmed = session.history.iter().any(|round| {
+            round.reproduced
+                && round
+                    .tested
+                    .iter()
+                    .any(|tested| tested.eq_ignore_ascii_case(&name))
+        });
+        BisectCulprit {
+            name,
+            file,
+            confirmed,
+        }
+    });
+    BisectStatus {
+        round: session.history.len(),
+        candidates: session.candidates.clone(),
+        remaining_rounds: remaining.max(1).next_power_of_two().trailing_zeros(),
+        running: session.launched_at.is_some(),
+        history: session.history.clone(),
+        culprit,
+    }
+}
+
+pub fn get_status(game_path: &str) -> anyhow::Result<Option<BisectStatus>> {
+    Ok(load_session(game_path)?.map(|session| status(game_path, &session)))
+}
+
+/// Starts a session from the latest crash and the Mods enabled right now.
+/// Always-on Mods and their dependencies stay enabled in every run.
+pub fn start(game_path: &str, always_on_mods: &[String]) -> anyhow::Result<BisectStatus> {
+    if load_session(game_path)?.is_some() {
+        bail!("A crash bisection is already in progress");
+    }
+    let probe = crash_analysis::latest_crash_probe(Path::new(game_path))
+        .context("No crash was found in the Everest logs")?;
+
+    let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
+    let blacklisted = blacklist::direct_blacklisted_files(game_path);
+    let pinned = blacklist::expand_installed_dependencies(&installed, always_on_mods)
+        .into_iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    let enabled = installed
+        .iter()
+        .filter(|mod_info| !blacklisted.contains(&mod_info.file.to_ascii_lowercase()))
+        .filter(|mod_info| !pinned.contains(&mod_info.name.to_ascii_lowercase()))
+        .map(|mod_info| mod_info.name.clone())
+        .collect::<Vec<_>>();
+    let candidates = dependency_order(&installed, &enabled);
+    if candidates.is_empty() {
+        bail!("There are no enabled Mods to bisect");
+    }
+
+    let original_blacklist = match fs::read_to_string(blacklist_path(game_path)) {
+        Ok(contents) => Some(contents),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error).context("Failed to read blacklist.txt"),
+    };
+    let session = BisectSession {
+        original_blacklist,
+        always_on: always_on_mods.to_vec(),
+        signature: probe.signature,
+        candidates,
+        history: Vec::new(),
+        launched_at: None,
+    };
+    save_session(game_path, &session)?;
+    Ok(status(game_path, &session))
+}
+
+/// Checks the logs after a test run and narrows the candidates.
+fn evaluate_run(game_path: &str, session: &mut BisectSession) -> Option<BisectRound> {
+    let launched_at = session.launched_at.take()?;
+    let probe = crash_analysis::latest_crash_probe(Path::new(game_path))
+        .filter(|probe| probe.modified_at >= launched_at);
+    let reproduced = probe
+        .as_ref()
+        .is_some_and(|probe| probe.signature == session.signature);
+    narrow(session, reproduced, probe.is_some() && !reproduced);
+    session.history.last().cloned()
+}
+
+fn prepare_run(game_path: &str, session: &mut BisectSession) -> anyhow::Result<Vec<String>> {
+    let tested = tested_half(&session.candidates).to_vec();
+    let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
+    let selected = tested
+        .iter()
+        .chain(&session.always_on)
+        .cloned()
+        .collect::<Vec<_>>();
+    let enabled = blacklist::expand_installed_dependencies(&installed, &selected);
+    blacklist::set_mod_profile_mods(game_path, BISECT_PROFILE, enabled)?;
+    blacklist::apply_mod_blacklist_profiles(
+        game_path,
+        &[BISECT_PROFILE.to_owned()],
+        &session.always_on,
+    )?;
+    Ok(tested)
+}
+
+fn run_step_sync(game_path: &str, channel: &Channel<IpcEvent>) -> anyhow::Result<()> {
+    let game = Path::new(game_path);
+    let mut session = load_session(game_path)?.context("No crash bisection is in progress")?;
+    // The logs of a run that has not ended yet would read as "no crash".
+    if session.launched_at.is_some() && is_celeste_running(game) {
+        bail!("Exit Celeste before continuing the bisection");
+    }
+    if let Some(round) = evaluate_run(game_path, &mut session) {
+        save_session(game_path, &session)?;
+        send_event(
+            channel,
+            vec![serde_json::json!("result"), serde_json::json!(round)],
+        );
+    }
+    if session.candidates.len() <= 1 {
+        send_event(
+            channel,
+            vec![
+                serde_json::json!("status"),
+                serde_json::json!(status(game_path, &session)),
+            ],
+        );
+        return Ok(());
+    }
+
+    stop_celeste_for_restart(game)?;
+    let tested = prepare_run(game_path, &mut session)?;
+    let launched_at = crash_analysis::now_millis();
+    let mut child = match start_game_directly_with_loader_impl(game_path.to_owned(), false, false) {
+        Ok(child) => child,
+        Err(error) => {
+            session.launched_at = None;
+            save_session(game_path, &session)?;
+            return Err(error);
+        }
+    };
+    // Only a run that actually started may be evaluated.
+    session.launched_at = Some(launched_at);
+    save_session(game_path, &session)?;
+    send_event(
+        channel,
+        vec![serde_json::json!("launched"), serde_json::json!(tested)],
+    );
+    child.wait()?;
+    // Steam copies relaunch themselves, so the spawned process may exit first.
+    while is_celeste_running(game) {
+        std::thread::sleep(Duration::from_secs(2));
+    }
+
+    if let Some(round) = evaluate_run(game_path, &mut session) {
+        save_session(game_path, &session)?;
+        send_event(
+            channel,
+            vec![serde_json::json!("result"), serde_json::json!(round)],
+        );
+    }
+    send_event(
+        channel,
+        vec![
+            serde_json::json!("status"),
+            serde_json::json!(status(game_path, &session)),
+        ],
+    );
+    Ok(())
+}
+
+/// Runs one round in the background: enable half of the candidates, launch
+/// Celeste, wait for it to exit and compare the newest crash. Events are
+/// `["launched", [mod...]]`, `["result", round]`, `["status", status]` and
+/// `["error", message]`.
+pub fn run_step(game_path: String, channel: Channel<IpcEvent>) -> anyhow::Result<()> {
+    let key = PathBuf::from(&game_path);
+    if !RUNNING.lock().insert(key.clone()) {
+        bail!("A bisection run is already in progress");
+    }
+    std::thread::spawn(move || {
+        if let Err(error) = run_step_sync(&game_path, &channel) {
+            send_event(
+                &channel,
+                vec![
+                    serde_json::json!("error"),
+                    serde_json::json!(format!("{error:#}")),
+                ],
+            );
+        }
+        RUNNING.lock().remove(&key);
+    });
+    Ok(())
+}
+
+/// Restores blacklist.txt exactly as it was before the session and removes
+/// the temporary profile. Used both when the culprit is found and on abort.
+pub fn finish(game_path: &str) -> anyhow::Result<()> {
+    if RUNNING.lock().contains(Path::new(game_path)) {
+        bail!("Exit Celeste before ending the bisection");
+    }
+    let Some(session) = load_session(game_path)? else {
+        return Ok(());
+    };
+    match &session.original_blacklist {
+        Some(contents) => fs::write(blacklist_path(game_path), contents)?,
+        None => match fs::remove_file(blacklist_path(game_path)) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error.into()),
+            _ => {}
+        },
+    }
+    if blacklist::get_mod_blacklist_profiles(game_path)
+        .iter()
+        .any(|profile| profile.name.eq_ignore_ascii_case(BISECT_PROFILE))
+    {
+        blacklist::remove_mod_blacklist_profile(game_path, BISECT_PROFILE)
+            .context("Failed to remove the bisection profile")?;
+    }
+    fs::remove_file(session_path(game_path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ModDependency;
+    use super::*;
+
+    fn local_mod(name: &str, deps: &[&str]) -> LocalMod {
+        LocalMod {
+            game_banana_id: 0,
+            name: name.to_owned(),
+            deps: deps
+                .iter()
+                .map(|dep| ModDependency {
+                    name: (*dep).to_owned(),
+                    version: "1.0.0".to_owned(),
+                    optional: false,
+                })
+                .collect(),
+            version: "1.0.0".to_owned(),
+            file: format!("{name}.zip"),
+            size: 0,
+            modified_at: 0,
+        }
+    }
+
+    #[test]
+    fn bisection_keeps_dependencies_together_and_finds_the_culprit() {
+        let installed = vec![
+            local_mod("Alpha", &["Zeta"]),
+            local_mod("Beta", &[]),
+            local_mod("Gamma", &["Beta", "Everest"]),
+            local_mod("Zeta", &[]),
+            local_mod("Omega", &[]),
+        ];
+        let names = installed
+            .iter()
+            .map(|mod_info| mod_info.name.clone())
+            .collect::<Vec<_>>();
+        let order = dependency_order(&installed, &names);
+        assert_eq!(order, ["Zeta", "Alpha", "Beta", "Gamma", "Omega"]);
+        for end in 0..=order.len() {
+            let prefix = &order[..end];
+            for mod_info in installed.iter().filter(|item| prefix.contains(&item.name)) {
+                for dep in mod_info.deps.iter().filter(|dep| names.contains(&dep.name)) {
+                    assert!(
+                        prefix.contains(&dep.name),
+                        "{} without {}",
+                        mod_info.name,
+                        dep.name
+                    );
+                }
+            }
+        }
+
+        let mut session = BisectSession {
+            original_blacklist: None,
+            always_on: Vec::new(),
+            signature: String::new(),
+            candidates: order,
+            history: Vec::new(),
+            launched_at: None,
+        };
+        while session.candidates.len() > 1 {
+            let reproduced = tested_half(&session.candidates).contains(&"Gamma".to_owned());
+            narrow(&mut session, reproduced, false);
+        }
+        assert_eq!(session.candidates, ["Gamma"]);
+        assert_eq!(session.history.len(), 3);
+        assert!(session.history.iter().any(|round| round.reproduced));
+    }
+}
@@ -0,0 +1,465 @@
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::write::SimpleFileOptions;
+
+use super::{
+    blacklist,
+    crash_analysis::{self, CrashAnalysis},
+    get_installed_mods_without_catalog_sync, miaonet_settings_path, mod_index,
+    read_miaonet_settings_document, yaml_string_property,
+};
+
+const BUNDLE_FORMAT: &str = "celemod-crash-bundle";
+const BUNDLE_VERSION: u32 = 1;
+/// `log.txt` of a long session can be very large; helpers need the ends.
+const MAX_LOG_BYTES: usize = 4_000_000;
+/// Bundles come from other people; no entry is read past this size.
+const MAX_ENTRY_BYTES: u64 = 16_000_000;
+const REDACTED: &str = "<redacted>";
+const USER: &str = "<user>";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format: String,
+    pub version: u32,
+    pub created_at: u64,
+    pub celemod_version: String,
+    pub everest_version: Option<i32>,
+    pub platform: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundledMod {
+    pub name: String,
+    pub version: String,
+    pub file: String,
+    /// Empty for folder Mods.
+    pub xx_hash: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedBundle {
+    pub manifest: BundleManifest,
+    pub analysis: CrashAnalysis,
+    pub mods: Vec<BundledMod>,
+    pub everest_launch: Option<String>,
+    pub files: Vec<String>,
+}
+
+/// Removes what identifies the user: the MiaoNet token and name, the home
+/// directory and the account name.
+struct Redactor {
+    secrets: Vec<String>,
+    home_paths: Vec<String>,
+    user_names: Vec<String>,
+}
+
+/// Byte offsets of `needle` in `text`, ignoring ASCII case. Lowercasing
+/// ASCII keeps every offset valid in the original text.
+fn match_positions(text: &str, needle: &str) -> Vec<usize> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    text.to_ascii_lowercase()
+        .match_indices(&needle.to_ascii_lowercase())
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Replaces the matches `end_of` accepts; it returns where a match ends.
+fn replace_matches(
+    text: &str,
+    needle: &str,
+    replacement: &str,
+    end_of: impl Fn(usize) -> Option<usize>,
+) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut position = 0;
+    for index in match_positions(text, needle) {
+        if index < position {
+            continue;
+        }
+        let Some(end) = end_of(index) else {
+            continue;
+        };
+        output.push_str(&text[position..index]);
+        output.push_str(replacement);
+        position = end;
+    }
+    output.push_str(&text[position..]);
+    output
+}
+
+fn replace_ignore_ascii_case(text: &str, needle: &str, replacement: &str) -> String {
+    replace_matches(text, needle, replacement, |index| {
+        Some(index + needle.len())
+    })
+}
+
+fn replace_word(text: &str, word: &str, replacement: &str) -> String {
+    let is_word = |character: Option<char>| character.is_some_and(char::is_alphanumeric);
+    replace_matches(text, word, replacement, |index| {
+        let end = index + word.len();
+        (!is_word(text[..index].chars().next_back()) && !is_word(text[end..].chars().next()))
+            .then_some(end)
+    })
+}
+
+/// `C:\Users\name\...` and `/home/name/...` for any account, not only the
+/// current one: logs can contain paths copied from another machine.
+fn redact_user_directories(text: &str) -> String {
+    let mut text = text.to_string();
+    for marker in ["\\Users\\", "/Users/", "/home/"] {
+        let replacement = format!("{marker}{USER}");
+        text = replace_matches(&text, marker, &replacement, |index| {
+            let start = index + marker.len();
+            Some(
+                text[start..]
+                    .find(['\\', '/', '"', '\'', ' ', '\n', '\r', ')', ']'])
+                    .map_or(text.len(), |offset| start + offset),
+            )
+        });
+    }
+    text
+}
+
+impl Redactor {
+    fn for_game(game_path: &Path) -> Self {
+        let mut secrets = Vec::new();
+        let mut user_names = vec![whoami::username()];
+        user_names.extend(std::env::var("USERNAME").ok());
+        user_names.extend(std::env::var("USER").ok());
+        if let Ok(settings) =
+            miaonet_settings_path(game_path).and_then(|path| read_miaonet_settings_document(&path))
+        {
+            secrets.extend(yaml_string_property(&settings, "TokenDataEncrypted"));
+            user_names.extend(yaml_string_property(&settings, "LastName"));
+        }
+        let home_paths = dirs::home_dir()
+            .map(|home| {
+                let home = home.to_string_lossy().into_owned();
+                vec![home.replace('\\', "/"), home.replace('/', "\\"), home]
+            })
+            .unwrap_or_default();
+        Self::new(secrets, home_paths, user_names)
+    }
+
+    fn new(secrets: Vec<String>, mut home_paths: Vec<String>, mut user_names: Vec<String>) -> Self {
+        // Longer variants first, so a prefix never leaves half a path behind.
+        home_paths.retain(|path| path.len() > 3);
+        home_paths.sort_by(|left, right| right.len().cmp(&left.len()).then(left.cmp(right)));
+        home_paths.dedup();
+        user_names.retain(|name| name.trim().len() >= 3);
+        user_names.sort_by(|left, right| right.len().cmp(&left.len()).then(left.cmp(right)));
+        user_names.dedup();
+        Self {
+            secrets: secrets
+                .into_iter()
+                .filter(|secret| secret.len() >= 8)
+                .collect(),
+            home_paths,
+            user_names,
+        }
+    }
+
+    fn redact(&self, text: &str) -> String {
+        let mut text = text
+            .lines()
+            .map(|line| match line.split_once(':') {
+                Some((key, _)) if key.to_ascii_lowercase().contains("tokendata") => {
+                    format!("{key}: {REDACTED}")
+                }
+                _ => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        for secret in &self.secrets {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+        for home in &self.home_paths {
+            text = replace_ignore_ascii_case(&text, home, "~");
+        }
+        text = redact_user_directories(&text);
+        for name in &self.user_names {
+            text = replace_word(&text, name, USER);
+        }
+        text
+    }
+
+    /// JSON escapes backslashes, so Windows paths are redacted per string
+    /// value before serializing.
+    fn redact_json(&self, value: &impl Serialize) -> anyhow::Result<String> {
+        fn walk(redactor: &Redactor, value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::String(text) => *text = redactor.redact(text),
+                serde_json::Value::Array(items) => {
+                    items.iter_mut().for_each(|item| walk(redactor, item))
+                }
+                serde_json::Value::Object(fields) => {
+                    fields.values_mut().for_each(|item| walk(redactor, item))
+                }
+                _ => {}
+            }
+        }
+        let mut value = serde_json::to_value(value)?;
+        walk(self, &mut value);
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+}
+
+fn bundled_mods(game_path: &Path) -> Vec<BundledMod> {
+    let mods_dir = game_path.join("Mods");
+    let disabled = blacklist::direct_blacklisted_files(&game_path.to_string_lossy());
+    let mut mods = get_installed_mods_without_catalog_sync(mods_dir.to_string_lossy().into_owned())
+        .into_iter()
+        .map(|local_mod| {
+            let path = mods_dir.join(&local_mod.file);
+            BundledMod {
+                xx_hash: if path.is_file() {
+                    mod_index::archive_xx_hash(&path).unwrap_or_default()
+                } else {
+                    String::new()
+                },
+                enabled: !disabled.contains(&local_mod.file.to_ascii_lowercase()),
+                name: local_mod.name,
+                version: local_mod.version,
+                file: local_mod.file,
+            }
+        })
+        .collect::<Vec<_>>();
+    mod_index::flush(&mods_dir);
+    mods.sort_by(|left, right| left.name.cmp(&right.name));
+    mods
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "log.txt".to_string())
+}
+
+/// The bundle entry of each log to include. Crashes read from errorLog.txt
+/// name it as both logs, and zip entries must be unique.
+fn log_entry_names<'a>(
+    source_log: &'a str,
+    error_log: Option<&'a String>,
+) -> Vec<(&'a str, String)> {
+    let mut logs = Vec::<(&str, String)>::new();
+    for log in std::iter::once(source_log).chain(error_log.map(String::as_str)) {
+        let name = format!("logs/{}", file_name(log));
+        if !logs.iter().any(|(_, existing)| *existing == name) {
+            logs.push((log, name));
+        }
+    }
+    logs
+}
+
+/// Writes `analysis` with its logs and the Mod list to `destination`.
+pub fn export_bundle(
+    game_path: &Path,
+    analysis: &CrashAnalysis,
+    destination: &Path,
+) -> anyhow::Result<()> {
+    let redactor = Redactor::for_game(game_path);
+    let manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        created_at: crash_analysis::now_millis(),
+        celemod_version: env!("VERSION").to_string(),
+        everest_version: analysis.everest_version,
+        platform: std::env::consts::OS.to_string(),
+    };
+    let mut entries = vec![
+        (
+            "manifest.json".to_string(),
+            serde_json::to_string_pretty(&manifest)?,
+        ),
+        ("analysis.json".to_string(), redactor.redact_json(analysis)?),
+        (
+            "mods.json".to_string(),
+            redactor.redact_json(&bundled_mods(game_path))?,
+        ),
+    ];
+    if let Ok(report) = fs::read_to_string(&analysis.report_path) {
+        entries.push(("report.txt".to_string(), redactor.redact(&report)));
+    }
+    for (log, name) in log_entry_names(&analysis.source_log, analysis.error_log.as_ref()) {
+        let text = crash_analysis::read_limited_text(Path::new(log), MAX_LOG_BYTES);
+        if !text.is_empty() {
+            entries.push((name, redactor.redact(&text)));
+        }
+    }
+    if let Ok(launch) = fs::read_to_string(game_path.join("everest-launch.txt")) {
+        entries.push(("everest-launch.txt".to_string(), redactor.redact(&launch)));
+    }
+
+    let temp_path = destination.with_extension("zip.partial");
+    let mut writer = zip::ZipWriter::new(
+        fs::File::create(&temp_path)
+            .with_context(|| format!("Failed to create {}", temp_path.display()))?,
+    );
+    for (name, contents) in &entries {
+        writer.start_file(name.as_str(), SimpleFileOptions::default())?;
+        writer.write_all(contents.as_bytes())?;
+    }
+    writer.finish()?;
+    fs::rename(&temp_path, destination)
+        .with_context(|| format!("Failed to write {}", destination.display()))?;
+    Ok(())
+}
+
+fn read_entry(
+    archive: &mut zip::ZipArchive<fs::File>,
+    name: &str,
+) -> anyhow::Result<Option<String>> {
+    let entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    let mut contents = String::new();
+    entry
+        .take(MAX_ENTRY_BYTES)
+        .read_to_string(&mut contents)
+        .with_context(|| format!("Failed to read {name}"))?;
+    Ok(Some(contents))
+}
+
+/// The event id ends up in a local file name; one that is not a plain hash
+/// cannot be trusted and is replaced by a hash of itself.
+fn local_event_id(event_id: &str) -> String {
+    if !event_id.is_empty()
+        && event_id.len() <= 64
+        && event_id.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return event_id.to_string();
+    }
+    format!("{:x}", Sha256::digest(event_id.as_bytes()))[..16].to_string()
+}
+
+/// Opens a bundle exported by `export_bundle` on another machine. The
+/// summary and suggestions are re-read with the local rules and `language`;
+/// the report is copied next to local reports so it can be revealed.
+pub fn import_bundle(path: &Path, language: &str) -> anyhow::Result<ImportedBundle> {
+    let mut archive = zip::ZipArchive::new(
+        fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
+    )
+    .context("The crash bundle is not a zip file")?;
+    let manifest: BundleManifest = serde_json::from_str(
+        &read_entry(&mut archive, "manifest.json")?.context("The zip is not a crash bundle")?,
+    )?;
+    if manifest.format != BUNDLE_FORMAT {
+        bail!("The zip is not a crash bundle");
+    }
+    if manifest.version > BUNDLE_VERSION {
+        bail!(
+            "The crash bundle was made by a newer CeleMod (format {})",
+            manifest.version
+        );
+    }
+    let mut analysis: CrashAnalysis = serde_json::from_str(
+        &read_entry(&mut archive, "analysis.json")?.context("The crash bundle has no analysis")?,
+    )?;
+    let mods = match read_entry(&mut archive, "mods.json")? {
+        Some(contents) => serde_json::from_str(&contents)?,
+        None => Vec::new(),
+    };
+    let everest_launch = read_entry(&mut archive, "everest-launch.txt")?;
+    let error_log = match &analysis.error_log {
+        Some(log) => read_entry(&mut archive, &format!("logs/{}", file_name(log)))?,
+        None => None,
+    };
+    crash_analysis::reinterpret(&mut analysis, language, error_log.as_deref());
+
+    analysis.event_id = local_event_id(&analysis.event_id);
+    analysis.report_path = String::new();
+    if let Some(report) = read_entry(&mut archive, "report.txt")? {
+        let report_dir = crash_analysis::report_directory()?;
+        fs::create_dir_all(&report_dir)?;
+        let report_path = report_dir.join(format!(
+            "CeleMod-Crash-Bundle-{}-{}.txt",
+            manifest.created_at, analysis.event_id
+        ));
+        fs::write(&report_path, report)?;
+        analysis.report_path = report_path.to_string_lossy().into_owned();
+    }
+    let files = archive.file_names().map(str::to_owned).collect();
+    Ok(ImportedBundle {
+        manifest,
+        analysis,
+        mods,
+        everest_launch,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_tokens_home_paths_and_user_names() {
+        let redactor = Redactor::new(
+            vec!["c2VjcmV0LXRva2Vu".to_string()],
+            vec![
+                "C:\\Users\\Madeline".to_string(),
+                "C:/Users/Madeline".to_string(),
+            ],
+            vec!["Madeline".to_string(), "ab".to_string()],
+        );
+        let text = "TokenDataEncrypted: c2VjcmV0LXRva2Vu\n\
+            sent c2VjcmV0LXRva2Vu to the server\n\
+            Loading c:\\users\\madeline\\Celeste\\Mods\\Pack.zip\n\
+            Loading /home/theo/.local/share/Celeste/log.txt\n\
+            Player Madeline joined; MadelineHelper loaded; abc";
+        let redacted = redactor.redact(text);
+        assert_eq!(
+            redacted,
+            "TokenDataEncrypted: <redacted>\n\
+            sent <redacted> to the server\n\
+            Loading ~\\Celeste\\Mods\\Pack.zip\n\
+            Loading /home/<user>/.local/share/Celeste/log.txt\n\
+            Player <user> joined; MadelineHelper loaded; abc"
+        );
+        let json = redactor
+            .redact_json(&serde_json::json!({ "sourceLog": "C:\\Users\\Madeline\\log.txt" }))
+            .unwrap();
+        assert!(json.contains(r#""sourceLog": "~\\log.txt""#), "{json}");
+    }
+
+    #[test]
+    fn imported_event_ids_are_safe_file_name_parts() {
+        assert_eq!(local_event_id("0123abcdef456789"), "0123abcdef456789");
+        for event_id in ["../../evil", "", "a/b", "..\\x"] {
+            let local = local_event_id(event_id);
+            assert_eq!(local.len(), 16);
+            assert!(local.chars().all(|c| c.is_ascii_hexdigit()));
+        }
+    }
+
+    #[test]
+    fn error_log_crashes_bundle_their_log_once() {
+        let error_log = "/game/errorLog.txt".to_string();
+        assert_eq!(
+            log_entry_names(&error_log, Some(&error_log)),
+            [("/game/errorLog.txt", "logs/errorLog.txt".to_string())]
+        );
+        let names = log_entry_names("/game/log.txt", Some(&error_log))
+            .into_iter()
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["logs/log.txt", "logs/errorLog.txt"]);
+    }
+}
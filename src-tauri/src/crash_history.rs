@@ -0,0 +1,432 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use super::{blacklist, crash_analysis::CrashAnalysis, get_installed_mods_without_catalog_sync};
+
+const HISTORY_FILE: &str = "crash_history.json";
+const HISTORY_VERSION: u32 = 1;
+/// The oldest crashes are dropped first; a few hundred cover months of play.
+const MAX_ENTRIES: usize = 500;
+
+lazy_static! {
+    static ref HISTORY_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModSnapshot {
+    pub name: String,
+    pub version: String,
+    pub file: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistorySuspect {
+    pub name: String,
+    pub version: String,
+    pub confidence: u8,
+}
+
+/// One analysed crash. `signature` is the same for the same crash in later
+/// game runs and groups recurrences; `event_id` identifies this occurrence.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashHistoryEntry {
+    pub event_id: String,
+    pub signature: String,
+    pub game_path: String,
+    pub crashed_at: u64,
+    pub analysed_at: u64,
+    pub exception: String,
+    pub summary: String,
+    pub everest_version: Option<i32>,
+    pub report_path: String,
+    pub suspects: Vec<HistorySuspect>,
+    pub enabled_mods: Vec<ModSnapshot>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct HistoryFile {
+    version: u32,
+    entries: Vec<CrashHistoryEntry>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModVersionChange {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModSetChanges {
+    pub added: Vec<ModSnapshot>,
+    pub removed: Vec<ModSnapshot>,
+    pub updated: Vec<ModVersionChange>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashRecurrence {
+    pub signature: String,
+    pub exception: String,
+    pub summary: String,
+    pub occurrences: usize,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    /// Changes between the crash recorded just before the first occurrence and
+    /// the first occurrence. `None` when nothing was recorded before it.
+    pub changes_before_first: Option<ModSetChanges>,
+    /// Suspects that were updated between two occurrences without helping.
+    pub updates_without_effect: Vec<ModVersionChange>,
+    /// Suspects whose installed version changed after the last occurrence.
+    pub updated_since_last_seen: Vec<ModVersionChange>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashHistory {
+    /// Newest first.
+    pub entries: Vec<CrashHistoryEntry>,
+    /// Most recently seen first.
+    pub recurrences: Vec<CrashRecurrence>,
+}
+
+fn history_path() -> anyhow::Result<PathBuf> {
+    let root = dirs::data_local_dir()
+        .or_else(dirs::cache_dir)
+        .context("Failed to find a data directory")?;
+    Ok(root.join("CeleMod").join(HISTORY_FILE))
+}
+
+/// A history that cannot be read back is moved aside to `.corrupt` rather
+/// than overwritten, so the old crashes can still be recovered by hand.
+fn load(path: &Path) -> HistoryFile {
+    let empty = || HistoryFile {
+        version: HISTORY_VERSION,
+        entries: Vec::new(),
+    };
+    let Ok(bytes) = fs::read(path) else {
+        return empty();
+    };
+    match serde_json::from_slice::<HistoryFile>(&bytes) {
+        Ok(history) if history.version == HISTORY_VERSION => history,
+        result => {
+            let reason = match result {
+                Ok(history) => format!("unsupported version {}", history.version),
+                Err(error) => error.to_string(),
+            };
+            let corrupt = path.with_extension("json.corrupt");
+            match fs::rename(path, &corrupt) {
+                Ok(()) => crate::logging::warn(format_args!(
+                    "Crash history {} could not be read ({reason}); moved it to {} and started a new one",
+                    path.display(),
+                    corrupt.display()
+                )),
+                Err(error) => crate::logging::error(format_args!(
+                    "Crash history {} could not be read ({reason}) or moved aside: {error}",
+                    path.display()
+                )),
+            }
+            empty()
+        }
+    }
+}
+
+fn save(path: &Path, history: &HistoryFile) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_vec(history)?)?;
+    fs::rename(&temp_path, path).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Analysing the same crash again (another language, new rules) replaces its
+/// entry instead of counting it twice.
+fn insert(history: &mut HistoryFile, entry: CrashHistoryEntry) {
+    history.entries.retain(|existing| {
+        existing.event_id != entry.event_id || existing.game_path != entry.game_path
+    });
+    history.entries.push(entry);
+    history.entries.sort_by_key(|entry| entry.crashed_at);
+    let overflow = history.entries.len().saturating_sub(MAX_ENTRIES);
+    history.entries.drain(..overflow);
+}
+
+pub(super) fn enabled_mods(game_path: &Path) -> Vec<ModSnapshot> {
+    let disabled = blacklist::direct_blacklisted_files(&game_path.to_string_lossy());
+    let mut mods = get_installed_mods_without_catalog_sync(
+        game_path.join("Mods").to_string_lossy().into_owned(),
+    )
+    .into_iter()
+    .filter(|local_mod| !disabled.contains(&local_mod.file.to_ascii_lowercase()))
+    .map(|local_mod| ModSnapshot {
+        name: local_mod.name,
+        version: local_mod.version,
+        file: local_mod.file,
+    })
+    .collect::<Vec<_>>();
+    mods.sort_by(|left, right| left.name.cmp(&right.name));
+    mods
+}
+
+/// Failing to persist history must not hide the analysis itself, so errors
+/// are only logged.
+pub(super) fn record_analysis(game_path: &Path, analysis: &CrashAnalysis) {
+    let entry = CrashHistoryEntry {
+        event_id: analysis.event_id.clone(),
+        signature: analysis.signature.clone(),
+        game_path: game_path.to_string_lossy().into_owned(),
+        crashed_at: analysis.log_modified_at,
+        analysed_at: super::crash_analysis::now_millis(),
+        exception: analysis.exception.clone(),
+        summary: analysis.summary.clone(),
+        everest_version: analysis.everest_version,
+        report_path: analysis.report_path.clone(),
+        suspects: analysis
+            .suspects
+            .iter()
+            .map(|suspect| HistorySuspect {
+                name: suspect.name.clone(),
+                version: suspect.installed_version.clone(),
+                confidence: suspect.confidence,
+            })
+            .collect(),
+        enabled_mods: enabled_mods(game_path),
+    };
+    let result = history_path().and_then(|path| {
+        let _guard = HISTORY_LOCK.lock();
+        let mut history = load(&path);
+        insert(&mut history, entry);
+        save(&path, &history)
+    });
+    if let Err(error) = result {
+        crate::logging::error(format_args!("Failed to record crash history: {error:#}"));
+    }
+}
+
+fn by_name(mods: &[ModSnapshot]) -> HashMap<String, &ModSnapshot> {
+    mods.iter()
+        .map(|item| (item.name.to_ascii_lowercase(), item))
+        .collect()
+}
+
+fn diff_snapshots(before: &[ModSnapshot], after: &[ModSnapshot]) -> ModSetChanges {
+    let old = by_name(before);
+    let new = by_name(after);
+    let mut changes = ModSetChanges::default();
+    for item in after {
+        match old.get(&item.name.to_ascii_lowercase()) {
+            None => changes.added.push(item.clone()),
+            Some(previous) if previous.version != item.version => {
+                changes.updated.push(ModVersionChange {
+                    name: item.name.clone(),
+                    from: previous.version.clone(),
+                    to: item.version.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    changes.removed = before
+        .iter()
+        .filter(|item| !new.contains_key(&item.name.to_ascii_lowercase()))
+        .cloned()
+        .collect();
+    changes
+}
+
+fn suspect_updates(
+    suspects: &[HistorySuspect],
+    before: &[ModSnapshot],
+    after: &[ModSnapshot],
+) -> Vec<ModVersionChange> {
+    let old = by_name(before);
+    let new = by_name(after);
+    suspects
+        .iter()
+        .filter_map(|suspect| {
+            let key = suspect.name.to_ascii_lowercase();
+            let (from, to) = (old.get(&key)?, new.get(&key)?);
+            (from.version != to.version).then(|| ModVersionChange {
+                name: suspect.name.clone(),
+                from: from.version.clone(),
+                to: to.version.clone(),
+            })
+        })
+        .collect()
+}
+
+/// `entries` are sorted oldest first; `current` is what is enabled now.
+fn summarize(entries: &[CrashHistoryEntry], current: &[ModSnapshot]) -> Vec<CrashRecurrence> {
+    let mut groups = Vec::<(String, Vec<usize>)>::new();
+    for (index, entry) in entries.iter().enumerate() {
+        match groups
+            .iter_mut()
+            .find(|(signature, _)| *signature == entry.signature)
+        {
+            Some((_, indices)) => indices.push(index),
+            None => groups.push((entry.signature.clone(), vec![index])),
+        }
+    }
+
+    let mut recurrences = groups
+        .into_iter()
+        .map(|(signature, indices)| {
+            let first = &entries[indices[0]];
+            let last = &entries[*indices.last().unwrap_or(&indices[0])];
+            let changes_before_first = indices[0].checked_sub(1).map(|previous| {
+                diff_snapshots(&entries[previous].enabled_mods, &first.enabled_mods)
+            });
+            let updates_without_effect = indices
+                .windows(2)
+                .flat_map(|pair| {
+                    let (before, after) = (&entries[pair[0]], &entries[pair[1]]);
+                    suspect_updates(&after.suspects, &before.enabled_mods, &after.enabled_mods)
+                })
+                .collect();
+            CrashRecurrence {
+                signature,
+                exception: last.exception.clone(),
+                summary: last.summary.clone(),
+                occurrences: indices.len(),
+                first_seen: first.crashed_at,
+                last_seen: last.crashed_at,
+                changes_before_first,
+                updates_without_effect,
+                updated_since_last_seen: suspect_updates(
+                    &last.suspects,
+                    &last.enabled_mods,
+                    current,
+                ),
+            }
+        })
+        .collect::<Vec<_>>();
+    recurrences.sort_by_key(|recurrence| std::cmp::Reverse(recurrence.last_seen));
+    recurrences
+}
+
+pub fn crash_history(game_path: &str) -> anyhow::Result<CrashHistory> {
+    let path = history_path()?;
+    let mut entries = {
+        let _guard = HISTORY_LOCK.lock();
+        load(&path).entries
+    };
+    entries.retain(|entry| entry.game_path == game_path);
+    let recurrences = summarize(&entries, &enabled_mods(Path::new(game_path)));
+    entries.reverse();
+    Ok(CrashHistory {
+        entries,
+        recurrences,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(mods: &[(&str, &str)]) -> Vec<ModSnapshot> {
+        mods.iter()
+            .map(|(name, version)| ModSnapshot {
+                name: (*name).to_owned(),
+                version: (*version).to_owned(),
+                file: format!("{name}.zip"),
+            })
+            .collect()
+    }
+
+    fn entry(
+        event_id: &str,
+        signature: &str,
+        crashed_at: u64,
+        mods: &[(&str, &str)],
+    ) -> CrashHistoryEntry {
+        CrashHistoryEntry {
+            event_id: event_id.to_owned(),
+            signature: signature.to_owned(),
+            game_path: "/game".to_owned(),
+            crashed_at,
+            analysed_at: crashed_at,
+            exception: "System.Exception: boom".to_owned(),
+            summary: String::new(),
+            everest_version: Some(4465),
+            report_path: String::new(),
+            suspects: vec![HistorySuspect {
+                name: "Helper".to_owned(),
+                version: "1.0.0".to_owned(),
+                confidence: 90,
+            }],
+            enabled_mods: snapshot(mods),
+        }
+    }
+
+    #[test]
+    fn recurrences_track_preceding_changes_and_updates() {
+        let mut history = HistoryFile::default();
+        insert(&mut history, entry("a", "other", 1, &[("Helper", "1.0.0")]));
+        insert(
+            &mut history,
+            entry("b", "boom", 2, &[("Helper", "1.0.0"), ("Map", "1.0.0")]),
+        );
+        insert(
+            &mut history,
+            entry("c", "boom", 3, &[("Helper", "1.1.0"), ("Map", "1.0.0")]),
+        );
+        // Re-analysing an occurrence replaces it.
+        insert(
+            &mut history,
+            entry("c", "boom", 3, &[("Helper", "1.1.0"), ("Map", "1.0.0")]),
+        );
+        assert_eq!(history.entries.len(), 3);
+
+        let recurrences = summarize(
+            &history.entries,
+            &snapshot(&[("Helper", "1.2.0"), ("Map", "1.0.0")]),
+        );
+        let boom = &recurrences[0];
+        assert_eq!((boom.signature.as_str(), boom.occurrences), ("boom", 2));
+        assert_eq!((boom.first_seen, boom.last_seen), (2, 3));
+        let before = boom.changes_before_first.as_ref().unwrap();
+        assert_eq!(before.added, snapshot(&[("Map", "1.0.0")]));
+        assert!(before.removed.is_empty() && before.updated.is_empty());
+        assert_eq!(boom.updates_without_effect[0].to, "1.1.0");
+        assert_eq!(boom.updated_since_last_seen[0].to, "1.2.0");
+        assert!(recurrences[1].changes_before_first.is_none());
+    }
+
+    #[test]
+    fn unreadable_history_is_moved_aside() {
+        let directory = std::env::temp_dir().join(format!(
+            "celemod-crash-history-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(HISTORY_FILE);
+        fs::write(&path, b"{\"version\": 1, \"entries\": [").unwrap();
+
+        assert!(load(&path).entries.is_empty());
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read(path.with_extension("json.corrupt")).unwrap(),
+            b"{\"version\": 1, \"entries\": ["
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,625 @@
+use super::crash_analysis::{CrashSuspect, normalize_token, version_numbers};
+
+use ::ureq::get;
+use anyhow::{Context, bail, ensure};
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Bump when a rule file written for this build could be misread by an older
+/// one; older builds then keep their built-in rules instead.
+pub const RULES_FORMAT_VERSION: u32 = 2;
+const BUILTIN_RULES: &str = include_str!("crash_rules.json");
+const SUSPECT_PLACEHOLDER: &str = "{suspect}";
+const DEFAULT_ALIAS_CONFIDENCE: u8 = 78;
+/// Every rule file has to provide this locale; others fall back to it.
+pub const FALLBACK_LOCALE: &str = "en";
+/// The languages `keybindings::language_dialog_names` knows about.
+pub const SUPPORTED_LOCALES: [&str; 6] = ["en", "zh-CN", "ru-RU", "pt-BR", "fr-FR", "de-DE"];
+
+/// A crash rule file. Rules are tried in order and the first match decides
+/// the summary, so the last rule has to be a catch-all.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashRuleSet {
+    pub version: u32,
+    /// Increases with every published rule file; a downloaded file older
+    /// than the built-in one is ignored.
+    pub revision: u64,
+    /// Keyed by locale, like `CrashRule::text`.
+    pub messages: BTreeMap<String, RuleMessages>,
+    pub rules: Vec<CrashRule>,
+    #[serde(default)]
+    pub aliases: Vec<ModAlias>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleMessages {
+    /// Replaces `{suspect}` when no suspect was found.
+    pub suspect_fallback: String,
+    pub update_available: String,
+    pub legacy_loader: String,
+}
+
+/// Terms are matched case-insensitively against the crash body: at least one
+/// of `any` (when given), every one of `all` and none of `none`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashRule {
+    pub id: String,
+    #[serde(default)]
+    pub any: Vec<String>,
+    #[serde(default)]
+    pub all: Vec<String>,
+    #[serde(default)]
+    pub none: Vec<String>,
+    #[serde(default)]
+    pub requires_suspects: bool,
+    /// Restricts the rule to crashes where this Mod is a suspect; `{suspect}`
+    /// then names it instead of the top suspect.
+    #[serde(default)]
+    pub affects: Option<AffectedMod>,
+    /// Keyed by locale; `FALLBACK_LOCALE` is required.
+    pub text: BTreeMap<String, RuleText>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleText {
+    pub summary: String,
+    pub reasons: Vec<String>,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AffectedMod {
+    pub name: String,
+    /// Comma-separated comparators such as `>=1.1.0, <1.2.0`; a bare version
+    /// means `=`.
+    #[serde(default)]
+    pub versions: Option<String>,
+}
+
+/// Names a Mod when its identifiers never appear in the stacktrace but a
+/// known namespace does, e.g. MaxHelpingHand for Maddie's Helping Hand.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModAlias {
+    pub mods: Vec<String>,
+    pub traces: Vec<String>,
+    pub evidence: String,
+    #[serde(default = "default_alias_confidence")]
+    pub confidence: u8,
+}
+
+fn default_alias_confidence() -> u8 {
+    DEFAULT_ALIAS_CONFIDENCE
+}
+
+#[derive(Clone, Debug)]
+pub struct RuleOutcome {
+    pub summary: String,
+    pub reasons: Vec<String>,
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashRulesInfo {
+    pub revision: u64,
+    /// `builtin`, or the file or URL the active rules were refreshed from.
+    pub source: String,
+    pub fetched_at: Option<u64>,
+    pub rule_count: usize,
+    pub alias_count: usize,
+    /// Why a previously refreshed rule file was not used.
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedRules {
+    source: String,
+    fetched_at: u64,
+    rules: CrashRuleSet,
+}
+
+struct ActiveRules {
+    rules: Arc<CrashRuleSet>,
+    info: CrashRulesInfo,
+}
+
+lazy_static! {
+    static ref BUILTIN: Arc<CrashRuleSet> =
+        Arc::new(parse_rules(BUILTIN_RULES).expect("built-in crash rules are invalid"));
+    static ref ACTIVE: RwLock<ActiveRules> = RwLock::new(load_active());
+}
+
+/// Maps a UI language such as `ru-RU`, `ru` or `en-US` to one of
+/// `SUPPORTED_LOCALES`, falling back to English.
+pub fn crash_locale(language: &str) -> &'static str {
+    let primary = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    SUPPORTED_LOCALES
+        .iter()
+        .copied()
+        .find(|locale| locale.split('-').next() == Some(primary.as_str()))
+        .unwrap_or(FALLBACK_LOCALE)
+}
+
+fn localized<'a, T>(items: &'a BTreeMap<String, T>, locale: &str) -> &'a T {
+    items
+        .get(locale)
+        .or_else(|| items.get(FALLBACK_LOCALE))
+        .expect("validated crash rules include the fallback locale")
+}
+
+#[derive(Clone, Copy)]
+enum Comparator {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+fn parse_range(range: &str) -> anyhow::Result<Vec<(Comparator, String)>> {
+    let mut comparators = Vec::new();
+    for part in range.split(',').map(str::trim) {
+        let (comparator, version) = if let Some(version) = part.strip_prefix(">=") {
+            (Comparator::GreaterOrEqual, version)
+        } else if let Some(version) = part.strip_prefix("<=") {
+            (Comparator::LessOrEqual, version)
+        } else if let Some(version) = part.strip_prefix('>') {
+            (Comparator::Greater, version)
+        } else if let Some(version) = part.strip_prefix('<') {
+            (Comparator::Less, version)
+        } else {
+            (Comparator::Equal, part.strip_prefix('=').unwrap_or(part))
+        };
+        let version = version.trim();
+        ensure!(
+            !version_numbers(version).is_empty(),
+            "Invalid version range {range:?}"
+        );
+        comparators.push((comparator, version.to_string()));
+    }
+    Ok(comparators)
+}
+
+fn compare_versions(left: &str, right: &str) -> Ordering {
+    let left = version_numbers(left);
+    let right = version_numbers(right);
+    (0..left.len().max(right.len()))
+        .map(|index| {
+            let left = left.get(index).copied().unwrap_or_default();
+            let right = right.get(index).copied().unwrap_or_default();
+            left.cmp(&right)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn version_in_range(version: &str, range: &str) -> bool {
+    parse_range(range).is_ok_and(|comparators| {
+        comparators.iter().all(|(comparator, bound)| {
+            let ordering = compare_versions(version, bound);
+            match comparator {
+                Comparator::Less => ordering.is_lt(),
+                Comparator::LessOrEqual => ordering.is_le(),
+                Comparator::Equal => ordering.is_eq(),
+                Comparator::GreaterOrEqual => ordering.is_ge(),
+                Comparator::Greater => ordering.is_gt(),
+            }
+        })
+    })
+}
+
+impl CrashRule {
+    fn is_catch_all(&self) -> bool {
+        self.any.is_empty()
+            && self.all.is_empty()
+            && self.none.is_empty()
+            && !self.requires_suspects
+            && self.affects.is_none()
+    }
+
+    /// Returns the suspect `{suspect}` refers to when the rule matches.
+    fn matches<'a>(
+        &self,
+        lower: &str,
+        suspects: &'a [CrashSuspect],
+    ) -> Option<Option<&'a CrashSuspect>> {
+        let terms_match = (self.any.is_empty() || self.any.iter().any(|term| lower.contains(term)))
+            && self.all.iter().all(|term| lower.contains(term))
+            && !self.none.iter().any(|term| lower.contains(term));
+        if !terms_match || (self.requires_suspects && suspects.is_empty()) {
+            return None;
+        }
+        let Some(affects) = &self.affects else {
+            return Some(suspects.first());
+        };
+        let name = normalize_token(&affects.name);
+        suspects
+            .iter()
+            .find(|suspect| {
+                normalize_token(&suspect.name) == name
+                    && affects
+                        .versions
+                        .as_deref()
+                        .is_none_or(|range| version_in_range(&suspect.installed_version, range))
+            })
+            .map(Some)
+    }
+}
+
+impl CrashRuleSet {
+    fn validate(&mut self) -> anyhow::Result<()> {
+        ensure!(
+            self.version == RULES_FORMAT_VERSION,
+            "Unsupported crash rule format {} (expected {RULES_FORMAT_VERSION})",
+            self.version
+        );
+        ensure!(
+            self.messages.contains_key(FALLBACK_LOCALE),
+            "Crash rule messages have no {FALLBACK_LOCALE} text"
+        );
+        for (locale, messages) in &self.messages {
+            ensure!(
+                !messages.suspect_fallback.trim().is_empty()
+                    && !messages.update_available.trim().is_empty()
+                    && !messages.legacy_loader.trim().is_empty(),
+                "Crash rule messages for {locale} must not be empty"
+            );
+        }
+        let mut ids = HashSet::new();
+        for rule in &mut self.rules {
+            ensure!(!rule.id.trim().is_empty(), "A crash rule has no id");
+            ensure!(
+                ids.insert(rule.id.clone()),
+                "Duplicate crash rule {}",
+                rule.id
+            );
+            ensure!(
+                rule.text.contains_key(FALLBACK_LOCALE),
+                "Crash rule {} has no {FALLBACK_LOCALE} text",
+                rule.id
+            );
+            for (locale, text) in &rule.text {
+                ensure!(
+                    !text.summary.trim().is_empty()
+                        && !text.reasons.is_empty()
+                        && !text.suggestions.is_empty(),
+                    "Crash rule {} needs a summary, reasons and suggestions in {locale}",
+                    rule.id
+                );
+            }
+            for term in rule
+                .any
+                .iter_mut()
+                .chain(rule.all.iter_mut())
+                .chain(rule.none.iter_mut())
+            {
+                *term = term.trim().to_ascii_lowercase();
+                ensure!(!term.is_empty(), "Crash rule {} has an empty term", rule.id);
+            }
+            if let Some(affects) = &rule.affects {
+                ensure!(
+                    !normalize_token(&affects.name).is_empty(),
+                    "Crash rule {} affects an unnamed Mod",
+                    rule.id
+                );
+                if let Some(range) = affects.versions.as_deref() {
+                    parse_range(range).with_context(|| format!("Crash rule {}", rule.id))?;
+                }
+            }
+        }
+        ensure!(
+            self.rules.last().is_some_and(CrashRule::is_catch_all),
+            "The last crash rule must match every crash"
+        );
+        for alias in &mut self.aliases {
+            for value in alias.mods.iter_mut().chain(alias.traces.iter_mut()) {
+                *value = normalize_token(value);
+            }
+            alias.mods.retain(|value| !value.is_empty());
+            alias.traces.retain(|value| !value.is_empty());
+            ensure!(
+                !alias.mods.is_empty() && !alias.traces.is_empty() && alias.confidence <= 100,
+                "Invalid Mod alias {:?}",
+                alias.evidence
+            );
+        }
+        Ok(())
+    }
+
+    pub fn messages(&self, locale: &str) -> &RuleMessages {
+        localized(&self.messages, locale)
+    }
+
+    /// The first matching rule in `locale`, with `{suspect}` filled in.
+    pub fn evaluate(&self, text: &str, suspects: &[CrashSuspect], locale: &str) -> RuleOutcome {
+        let lower = text.to_ascii_lowercase();
+        let (rule, suspect) = self
+            .rules
+            .iter()
+            .find_map(|rule| {
+                rule.matches(&lower, suspects)
+                    .map(|suspect| (rule, suspect))
+            })
+            .expect("validated crash rules end with a catch-all");
+        let name = suspect
+            .map(|suspect| suspect.name.as_str())
+            .unwrap_or(&self.messages(locale).suspect_fallback);
+        let text = localized(&rule.text, locale);
+        let fill = |items: &[String]| {
+            items
+                .iter()
+                .map(|item| item.replace(SUSPECT_PLACEHOLDER, name))
+                .collect::<Vec<_>>()
+        };
+        RuleOutcome {
+            summary: text.summary.replace(SUSPECT_PLACEHOLDER, name),
+            reasons: fill(&text.reasons),
+            suggestions: fill(&text.suggestions),
+        }
+    }
+
+    /// `crash_compact` is the crash body passed through `normalize_token`.
+    pub fn alias_score(&self, mod_name: &str, crash_compact: &str) -> Option<(u8, &str)> {
+        let name = normalize_token(mod_name);
+        self.aliases.iter().find_map(|alias| {
+            (alias.mods.iter().any(|value| name.contains(value.as_str()))
+                && alias
+                    .traces
+                    .iter()
+                    .any(|value| crash_compact.contains(value.as_str())))
+            .then_some((alias.confidence, alias.evidence.as_str()))
+        })
+    }
+}
+
+pub fn parse_rules(text: &str) -> anyhow::Result<CrashRuleSet> {
+    let mut rules: CrashRuleSet =
+        serde_json::from_str(text.trim_start_matches('\u{feff}')).context("Invalid rule file")?;
+    rules.validate()?;
+    Ok(rules)
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|directory| directory.join("CeleMod").join("crash_rules.json"))
+}
+
+fn builtin_info(error: Option<String>) -> CrashRulesInfo {
+    CrashRulesInfo {
+        revision: BUILTIN.revision,
+        source: "builtin".to_string(),
+        fetched_at: None,
+        rule_count: BUILTIN.rules.len(),
+        alias_count: BUILTIN.aliases.len(),
+        error,
+    }
+}
+
+fn cached_info(cached: &CachedRules) -> CrashRulesInfo {
+    CrashRulesInfo {
+        revision: cached.rules.revision,
+        source: cached.source.clone(),
+        fetched_at: Some(cached.fetched_at),
+        rule_count: cached.rules.rules.len(),
+        alias_count: cached.rules.aliases.len(),
+        error: None,
+    }
+}
+
+fn read_cached(path: &Path) -> anyhow::Result<CachedRules> {
+    let mut cached: CachedRules = serde_json::from_str(&fs::read_to_string(path)?)?;
+    cached.rules.validate()?;
+    ensure!(
+        cached.rules.revision >= BUILTIN.revision,
+        "Revision {} is older than the built-in rules",
+        cached.rules.revision
+    );
+    Ok(cached)
+}
+
+fn load_active() -> ActiveRules {
+    let Some(path) = cache_path().filter(|path| path.is_file()) else {
+        return ActiveRules {
+            rules: BUILTIN.clone(),
+            info: builtin_info(None),
+        };
+    };
+    match read_cached(&path) {
+        Ok(cached) => ActiveRules {
+            info: cached_info(&cached),
+            rules: Arc::new(cached.rules),
+        },
+        Err(error) => ActiveRules {
+            rules: BUILTIN.clone(),
+            info: builtin_info(Some(format!("{}: {error:#}", path.display()))),
+        },
+    }
+}
+
+pub fn active_rules() -> Arc<CrashRuleSet> {
+    ACTIVE.read().rules.clone()
+}
+
+pub fn rules_info() -> CrashRulesInfo {
+    ACTIVE.read().info.clone()
+}
+
+fn fetch_rules(source: &str) -> anyhow::Result<String> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let response = get(source)
+            .set(
+                "User-Agent",
+                &format!("CeleMod/{}-{}", env!("VERSION"), &env!("GIT_HASH")[..6]),
+            )
+            .timeout(std::time::Duration::from_secs(20))
+            .call()?;
+        Ok(response.into_string()?)
+    } else {
+        fs::read_to_string(source).with_context(|| format!("Failed to read {source}"))
+    }
+}
+
+/// Replaces the active rules with a local file or an http(s) URL, or goes
+/// back to the built-in rules for `None`/`builtin`. The file is validated
+/// before anything changes and kept in the cache for later starts.
+pub fn refresh_rules(source: Option<&str>) -> anyhow::Result<CrashRulesInfo> {
+    let source = source.map(str::trim).filter(|source| !source.is_empty());
+    let Some(source) = source.filter(|source| *source != "builtin") else {
+        if let Some(path) = cache_path().filter(|path| path.exists()) {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        let info = builtin_info(None);
+        *ACTIVE.write() = ActiveRules {
+            rules: BUILTIN.clone(),
+            info: info.clone(),
+        };
+        return Ok(info);
+    };
+
+    let rules = parse_rules(&fetch_rules(source)?)
+        .with_context(|| format!("Rejected crash rules from {source}"))?;
+    if rules.revision < BUILTIN.revision {
+        bail!(
+            "Crash rules from {source} (revision {}) are older than the built-in rules (revision {})",
+            rules.revision,
+            BUILTIN.revision
+        );
+    }
+    let cached = CachedRules {
+        source: source.to_string(),
+        fetched_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .try_into()
+            .unwrap_or(u64::MAX),
+        rules,
+    };
+    let path = cache_path().context("Failed to find a cache directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_vec_pretty(&cached)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    let info = cached_info(&cached);
+    *ACTIVE.write() = ActiveRules {
+        rules: Arc::new(cached.rules),
+        info: info.clone(),
+    };
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suspect(name: &str, version: &str) -> CrashSuspect {
+        CrashSuspect {
+            name: name.to_string(),
+            file: format!("{name}.zip"),
+            installed_version: version.to_string(),
+            latest_version: None,
+            game_banana_file_id: None,
+            download_url: None,
+            update_available: false,
+            confidence: 100,
+            evidence: String::new(),
+            dependents: Vec::new(),
+            changed_since_last_good: false,
+        }
+    }
+
+    #[test]
+    fn affected_mod_version_range_selects_the_rule() {
+        let text = "MonoMod.Cil.ILCursor.GotoNext\nMonoMod.Cil.ILContext.Invoke\nCeleste.Mod.RushHelper.PlayerExtensions.Player_BeforeDownTransition_il";
+        let old = BUILTIN.evaluate(text, &[suspect("RushHelper", "1.1.1")], "zh-CN");
+        assert_eq!(old.summary, "RushHelper 1.1.x 使用了过于严格的 IL 匹配");
+        let fixed = BUILTIN.evaluate(text, &[suspect("RushHelper", "1.2.0")], "en");
+        assert_eq!(
+            fixed.summary,
+            "A Mod's IL hook does not fit the current loader"
+        );
+        assert!(fixed.reasons[0].contains("The IL hook of RushHelper "));
+        assert_eq!(
+            BUILTIN.evaluate("", &[], "zh-CN").summary,
+            "暂时无法自动确定根因"
+        );
+        assert_eq!(
+            BUILTIN.alias_score("Maddie's Helping Hand", "atcelestemodmaxhelpinghand"),
+            Some((78, "stacktrace mentions MaxHelpingHand"))
+        );
+    }
+
+    #[test]
+    fn every_locale_is_complete_and_unknown_ones_fall_back_to_english() {
+        assert_eq!(crash_locale("en-US"), "en");
+        assert_eq!(crash_locale("ru"), "ru-RU");
+        assert_eq!(crash_locale("pt_BR"), "pt-BR");
+        assert_eq!(crash_locale("ja-JP"), "en");
+        for locale in SUPPORTED_LOCALES {
+            assert!(BUILTIN.messages.contains_key(locale), "{locale}");
+            for rule in &BUILTIN.rules {
+                assert!(rule.text.contains_key(locale), "{} {locale}", rule.id);
+            }
+        }
+        let outcome = BUILTIN.evaluate("System.OutOfMemoryException", &[], "ja-JP");
+        assert_eq!(outcome.summary, "Crash caused by running out of memory");
+        // `analyze_latest_crash` looks for this name to avoid repeating it.
+        assert!(SUPPORTED_LOCALES.iter().all(|locale| {
+            BUILTIN
+                .messages(locale)
+                .legacy_loader
+                .contains("Legacy Loader")
+        }));
+    }
+
+    #[test]
+    fn rejects_invalid_rule_files() {
+        let valid = serde_json::to_value(&**BUILTIN).unwrap();
+        let reject = |edit: &dyn Fn(&mut serde_json::Value)| {
+            let mut value = valid.clone();
+            edit(&mut value);
+            parse_rules(&value.to_string()).is_err()
+        };
+        assert!(!reject(&|_| {}));
+        assert!(reject(
+            &|value| value["version"] = (RULES_FORMAT_VERSION + 1).into()
+        ));
+        assert!(reject(&|value| {
+            value["rules"].as_array_mut().unwrap().pop();
+        }));
+        assert!(reject(
+            &|value| value["rules"][1]["id"] = "out-of-memory".into()
+        ));
+        assert!(reject(&|value| value["rules"][0]["any"][0] = " ".into()));
+        assert!(reject(&|value| {
+            value["rules"][0]["text"]
+                .as_object_mut()
+                .unwrap()
+                .remove("en");
+        }));
+        assert!(reject(&|value| {
+            value["rules"][10]["affects"]["versions"] = ">=one".into()
+        }));
+    }
+}
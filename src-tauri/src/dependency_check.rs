@@ -0,0 +1,289 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{LocalMod, everest, get_installed_mods_without_catalog_sync, resolver};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyProblem {
+    /// Not installed at all.
+    Missing,
+    /// Installed in a suitable version, but blacklisted.
+    Disabled,
+    /// Every installed copy is older than required.
+    TooOld,
+    /// The installed copy is a newer major version, which Everest will not
+    /// load against a dependent built for the older one.
+    WrongMajor,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogDownload {
+    pub version: String,
+    pub game_banana_file_id: i64,
+    pub download_url: String,
+}
+
+/// What the crash popup does to repair an issue: enable an installed file,
+/// download a suitable version, or both for a disabled outdated copy.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyFix {
+    pub enable_file: Option<String>,
+    pub download: Option<CatalogDownload>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyIssue {
+    pub dependent: String,
+    pub dependency: String,
+    pub required_version: String,
+    pub optional: bool,
+    pub problem: DependencyProblem,
+    pub installed_version: Option<String>,
+    /// `None` when neither an installed copy nor the catalog can help.
+    pub fix: Option<DependencyFix>,
+}
+
+struct InstalledCopy<'a> {
+    local_mod: &'a LocalMod,
+    enabled: bool,
+}
+
+/// Dependencies of enabled Mods that Everest will refuse to load against.
+/// Optional dependencies only count when an enabled copy is too old or of
+/// another major version, which Everest treats like a missing required
+/// dependency.
+fn check(
+    installed: &[LocalMod],
+    disabled_files: &HashSet<String>,
+    catalog: Option<&HashMap<String, everest::ModInfoCached>>,
+) -> Vec<DependencyIssue> {
+    let mut copies = HashMap::<String, Vec<InstalledCopy>>::new();
+    for local_mod in installed {
+        copies
+            .entry(local_mod.name.to_ascii_lowercase())
+            .or_default()
+            .push(InstalledCopy {
+                local_mod,
+                enabled: !disabled_files.contains(&local_mod.file.to_ascii_lowercase()),
+            });
+    }
+    let download = |name: &str, required: &str| {
+        catalog
+            .and_then(|catalog| catalog.get(name))
+            .filter(|latest| resolver::version_satisfies(required, &latest.version))
+            .map(|latest| CatalogDownload {
+                version: latest.version.clone(),
+                game_banana_file_id: latest.game_banana_file_id,
+                download_url: latest.download_url.clone(),
+            })
+    };
+
+    let outdated = |installed: &str, required: &str| {
+        if resolver::ModVersion::parse(installed) > resolver::ModVersion::parse(required) {
+            DependencyProblem::WrongMajor
+        } else {
+            DependencyProblem::TooOld
+        }
+    };
+
+    let mut issues = Vec::new();
+    for dependent in installed
+        .iter()
+        .filter(|local_mod| !disabled_files.contains(&local_mod.file.to_ascii_lowercase()))
+    {
+        for dependency in &dependent.deps {
+            if resolver::is_loader_dependency(&dependency.name) {
+                continue;
+            }
+            let available = copies
+                .get(&dependency.name.to_ascii_lowercase())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let satisfies = |copy: &&InstalledCopy| {
+                resolver::version_satisfies(&dependency.version, &copy.local_mod.version)
+            };
+            let enabled = available.iter().filter(|copy| copy.enabled);
+            if enabled.clone().any(|copy| satisfies(&copy)) {
+                continue;
+            }
+            let newest_enabled =
+                enabled.max_by_key(|copy| resolver::ModVersion::parse(&copy.local_mod.version));
+            let (problem, installed_version, fix) = if let Some(copy) = newest_enabled {
+                let fix =
+                    download(&dependency.name, &dependency.version).map(|download| DependencyFix {
+                        enable_file: None,
+                        download: Some(download),
+                    });
+                (
+                    outdated(&copy.local_mod.version, &dependency.version),
+                    Some(copy.local_mod.version.clone()),
+                    fix,
+                )
+            } else if dependency.optional {
+                continue;
+            } else if let Some(copy) = available.iter().find(satisfies) {
+                (
+                    DependencyProblem::Disabled,
+                    Some(copy.local_mod.version.clone()),
+                    Some(DependencyFix {
+                        enable_file: Some(copy.local_mod.file.clone()),
+                        download: None,
+                    }),
+                )
+            } else if let Some(copy) = available
+                .iter()
+                .max_by_key(|copy| resolver::ModVersion::parse(&copy.local_mod.version))
+            {
+                // Updating keeps the file name, so the file can be enabled first.
+                let fix =
+                    download(&dependency.name, &dependency.version).map(|download| DependencyFix {
+                        enable_file: Some(copy.local_mod.file.clone()),
+                        download: Some(download),
+                    });
+                (
+                    outdated(&copy.local_mod.version, &dependency.version),
+                    Some(copy.local_mod.version.clone()),
+                    fix,
+                )
+            } else {
+                let fix =
+                    download(&dependency.name, &dependency.version).map(|download| DependencyFix {
+                        enable_file: None,
+                        download: Some(download),
+                    });
+                (DependencyProblem::Missing, None, fix)
+            };
+            issues.push(DependencyIssue {
+                dependent: dependent.name.clone(),
+                dependency: dependency.name.clone(),
+                required_version: dependency.version.clone(),
+                optional: dependency.optional,
+                problem,
+                installed_version,
+                fix,
+            });
+        }
+    }
+    issues.sort_by(|left, right| {
+        left.dependency
+            .cmp(&right.dependency)
+            .then_with(|| left.dependent.cmp(&right.dependent))
+    });
+    issues
+}
+
+/// Checks the Mods that `blacklist.txt` currently leaves enabled.
+pub(super) fn check_game(
+    game_path: &Path,
+    disabled_files: &HashSet<String>,
+) -> Vec<DependencyIssue> {
+    let installed = get_installed_mods_without_catalog_sync(
+        game_path.join("Mods").to_string_lossy().into_owned(),
+    );
+    let catalog = everest::get_mod_cached_if_loaded();
+    check(&installed, disabled_files, catalog.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ModDependency;
+    use super::*;
+
+    fn local_mod(name: &str, version: &str, deps: &[(&str, &str, bool)]) -> LocalMod {
+        LocalMod {
+            game_banana_id: -1,
+            name: name.to_string(),
+            deps: deps
+                .iter()
+                .map(|(name, version, optional)| ModDependency {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    optional: *optional,
+                })
+                .collect(),
+            version: version.to_string(),
+            file: format!("{name}.zip"),
+            size: 0,
+            modified_at: 0,
+        }
+    }
+
+    #[test]
+    fn flags_missing_disabled_and_outdated_dependencies() {
+        let installed = vec![
+            local_mod(
+                "Map",
+                "1.0.0",
+                &[
+                    ("Everest", "1.4465.0", false),
+                    ("Disabled", "1.0.0", false),
+                    ("Missing", "2.0.0", false),
+                    ("Old", "1.5.0", false),
+                    ("Newer", "1.0.0", false),
+                    ("Fine", "1.0.0", false),
+                    ("OptionalMissing", "1.0.0", true),
+                    ("OptionalOld", "3.0.0", true),
+                ],
+            ),
+            local_mod("Disabled", "1.2.0", &[]),
+            local_mod("Old", "1.4.0", &[]),
+            local_mod("Newer", "2.0.0", &[]),
+            local_mod("Fine", "1.1.0", &[]),
+            local_mod("OptionalOld", "2.9.0", &[]),
+            local_mod("Ignored", "1.0.0", &[("Missing", "1.0.0", false)]),
+        ];
+        let disabled = HashSet::from(["disabled.zip".to_string(), "ignored.zip".to_string()]);
+        let catalog = HashMap::from([(
+            "Missing".to_string(),
+            everest::ModInfoCached {
+                name: "Missing".to_string(),
+                version: "2.1.0".to_string(),
+                game_banana_id: 1,
+                game_banana_file_id: 2,
+                download_url: "https://example.invalid/Missing.zip".to_string(),
+                xx_hash: Vec::new(),
+            },
+        )]);
+
+        let issues = check(&installed, &disabled, Some(&catalog));
+        let summary = issues
+            .iter()
+            .map(|issue| (issue.dependency.as_str(), issue.problem))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("Disabled", DependencyProblem::Disabled),
+                ("Missing", DependencyProblem::Missing),
+                ("Newer", DependencyProblem::WrongMajor),
+                ("Old", DependencyProblem::TooOld),
+                ("OptionalOld", DependencyProblem::TooOld),
+            ]
+        );
+        assert_eq!(
+            issues[0]
+                .fix
+                .as_ref()
+                .and_then(|fix| fix.enable_file.as_deref()),
+            Some("Disabled.zip")
+        );
+        assert_eq!(
+            issues[1]
+                .fix
+                .as_ref()
+                .and_then(|fix| fix.download.as_ref())
+                .map(|download| download.game_banana_file_id),
+            Some(2)
+        );
+        assert_eq!(issues[3].installed_version.as_deref(), Some("1.4.0"));
+        assert!(issues[3].fix.is_none());
+    }
+}
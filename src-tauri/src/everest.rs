@@ -1,6 +1,5 @@
-use super::{ureq, wegfan};
+use super::{catalog_sources, ureq, wegfan};
 
-use ::ureq::get;
 use anyhow::{Context, bail};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
@@ -24,15 +23,35 @@ pub struct ModInfoCached {
     pub game_banana_id: i64,
     pub game_banana_file_id: i64,
     pub download_url: String,
+    #[serde(default)]
+    pub xx_hash: Vec<String>,
 }
 
 static USING_CACHE: AtomicBool = AtomicBool::new(false);
+/// Set when the source list changes so the next load skips a cache that was
+/// filled from the previous sources.
+static SOURCES_CHANGED: AtomicBool = AtomicBool::new(false);
 static MOD_CACHE_TTL_SECONDS: AtomicU64 = AtomicU64::new(60 * 60);
 
 pub fn is_using_cache() -> bool {
     USING_CACHE.load(Ordering::Relaxed)
 }
 
+/// How the catalog in memory was obtained by the last load.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CatalogRefresh {
+    /// Served from memory or the disk cache without asking the source.
+    Hit,
+    /// The source confirmed the cached copy is current.
+    Revalidated,
+    /// The cached copy was patched with the source's changes.
+    Delta,
+    Full,
+    /// Every source failed and an expired cached copy was used.
+    Stale,
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModCacheStatus {
@@ -40,6 +59,18 @@ pub struct ModCacheStatus {
     pub updated_at: u64,
     pub count: usize,
     pub path: String,
+    /// Label of the catalog source that produced the data.
+    pub catalog_source: String,
+    pub refresh: CatalogRefresh,
+}
+
+/// Stored next to `mod_list.json` so the next refresh can be conditional.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawCacheMeta {
+    source: String,
+    #[serde(default)]
+    validators: catalog_sources::CatalogValidators,
 }
 
 #[derive(Clone)]
@@ -47,6 +78,7 @@ struct ModCatalogState {
     raw: String,
     compact: Arc<HashMap<String, ModInfoCached>>,
     categories: Arc<HashMap<String, String>>,
+    changelogs: Arc<HashMap<String, String>>,
     status: ModCacheStatus,
 }
 
@@ -63,6 +95,10 @@ fn raw_mod_cache_path() -> Option<PathBuf> {
     dirs::cache_dir().map(|directory| directory.join("CeleMod").join("mod_list.json"))
 }
 
+fn raw_mod_cache_meta_path() -> Option<PathBuf> {
+    raw_mod_cache_path().map(|path| path.with_extension("meta.json"))
+}
+
 fn timestamp_millis(time: SystemTime) -> u64 {
     time.duration_since(UNIX_EPOCH)
         .unwrap_or_default()
@@ -85,6 +121,7 @@ fn compact_catalog(mods: &[wegfan::Mod]) -> HashMap<String, ModInfoCached> {
                 download_url: item.submission_file.url.clone(),
                 name: item.name.clone(),
                 version: item.version.clone(),
+                xx_hash: item.xx_hash.clone(),
             };
             (compact.name.clone(), compact)
         })
@@ -103,16 +140,18 @@ fn catalog_categories(mods: &[wegfan::Mod]) -> HashMap<String, String> {
         .collect()
 }
 
-fn fetch_raw_catalog() -> anyhow::Result<String> {
-    Ok(get("https://celeste.weg.fan/api/v2/mod/list")
-        .set(
-            "User-Agent",
-            &format!("CeleMod/{}-{}", env!("VERSION"), &env!("GIT_HASH")[..6]),
-        )
-        .timeout(std::time::Duration::from_secs(20))
-        .set("Accept-Encoding", "gzip, deflate, br")
-        .call()?
-        .into_string()?)
+/// The description published with each Mod's current file, which is where
+/// GameBanana authors write their release notes.
+fn catalog_changelogs(mods: &[wegfan::Mod]) -> HashMap<String, String> {
+    mods.iter()
+        .filter(|item| !item.submission_file.description.trim().is_empty())
+        .map(|item| {
+            (
+                item.name.clone(),
+                item.submission_file.description.trim().to_string(),
+            )
+        })
+        .collect()
 }
 
 fn read_raw_cache() -> Option<(String, SystemTime)> {
@@ -122,7 +161,28 @@ fn read_raw_cache() -> Option<(String, SystemTime)> {
     Some((raw, modified))
 }
 
-fn save_raw_cache(raw: &str) {
+fn read_raw_cache_meta() -> RawCacheMeta {
+    raw_mod_cache_meta_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_raw_cache_meta(meta: &RawCacheMeta) {
+    let Some(path) = raw_mod_cache_meta_path() else {
+        return;
+    };
+    if let Err(error) = serde_json::to_vec(meta)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| Ok(std::fs::write(&path, contents)?))
+    {
+        crate::logging::error(format_args!(
+            "Failed to save Mod catalog cache metadata: {error:#}"
+        ));
+    }
+}
+
+fn save_raw_cache(raw: &str, meta: &RawCacheMeta) {
     let Some(path) = raw_mod_cache_path() else {
         return;
     };
@@ -133,17 +193,37 @@ fn save_raw_cache(raw: &str) {
         crate::logging::error(format_args!(
             "Failed to save raw Mod catalog cache: {error}"
         ));
+        return;
     }
+    save_raw_cache_meta(meta);
+}
+
+/// A revalidated cache restarts its TTL without rewriting the catalog.
+fn touch_raw_cache(meta: &RawCacheMeta) {
+    if let Some(path) = raw_mod_cache_path()
+        && let Err(error) = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+    {
+        crate::logging::warn(format_args!(
+            "Failed to refresh Mod catalog cache time: {error}"
+        ));
+    }
+    save_raw_cache_meta(meta);
 }
 
 fn catalog_state_from_raw(
     raw: String,
     source: &str,
+    catalog_source: &str,
+    refresh: CatalogRefresh,
     updated_at: SystemTime,
 ) -> anyhow::Result<ModCatalogState> {
     let mods = Arc::new(parse_raw_catalog(&raw)?);
     let compact = Arc::new(compact_catalog(&mods));
     let categories = Arc::new(catalog_categories(&mods));
+    let changelogs = Arc::new(catalog_changelogs(&mods));
     Ok(ModCatalogState {
         status: ModCacheStatus {
             source: source.to_string(),
@@ -152,10 +232,13 @@ fn catalog_state_from_raw(
             path: raw_mod_cache_path()
                 .map(|path| path.to_string_lossy().into_owned())
                 .unwrap_or_default(),
+            catalog_source: catalog_source.to_string(),
+            refresh,
         },
         raw,
         compact,
         categories,
+        changelogs,
     })
 }
 
@@ -165,11 +248,14 @@ fn cache_is_fresh(modified: SystemTime) -> bool {
 }
 
 fn load_catalog(force_refresh: bool) -> anyhow::Result<ModCatalogState> {
+    let force_refresh = SOURCES_CHANGED.swap(false, Ordering::Relaxed) || force_refresh;
     if !force_refresh {
         if let Some(current) = MOD_CATALOG_STATE.lock().unwrap().as_ref() {
             let updated_at = UNIX_EPOCH + Duration::from_millis(current.status.updated_at);
             if cache_is_fresh(updated_at) {
-                return Ok(current.clone());
+                let mut current = current.clone();
+                current.status.refresh = CatalogRefresh::Hit;
+                return Ok(current);
             }
         }
 
@@ -177,13 +263,57 @@ fn load_catalog(force_refresh: bool) -> anyhow::Result<ModCatalogState> {
             && cache_is_fresh(modified)
         {
             USING_CACHE.store(true, Ordering::Relaxed);
-            return catalog_state_from_raw(raw, "cache", modified);
+            return catalog_state_from_raw(
+                raw,
+                "cache",
+                &read_raw_cache_meta().source,
+                CatalogRefresh::Hit,
+                modified,
+            );
         }
     }
 
-    match fetch_raw_catalog().and_then(|raw| {
-        let state = catalog_state_from_raw(raw.clone(), "network", SystemTime::now())?;
-        save_raw_cache(&raw);
+    let cached = read_raw_cache();
+    let meta = read_raw_cache_meta();
+    let validators = cached
+        .as_ref()
+        .filter(|_| !meta.source.is_empty())
+        .map(|_| (meta.source.as_str(), &meta.validators));
+    match catalog_sources::fetch_catalog(validators).and_then(|(fetch, validators, label)| {
+        let meta = RawCacheMeta {
+            source: label,
+            validators,
+        };
+        let (raw, refresh) = match fetch {
+            catalog_sources::CatalogFetch::Full(raw) => (raw, CatalogRefresh::Full),
+            catalog_sources::CatalogFetch::Delta(delta) => {
+                let (raw, _) = cached.as_ref().context("No cached catalog to update")?;
+                (
+                    catalog_sources::apply_delta(raw, &delta)?,
+                    CatalogRefresh::Delta,
+                )
+            }
+            catalog_sources::CatalogFetch::NotModified => {
+                let (raw, _) = cached.as_ref().context("No cached catalog to revalidate")?;
+                let state = catalog_state_from_raw(
+                    raw.clone(),
+                    "network",
+                    &meta.source,
+                    CatalogRefresh::Revalidated,
+                    SystemTime::now(),
+                )?;
+                touch_raw_cache(&meta);
+                return Ok(state);
+            }
+        };
+        let state = catalog_state_from_raw(
+            raw.clone(),
+            "network",
+            &meta.source,
+            refresh,
+            SystemTime::now(),
+        )?;
+        save_raw_cache(&raw, &meta);
         Ok(state)
     }) {
         Ok(state) => {
@@ -194,9 +324,15 @@ fn load_catalog(force_refresh: bool) -> anyhow::Result<ModCatalogState> {
             crate::logging::error(format_args!(
                 "Failed to fetch Mod catalog: {network_error:#}"
             ));
-            if let Some((raw, modified)) = read_raw_cache() {
+            if let Some((raw, modified)) = cached {
                 USING_CACHE.store(true, Ordering::Relaxed);
-                return catalog_state_from_raw(raw, "stale-cache", modified);
+                return catalog_state_from_raw(
+                    raw,
+                    "stale-cache",
+                    &meta.source,
+                    CatalogRefresh::Stale,
+                    modified,
+                );
             }
             USING_CACHE.store(false, Ordering::Relaxed);
             Err(network_error)
@@ -215,6 +351,15 @@ pub fn set_mod_cache_ttl(seconds: u64) {
     MOD_CACHE_TTL_SECONDS.store(seconds, Ordering::Relaxed);
 }
 
+/// Replaces the ordered list of catalog sources. A different list makes the
+/// next catalog load fetch again instead of trusting the cache.
+pub fn set_mod_catalog_sources(specs: &[String]) -> anyhow::Result<()> {
+    if catalog_sources::set_catalog_sources(specs)? {
+        SOURCES_CHANGED.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
 pub fn get_mod_catalog_json(force_refresh: bool) -> anyhow::Result<String> {
     Ok(catalog(force_refresh)?.raw)
 }
@@ -239,6 +384,14 @@ pub fn get_mod_category(name: &str) -> Option<String> {
     catalog(false).ok()?.categories.get(name).cloned()
 }
 
+pub fn get_mod_categories() -> anyhow::Result<Arc<HashMap<String, String>>> {
+    Ok(catalog(false)?.categories)
+}
+
+pub fn get_mod_changelogs() -> anyhow::Result<Arc<HashMap<String, String>>> {
+    Ok(catalog(false)?.changelogs)
+}
+
 static MAGIC_STR: &str = "EverestBuild";
 static MAGIC_STR_ONLY_ORIGIN_EXE: &str = "_StarJumpEnd+<StartCirclingPlayer>";
 
@@ -247,7 +247,7 @@ fn parse_dialog(content: &str) -> DialogCatalog {
     catalog
 }
 
-fn save_directory(game_path: &Path) -> PathBuf {
+pub(super) fn save_directory(game_path: &Path) -> PathBuf {
     let directories = miaonet_settings_directories(game_path);
     directories
         .iter()
@@ -0,0 +1,292 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blacklist, crash_analysis, everest, get_installed_mods_without_catalog_sync, log_tail,
+    mod_index,
+};
+
+const SNAPSHOT_FILE: &str = "celemod_last_good_launch.json";
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotMod {
+    pub name: String,
+    pub version: String,
+    pub file: String,
+    /// Empty for folder Mods.
+    pub xx_hash: String,
+}
+
+/// What was loaded by a launch that exited without a crash.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchSnapshot {
+    version: u32,
+    taken_at: u64,
+    everest_version: Option<i32>,
+    /// Contents of `everest-launch.txt`, if present.
+    everest_launch: Option<String>,
+    enabled: Vec<SnapshotMod>,
+    /// Installed but blacklisted, to tell re-enabled Mods from new ones.
+    disabled: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedMod {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    /// The archive changed even though the version may be the same.
+    pub rebuilt: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchDiff {
+    pub last_good_at: u64,
+    pub added: Vec<SnapshotMod>,
+    pub removed: Vec<SnapshotMod>,
+    pub updated: Vec<ChangedMod>,
+    pub re_enabled: Vec<SnapshotMod>,
+    pub everest_version: Option<(Option<i32>, Option<i32>)>,
+    pub everest_launch_changed: bool,
+}
+
+impl LaunchDiff {
+    /// Names of Mods that are loaded now but were not loaded, or were loaded
+    /// in another build, by the last good launch.
+    pub fn changed_mods(&self) -> impl Iterator<Item = &str> {
+        self.added
+            .iter()
+            .chain(&self.re_enabled)
+            .map(|item| item.name.as_str())
+            .chain(self.updated.iter().map(|item| item.name.as_str()))
+    }
+}
+
+fn snapshot_path(game_path: &Path) -> PathBuf {
+    game_path.join(SNAPSHOT_FILE)
+}
+
+fn take_snapshot(game_path: &Path) -> LaunchSnapshot {
+    let mods_dir = game_path.join("Mods");
+    let disabled_files = blacklist::direct_blacklisted_files(&game_path.to_string_lossy());
+    let mut enabled = Vec::new();
+    let mut disabled = Vec::new();
+    for local_mod in
+        get_installed_mods_without_catalog_sync(mods_dir.to_string_lossy().into_owned())
+    {
+        if disabled_files.contains(&local_mod.file.to_ascii_lowercase()) {
+            disabled.push(local_mod.name);
+            continue;
+        }
+        let path = mods_dir.join(&local_mod.file);
+        let xx_hash = if path.is_file() {
+            mod_index::archive_xx_hash(&path).unwrap_or_default()
+        } else {
+            String::new()
+        };
+        enabled.push(SnapshotMod {
+            name: local_mod.name,
+            version: local_mod.version,
+            file: local_mod.file,
+            xx_hash,
+        });
+    }
+    mod_index::flush(&mods_dir);
+    enabled.sort_by(|left, right| left.name.cmp(&right.name));
+    disabled.sort();
+    LaunchSnapshot {
+        version: SNAPSHOT_VERSION,
+        taken_at: crash_analysis::now_millis(),
+        everest_version: everest::get_everest_version(&game_path.to_string_lossy()),
+        everest_launch: fs::read_to_string(game_path.join("everest-launch.txt")).ok(),
+        enabled,
+        disabled,
+    }
+}
+
+fn load_snapshot(game_path: &Path) -> Option<LaunchSnapshot> {
+    fs::read(snapshot_path(game_path))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<LaunchSnapshot>(&bytes).ok())
+        .filter(|snapshot| snapshot.version == SNAPSHOT_VERSION)
+}
+
+fn save_snapshot(game_path: &Path, snapshot: &LaunchSnapshot) -> anyhow::Result<()> {
+    let path = snapshot_path(game_path);
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_vec_pretty(snapshot)?)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+fn diff(last_good: &LaunchSnapshot, current: &LaunchSnapshot) -> LaunchDiff {
+    let key = |name: &str| name.to_ascii_lowercase();
+    let before = last_good
+        .enabled
+        .iter()
+        .map(|item| (key(&item.name), item))
+        .collect::<HashMap<_, _>>();
+    let after = current
+        .enabled
+        .iter()
+        .map(|item| key(&item.name))
+        .collect::<HashSet<_>>();
+    let mut changes = LaunchDiff {
+        last_good_at: last_good.taken_at,
+        ..LaunchDiff::default()
+    };
+    for item in &current.enabled {
+        match before.get(&key(&item.name)) {
+            Some(previous) => {
+                let rebuilt = !previous.xx_hash.is_empty()
+                    && !item.xx_hash.is_empty()
+                    && previous.xx_hash != item.xx_hash;
+                if previous.version != item.version || rebuilt {
+                    changes.updated.push(ChangedMod {
+                        name: item.name.clone(),
+                        from: previous.version.clone(),
+                        to: item.version.clone(),
+                        rebuilt,
+                    });
+                }
+            }
+            None if last_good
+                .disabled
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&item.name)) =>
+            {
+                changes.re_enabled.push(item.clone())
+            }
+            None => changes.added.push(item.clone()),
+        }
+    }
+    changes.removed = last_good
+        .enabled
+        .iter()
+        .filter(|item| !after.contains(&key(&item.name)))
+        .cloned()
+        .collect();
+    if last_good.everest_version != current.everest_version {
+        changes.everest_version = Some((last_good.everest_version, current.everest_version));
+    }
+    changes.everest_launch_changed = last_good.everest_launch != current.everest_launch;
+    changes
+}
+
+/// Compares what is installed now with the last launch that exited cleanly.
+pub(super) fn diff_since_last_good(game_path: &Path) -> Option<LaunchDiff> {
+    let last_good = load_snapshot(game_path)?;
+    Some(diff(&last_good, &take_snapshot(game_path)))
+}
+
+/// What is known about a launch before the game starts.
+pub(super) struct PendingLaunch {
+    tail: log_tail::Tail,
+    launched_at: u64,
+    snapshot: LaunchSnapshot,
+}
+
+/// Call right before starting the game: the log position is recorded before
+/// the Mods folder is hashed, so the tail sees every line of the session.
+pub(super) fn prepare_launch(game_path: &Path) -> PendingLaunch {
+    let tail = log_tail::Tail::new(game_path.join("log.txt"));
+    let launched_at = crash_analysis::now_millis();
+    PendingLaunch {
+        tail,
+        launched_at,
+        snapshot: take_snapshot(game_path),
+    }
+}
+
+/// Follows a launch started by CeleMod until it exits. The Mods loaded at
+/// launch become the last good snapshot if the session ended cleanly.
+pub(super) fn watch_launch(game_path: String, launch: PendingLaunch, child: std::process::Child) {
+    std::thread::spawn(move || {
+        let game_path = PathBuf::from(game_path);
+        let PendingLaunch {
+            tail,
+            launched_at,
+            snapshot,
+        } = launch;
+        let outcome = log_tail::follow_launch(&game_path, child, launched_at, tail);
+        if outcome == log_tail::SessionOutcome::Exit
+            && let Err(error) = save_snapshot(&game_path, &snapshot)
+        {
+            crate::logging::error(format_args!(
+                "Failed to save the last good launch: {error:#}"
+            ));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str, version: &str, xx_hash: &str) -> SnapshotMod {
+        SnapshotMod {
+            name: name.to_owned(),
+            version: version.to_owned(),
+            file: format!("{name}.zip"),
+            xx_hash: xx_hash.to_owned(),
+        }
+    }
+
+    #[test]
+    fn diff_reports_added_removed_updated_and_re_enabled_mods() {
+        let last_good = LaunchSnapshot {
+            version: SNAPSHOT_VERSION,
+            taken_at: 1,
+            everest_version: Some(4465),
+            everest_launch: None,
+            enabled: vec![
+                item("Kept", "1.0.0", "aa"),
+                item("Rebuilt", "1.0.0", "bb"),
+                item("Removed", "1.0.0", "cc"),
+                item("Updated", "1.0.0", "dd"),
+            ],
+            disabled: vec!["Returning".to_owned()],
+        };
+        let current = LaunchSnapshot {
+            version: SNAPSHOT_VERSION,
+            taken_at: 2,
+            everest_version: Some(4465),
+            everest_launch: Some("--console".to_owned()),
+            enabled: vec![
+                item("Kept", "1.0.0", "aa"),
+                item("New", "1.0.0", "ee"),
+                item("Rebuilt", "1.0.0", "ff"),
+                item("Returning", "2.0.0", "gg"),
+                item("Updated", "1.1.0", "hh"),
+            ],
+            disabled: Vec::new(),
+        };
+        let changes = diff(&last_good, &current);
+        assert_eq!(changes.added, [item("New", "1.0.0", "ee")]);
+        assert_eq!(changes.removed, [item("Removed", "1.0.0", "cc")]);
+        assert_eq!(changes.re_enabled, [item("Returning", "2.0.0", "gg")]);
+        assert_eq!(
+            changes
+                .updated
+                .iter()
+                .map(|item| (item.name.as_str(), item.rebuilt))
+                .collect::<Vec<_>>(),
+            [("Rebuilt", true), ("Updated", true)]
+        );
+        assert!(changes.everest_version.is_none() && changes.everest_launch_changed);
+        assert_eq!(
+            changes.changed_mods().collect::<Vec<_>>(),
+            ["New", "Returning", "Rebuilt", "Updated"]
+        );
+    }
+}
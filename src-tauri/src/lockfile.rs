@@ -0,0 +1,585 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::{Arc, atomic::AtomicBool},
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    LocalMod, blacklist, download_mod_archive_with_cancel, everest,
+    get_installed_mods_sync_with_catalog, mod_backups, mod_index,
+};
+
+const LOCKFILE_FORMAT: &str = "celemod-lock";
+const LOCKFILE_VERSION: u8 = 1;
+
+/// One Mod pinned by a lockfile. Mods that share an archive (a zip with several
+/// everest.yaml entries) get one entry each with the same `file`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LockedMod {
+    pub name: String,
+    pub version: String,
+    pub file: String,
+    #[serde(default = "unknown_file_id")]
+    pub game_banana_file_id: i64,
+    /// Empty when the installed build is not the one the catalog publishes,
+    /// e.g. a local test build or an outdated archive.
+    #[serde(default)]
+    pub download_url: String,
+    /// xxHash64 of the archive; empty for unpacked directory Mods.
+    #[serde(default)]
+    pub xx_hash: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModLockfile {
+    pub format: String,
+    pub version: u8,
+    pub profile: String,
+    pub mods: Vec<LockedMod>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LockfileAction {
+    Install,
+    Replace,
+    Enable,
+    Disable,
+    /// Missing or different locally, and the lockfile has no URL to fetch it.
+    Unavailable,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LockfileChange {
+    pub name: String,
+    pub action: LockfileAction,
+    pub file: String,
+    pub installed_version: Option<String>,
+    pub locked_version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LockfileSyncPlan {
+    pub profile: String,
+    pub changes: Vec<LockfileChange>,
+    pub unchanged: usize,
+}
+
+fn unknown_file_id() -> i64 {
+    -1
+}
+
+fn mods_directory(game_path: &str) -> String {
+    format!("{game_path}/Mods")
+}
+
+fn installed_mods(game_path: &str) -> Vec<LocalMod> {
+    get_installed_mods_sync_with_catalog(mods_directory(game_path), None)
+}
+
+fn validate_locked_file(file: &str) -> anyhow::Result<()> {
+    if file.is_empty() || Path::new(file).file_name().and_then(|name| name.to_str()) != Some(file) {
+        bail!("Invalid Mod file name in lockfile: {file:?}");
+    }
+    Ok(())
+}
+
+pub fn parse_lockfile(contents: &str) -> anyhow::Result<ModLockfile> {
+    let lockfile: ModLockfile = serde_json::from_str(contents.trim_start_matches('\u{feff}'))
+        .context("Lockfile is not valid JSON")?;
+    if lockfile.format != LOCKFILE_FORMAT {
+        bail!("Not a CeleMod lockfile");
+    }
+    if lockfile.version > LOCKFILE_VERSION {
+        bail!("Lockfile was created by a newer version of CeleMod");
+    }
+    if lockfile.profile.trim().is_empty() {
+        bail!("Lockfile is missing a profile name");
+    }
+    for locked in &lockfile.mods {
+        validate_locked_file(&locked.file)?;
+    }
+    Ok(lockfile)
+}
+
+pub fn read_lockfile(source_path: &str) -> anyhow::Result<ModLockfile> {
+    parse_lockfile(
+        &fs::read_to_string(source_path)
+            .with_context(|| format!("Failed to read lockfile {source_path}"))?,
+    )
+}
+
+fn build_lockfile(
+    game_path: &str,
+    profile_name: &str,
+    catalog: &HashMap<String, everest::ModInfoCached>,
+) -> anyhow::Result<ModLockfile> {
+    let profile = blacklist::resolve_mod_profile(game_path, profile_name)?;
+    let installed = installed_mods(game_path);
+    let names = blacklist::expand_installed_dependencies(&installed, &profile.enabled_mods)
+        .into_iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+
+    let mut hashes = HashMap::<String, String>::new();
+    let mut mods = Vec::new();
+    for mod_info in installed
+        .iter()
+        .filter(|mod_info| names.contains(&mod_info.name.to_ascii_lowercase()))
+    {
+        let path = Path::new(game_path).join("Mods").join(&mod_info.file);
+        let xx_hash = if path.is_file() {
+            match hashes.get(&mod_info.file) {
+                Some(hash) => hash.clone(),
+                None => {
+                    let hash = mod_index::archive_xx_hash(&path)?;
+                    hashes.insert(mod_info.file.clone(), hash.clone());
+                    hash
+                }
+            }
+        } else {
+            String::new()
+        };
+        let published = catalog.get(&mod_info.name).filter(|item| {
+            !xx_hash.is_empty()
+                && (item
+                    .xx_hash
+                    .iter()
+                    .any(|hash| hash.eq_ignore_ascii_case(&xx_hash))
+                    || item.xx_hash.is_empty() && item.version == mod_info.version)
+        });
+        mods.push(LockedMod {
+            name: mod_info.name.clone(),
+            version: mod_info.version.clone(),
+            file: mod_info.file.clone(),
+            game_banana_file_id: published.map(|item| item.game_banana_file_id).unwrap_or(-1),
+            download_url: published
+                .map(|item| item.download_url.clone())
+                .unwrap_or_default(),
+            xx_hash,
+        });
+    }
+    for name in &profile.enabled_mods {
+        if !mods
+            .iter()
+            .any(|locked| locked.name.eq_ignore_ascii_case(name))
+        {
+            crate::logging::warn(format_args!(
+                "Skipping {name} while exporting lockfile: the Mod is not installed"
+            ));
+        }
+    }
+    mods.sort_unstable_by_key(|locked| locked.name.to_ascii_lowercase());
+    mod_index::flush(&Path::new(game_path).join("Mods"));
+    Ok(ModLockfile {
+        format: LOCKFILE_FORMAT.to_string(),
+        version: LOCKFILE_VERSION,
+        profile: profile.name,
+        mods,
+    })
+}
+
+pub fn export_mod_lockfile(
+    game_path: &str,
+    profile_name: &str,
+    destination: &str,
+) -> anyhow::Result<ModLockfile> {
+    let catalog = everest::get_mod_cached_new().unwrap_or_else(|error| {
+        crate::logging::warn(format_args!(
+            "Exporting lockfile without catalog download URLs: {error:#}"
+        ));
+        Arc::default()
+    });
+    let lockfile = build_lockfile(game_path, profile_name, &catalog)?;
+    fs::write(destination, serde_json::to_string_pretty(&lockfile)?)?;
+    Ok(lockfile)
+}
+
+fn locked_mod_matches(game_path: &str, installed: &LocalMod, locked: &LockedMod) -> bool {
+    let path = Path::new(game_path).join("Mods").join(&installed.file);
+    if locked.xx_hash.is_empty() || !path.is_file() {
+        return locked.xx_hash.is_empty() && installed.version == locked.version;
+    }
+    mod_index::archive_xx_hash(&path).is_ok_and(|hash| hash.eq_ignore_ascii_case(&locked.xx_hash))
+}
+
+/// Compares the Mods folder against a lockfile without changing anything.
+pub fn plan_lockfile_sync(
+    game_path: &str,
+    lockfile: &ModLockfile,
+    always_on_mods: &[String],
+) -> LockfileSyncPlan {
+    let installed = installed_mods(game_path);
+    let by_name = installed
+        .iter()
+        .map(|mod_info| (mod_info.name.to_ascii_lowercase(), mod_info))
+        .collect::<HashMap<_, _>>();
+    let blacklisted = blacklist::direct_blacklisted_files(game_path);
+    let always_on = always_on_mods
+        .iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    let mut locked_files = HashSet::new();
+    for locked in &lockfile.mods {
+        let current = by_name.get(&locked.name.to_ascii_lowercase());
+        let action = match current {
+            Some(current) if locked_mod_matches(game_path, current, locked) => {
+                if blacklisted.contains(&current.file.to_ascii_lowercase()) {
+                    Some(LockfileAction::Enable)
+                } else {
+                    None
+                }
+            }
+            _ if locked.download_url.is_empty() => Some(LockfileAction::Unavailable),
+            Some(_) => Some(LockfileAction::Replace),
+            None => Some(LockfileAction::Install),
+        };
+        let file = current
+            .map(|current| current.file.clone())
+            .unwrap_or_else(|| locked.file.clone());
+        locked_files.insert(file.to_ascii_lowercase());
+        match action {
+            Some(action) => changes.push(LockfileChange {
+                name: locked.name.clone(),
+                action,
+                file,
+                installed_version: current.map(|current| current.version.clone()),
+                locked_version: Some(locked.version.clone()),
+            }),
+            None => unchanged += 1,
+        }
+    }
+
+    for mod_info in &installed {
+        let file = mod_info.file.to_ascii_lowercase();
+        if locked_files.contains(&file)
+            || blacklisted.contains(&file)
+            || always_on.contains(&mod_info.name.to_ascii_lowercase())
+        {
+            continue;
+        }
+        changes.push(LockfileChange {
+            name: mod_info.name.clone(),
+            action: LockfileAction::Disable,
+            file: mod_info.file.clone(),
+            installed_version: Some(mod_info.version.clone()),
+            locked_version: None,
+        });
+    }
+    mod_index::flush(&Path::new(game_path).join("Mods"));
+
+    LockfileSyncPlan {
+        profile: lockfile.profile.clone(),
+        changes,
+        unchanged,
+    }
+}
+
+/// Downloads, replaces and disables Mods until the Mods folder matches the
+/// lockfile. The lockfile's profile is rewritten to exactly the locked Mods
+/// and applied, so anything else ends up in blacklist.txt.
+pub fn sync_mod_lockfile(
+    game_path: &str,
+    lockfile: &ModLockfile,
+    always_on_mods: &[String],
+    progress_callback: &mut dyn FnMut(&str, f32),
+    cancel_flag: &Arc<AtomicBool>,
+) -> anyhow::Result<LockfileSyncPlan> {
+    let plan = plan_lockfile_sync(game_path, lockfile, always_on_mods);
+    let unavailable = plan
+        .changes
+        .iter()
+        .filter(|change| change.action == LockfileAction::Unavailable)
+        .map(|change| change.name.as_str())
+        .collect::<Vec<_>>();
+    if !unavailable.is_empty() {
+        bail!(
+            "These locked Mods cannot be downloaded: {}",
+            unavailable.join(", ")
+        );
+    }
+
+    let mods_directory = Path::new(game_path).join("Mods");
+    let mut downloaded = HashSet::new();
+    for change in plan.changes.iter().filter(|change| {
+        matches!(
+            change.action,
+            LockfileAction::Install | LockfileAction::Replace
+        )
+    }) {
+        let locked = lockfile
+            .mods
+            .iter()
+            .find(|locked| locked.name == change.name)
+            .context("Locked Mod disappeared from the plan")?;
+        let installed_path = mods_directory.join(&change.file);
+        // Unpacked directory Mods cannot be overwritten by an archive; the
+        // locked zip goes next to them and the directory is removed once the
+        // archive is in place.
+        let destination = if installed_path.is_dir() {
+            mods_directory.join(&locked.file)
+        } else {
+            installed_path.clone()
+        };
+        if !downloaded.insert(destination.clone()) {
+            continue;
+        }
+        let expected_hashes = if locked.xx_hash.is_empty() {
+            Vec::new()
+        } else {
+            vec![locked.xx_hash.clone()]
+        };
+        download_mod_archive_with_cancel(
+            &locked.download_url,
+            destination.to_string_lossy().as_ref(),
+            &mut |info| progress_callback(&locked.name, info.progress),
+            false,
+            cancel_flag,
+            &expected_hashes,
+        )
+        .with_context(|| format!("Failed to download {}", locked.name))?;
+        if installed_path.is_dir() {
+            mod_backups::remove_to_backups(
+                Path::new(game_path),
+                &installed_path,
+                mod_backups::BackupReason::Replace,
+            )?;
+        }
+    }
+
+    let profile_name = blacklist::set_mod_profile_mods(
+        game_path,
+        &lockfile.profile,
+        lockfile
+            .mods
+            .iter()
+            .map(|locked| locked.name.clone())
+            .collect(),
+    )?;
+    blacklist::apply_mod_blacklist_profiles(game_path, &[profile_name], always_on_mods)?;
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mod_archive_xx_hash;
+    use std::io::Write;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use zip::write::SimpleFileOptions;
+
+    fn test_game_path(name: &str) -> String {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "celemod-lockfile-{name}-{}-{unique}",
+            std::process::id()
+        ));
+        fs::create_dir_all(path.join("Mods")).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn write_mod(game_path: &str, file: &str, yaml: &str) {
+        let archive = fs::File::create(Path::new(game_path).join("Mods").join(file)).unwrap();
+        let mut writer = zip::ZipWriter::new(archive);
+        writer
+            .start_file("everest.yaml", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(yaml.as_bytes()).unwrap();
+        writer.finish().unwrap();
+    }
+
+    fn catalog_entry(name: &str, version: &str, xx_hash: Vec<String>) -> everest::ModInfoCached {
+        everest::ModInfoCached {
+            name: name.to_string(),
+            version: version.to_string(),
+            game_banana_id: 10,
+            game_banana_file_id: 20,
+            download_url: format!("https://example.invalid/{name}.zip"),
+            xx_hash,
+        }
+    }
+
+    fn locked(name: &str, file: &str, xx_hash: &str, download_url: &str) -> LockedMod {
+        LockedMod {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            file: file.to_string(),
+            game_banana_file_id: -1,
+            download_url: download_url.to_string(),
+            xx_hash: xx_hash.to_string(),
+        }
+    }
+
+    #[test]
+    fn hashes_archives_with_xxhash64() {
+        let game_path = test_game_path("hash");
+        let path = Path::new(&game_path).join("empty.zip");
+        fs::write(&path, b"").unwrap();
+        assert_eq!(mod_archive_xx_hash(&path).unwrap(), "ef46db3751d8e999");
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
+    #[test]
+    fn exports_profile_mods_with_dependencies_and_published_builds() {
+        let game_path = test_game_path("export");
+        write_mod(
+            &game_path,
+            "Root.zip",
+            "- Name: Root\n  Version: 1.0.0\n  Dependencies:\n    - Name: Helper\n      Version: 1.2.0\n",
+        );
+        write_mod(
+            &game_path,
+            "Helper.zip",
+            "- Name: Helper\n  Version: 1.3.0\n",
+        );
+        write_mod(&game_path, "Other.zip", "- Name: Other\n  Version: 1.0.0\n");
+        blacklist::set_mod_profile_mods(&game_path, "Team", vec!["Root".to_string()]).unwrap();
+        let root_hash = mod_archive_xx_hash(&Path::new(&game_path).join("Mods/Root.zip")).unwrap();
+        let catalog = HashMap::from([
+            (
+                "Root".to_string(),
+                catalog_entry("Root", "1.0.0", vec![root_hash.clone()]),
+            ),
+            (
+                "Helper".to_string(),
+                catalog_entry("Helper", "1.4.0", vec!["0000000000000000".to_string()]),
+            ),
+        ]);
+
+        let lockfile = build_lockfile(&game_path, "Team", &catalog).unwrap();
+
+        assert_eq!(lockfile.profile, "Team");
+        assert_eq!(
+            lockfile
+                .mods
+                .iter()
+                .map(|locked| locked.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Helper", "Root"]
+        );
+        let helper = &lockfile.mods[0];
+        assert_eq!(helper.version, "1.3.0");
+        assert!(helper.download_url.is_empty());
+        assert_eq!(helper.game_banana_file_id, -1);
+        let root = &lockfile.mods[1];
+        assert_eq!(root.xx_hash, root_hash);
+        assert_eq!(root.download_url, "https://example.invalid/Root.zip");
+        assert_eq!(root.game_banana_file_id, 20);
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
+    #[test]
+    fn plans_installs_replacements_and_disables() {
+        let game_path = test_game_path("plan");
+        write_mod(&game_path, "Kept.zip", "- Name: Kept\n  Version: 1.0.0\n");
+        write_mod(
+            &game_path,
+            "Changed.zip",
+            "- Name: Changed\n  Version: 1.0.0\n",
+        );
+        write_mod(
+            &game_path,
+            "Hidden.zip",
+            "- Name: Hidden\n  Version: 1.0.0\n",
+        );
+        write_mod(&game_path, "Extra.zip", "- Name: Extra\n  Version: 1.0.0\n");
+        write_mod(
+            &game_path,
+            "Pinned.zip",
+            "- Name: Pinned\n  Version: 1.0.0\n",
+        );
+        fs::write(
+            Path::new(&game_path).join("Mods/blacklist.txt"),
+            "Hidden.zip\n",
+        )
+        .unwrap();
+        let hash = |file: &str| {
+            mod_archive_xx_hash(&Path::new(&game_path).join("Mods").join(file)).unwrap()
+        };
+        let lockfile = ModLockfile {
+            format: LOCKFILE_FORMAT.to_string(),
+            version: LOCKFILE_VERSION,
+            profile: "Team".to_string(),
+            mods: vec![
+                locked("Kept", "Kept.zip", &hash("Kept.zip"), ""),
+                locked(
+                    "Changed",
+                    "Changed.zip",
+                    "0000000000000000",
+                    "https://example.invalid/Changed.zip",
+                ),
+                locked("Hidden", "Hidden.zip", &hash("Hidden.zip"), ""),
+                locked(
+                    "Missing",
+                    "Missing.zip",
+                    "0000000000000000",
+                    "https://example.invalid/Missing.zip",
+                ),
+                locked("Local", "Local.zip", "0000000000000000", ""),
+            ],
+        };
+
+        let plan = plan_lockfile_sync(&game_path, &lockfile, &["pinned".to_string()]);
+        let actions = plan
+            .changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.action))
+            .collect::<Vec<_>>();
+
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(
+            actions,
+            [
+                ("Changed", LockfileAction::Replace),
+                ("Hidden", LockfileAction::Enable),
+                ("Missing", LockfileAction::Install),
+                ("Local", LockfileAction::Unavailable),
+                ("Extra", LockfileAction::Disable),
+            ]
+        );
+        let error = sync_mod_lockfile(
+            &game_path,
+            &lockfile,
+            &[],
+            &mut |_, _| {},
+            &Arc::new(AtomicBool::new(false)),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("Local"));
+        assert!(blacklist::get_mod_blacklist_profiles(&game_path).is_empty());
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
+    #[test]
+    fn rejects_foreign_and_unsafe_lockfiles() {
+        assert!(
+            parse_lockfile(r#"{"format":"celemod-profile","version":1,"profile":"A","mods":[]}"#)
+                .is_err()
+        );
+        assert!(
+            parse_lockfile(r#"{"format":"celemod-lock","version":99,"profile":"A","mods":[]}"#)
+                .is_err()
+        );
+        assert!(
+            parse_lockfile(
+                r#"{"format":"celemod-lock","version":1,"profile":"A","mods":[{"name":"A","version":"1.0.0","file":"../A.zip"}]}"#
+            )
+            .is_err()
+        );
+        let lockfile = parse_lockfile(
+            r#"{"format":"celemod-lock","version":1,"profile":"A","mods":[{"name":"A","version":"1.0.0","file":"A.zip"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(lockfile.mods[0].game_banana_file_id, -1);
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    process::Child,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::ipc::Channel;
+
+use super::{IpcEvent, crash_analysis, is_celeste_running, send_event};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Finding a Steam-relaunched process walks the process list; do it less often.
+const PROCESS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// A burst at startup is split so one event stays small.
+const MAX_BATCH_LINES: usize = 500;
+const MAX_READ_BYTES: u64 = 1_000_000;
+
+lazy_static! {
+    static ref SUBSCRIBERS: Mutex<HashMap<PathBuf, Channel<IpcEvent>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+    /// `Info`, `Warn`, `Error`... for Everest lines, `None` otherwise.
+    pub level: Option<String>,
+    pub tag: Option<String>,
+    pub text: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum SessionOutcome {
+    /// Everest logged a critical error, or a crash log appeared.
+    Crash,
+    Exit,
+    /// The process was ended from outside without crashing, which in practice
+    /// means it stopped responding and was killed.
+    Hang,
+    /// The game never wrote a log, e.g. it failed to start.
+    NotStarted,
+}
+
+impl SessionOutcome {
+    fn event_name(self) -> &'static str {
+        match self {
+            Self::Crash => "crash",
+            Self::Exit | Self::NotStarted => "exit",
+            Self::Hang => "hang",
+        }
+    }
+}
+
+/// Streams the log of the next launch of `game_path` to `channel`; replaces
+/// an earlier subscriber. Events:
+///
+/// - `["started", logPath]` when the game begins a new log.
+/// - `["lines", [line...]]` with `{ level, tag, text }` per line.
+/// - `["crashMarker", text]` as soon as Everest reports a critical error.
+/// - `["warning", line]` for Mod loading problems.
+/// - `["crash" | "exit" | "hang", { exitCode, silentForMs, logStarted }]`
+///   once the game process is gone.
+pub(super) fn subscribe(game_path: &str, channel: Channel<IpcEvent>) {
+    SUBSCRIBERS.lock().insert(PathBuf::from(game_path), channel);
+}
+
+pub(super) fn unsubscribe(game_path: &str) -> bool {
+    SUBSCRIBERS.lock().remove(Path::new(game_path)).is_some()
+}
+
+fn emit(game_path: &Path, args: Vec<IpcEvent>) {
+    if let Some(channel) = SUBSCRIBERS.lock().get(game_path) {
+        send_event(channel, args);
+    }
+}
+
+fn parse_line(line: &str) -> LogLine {
+    // `(01/01/2026 10:00:00) [Everest] [Info] [core] message`
+    let fields = line
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(") [Everest] ["))
+        .and_then(|(_, rest)| rest.split_once("] ["))
+        .and_then(|(level, rest)| {
+            let (tag, _) = rest.split_once(']')?;
+            Some((level.to_string(), tag.to_string()))
+        });
+    LogLine {
+        level: fields.as_ref().map(|(level, _)| level.clone()),
+        tag: fields.map(|(_, tag)| tag),
+        text: line.to_string(),
+    }
+}
+
+fn is_load_warning(line: &LogLine) -> bool {
+    let serious = matches!(line.level.as_deref(), Some("Warn" | "Error"));
+    let lower = line.text.to_ascii_lowercase();
+    serious
+        && (line.tag.as_deref() == Some("loader")
+            || lower.contains("failed loading")
+            || lower.contains("could not load")
+            || lower.contains("dependency"))
+}
+
+fn millis(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis().try_into().unwrap_or(u64::MAX))
+}
+
+/// Follows one log file across Everest's rotation at startup.
+pub(super) struct Tail {
+    path: PathBuf,
+    initial_len: u64,
+    offset: u64,
+    started: bool,
+    pending: Vec<u8>,
+}
+
+impl Tail {
+    /// Remembers where the log ends now; create it before the game starts so
+    /// none of its startup lines are skipped.
+    pub(super) fn new(path: PathBuf) -> Self {
+        let initial_len = fs::metadata(&path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        Self {
+            path,
+            initial_len,
+            offset: 0,
+            started: false,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Complete lines written since the last poll.
+    fn poll(&mut self, launched_at: u64) -> Vec<String> {
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return Vec::new();
+        };
+        if !self.started {
+            if millis(metadata.modified()).is_none_or(|modified| modified < launched_at) {
+                return Vec::new();
+            }
+            // Everest moves the previous log to LogHistory and starts a new
+            // file; without a creation time, a shorter file means the same.
+            let recreated = millis(metadata.created())
+                .is_some_and(|created| created >= launched_at)
+                || metadata.len() < self.initial_len;
+            self.offset = if recreated { 0 } else { self.initial_len };
+            self.started = true;
+        }
+        if metadata.len() < self.offset {
+            self.offset = 0;
+            self.pending.clear();
+        }
+        if metadata.len() == self.offset {
+            return Vec::new();
+        }
+        let mut bytes = Vec::new();
+        let read = fs::File::open(&self.path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(self.offset))?;
+            file.take(MAX_READ_BYTES).read_to_end(&mut bytes)
+        });
+        let Ok(read) = read else {
+            return Vec::new();
+        };
+        self.offset += read as u64;
+        self.pending.extend_from_slice(&bytes);
+        let Some(last_newline) = self.pending.iter().rposition(|byte| *byte == b'\n') else {
+            return Vec::new();
+        };
+        let complete = self.pending.drain(..=last_newline).collect::<Vec<_>>();
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect()
+    }
+}
+
+fn classify(log_started: bool, crashed: bool, killed: bool) -> SessionOutcome {
+    if crashed {
+        SessionOutcome::Crash
+    } else if !log_started {
+        SessionOutcome::NotStarted
+    } else if killed {
+        SessionOutcome::Hang
+    } else {
+        SessionOutcome::Exit
+    }
+}
+
+fn handle_lines(game_path: &Path, lines: Vec<String>, crash_seen: &mut bool) {
+    let lines = lines
+        .iter()
+        .map(|line| parse_line(line))
+        .collect::<Vec<_>>();
+    for line in &lines {
+        if crash_analysis::is_crash_marker(&line.text) {
+            *crash_seen = true;
+            emit(
+                game_path,
+                vec![
+                    serde_json::json!("crashMarker"),
+                    serde_json::json!(line.text),
+                ],
+            );
+        } else if is_load_warning(line) {
+            emit(
+                game_path,
+                vec![serde_json::json!("warning"), serde_json::json!(line)],
+            );
+        }
+    }
+    for batch in lines.chunks(MAX_BATCH_LINES) {
+        emit(
+            game_path,
+            vec![serde_json::json!("lines"), serde_json::json!(batch)],
+        );
+    }
+}
+
+/// Tails `log.txt` until the game launched as `child` has exited, including
+/// a copy Steam relaunched in its place, and reports how the session ended.
+pub(super) fn follow_launch(
+    game_path: &Path,
+    mut child: Child,
+    launched_at: u64,
+    mut tail: Tail,
+) -> SessionOutcome {
+    let mut crash_seen = false;
+    let mut last_output = Instant::now();
+    let mut child_status = None;
+    let mut relaunched = false;
+    let mut last_process_check: Option<Instant> = None;
+    loop {
+        let was_started = tail.started;
+        let lines = tail.poll(launched_at);
+        if tail.started && !was_started {
+            emit(
+                game_path,
+                vec![
+                    serde_json::json!("started"),
+                    serde_json::json!(tail.path.to_string_lossy()),
+                ],
+            );
+        }
+        if !lines.is_empty() {
+            last_output = Instant::now();
+            handle_lines(game_path, lines, &mut crash_seen);
+        }
+        if child_status.is_none() {
+            match child.try_wait() {
+                Ok(Some(status)) => child_status = Some(Some(status)),
+                Ok(None) => {}
+                Err(_) => child_status = Some(None),
+            }
+        }
+        if child_status.is_some()
+            && last_process_check.is_none_or(|checked| checked.elapsed() >= PROCESS_POLL_INTERVAL)
+        {
+            last_process_check = Some(Instant::now());
+            if !is_celeste_running(game_path) {
+                break;
+            }
+            // Steam copies exit at once and start the game through Steam.
+            relaunched = true;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    let lines = tail.poll(launched_at);
+    if !lines.is_empty() {
+        last_output = Instant::now();
+        handle_lines(game_path, lines, &mut crash_seen);
+    }
+
+    let status = child_status.flatten().filter(|_| !relaunched);
+    let crashed = crash_seen
+        || crash_analysis::latest_crash_probe(game_path)
+            .is_some_and(|probe| probe.modified_at >= launched_at);
+    let outcome = classify(
+        tail.started,
+        crashed,
+        status.is_some_and(|status| !status.success()),
+    );
+    emit(
+        game_path,
+        vec![
+            serde_json::json!(outcome.event_name()),
+            serde_json::json!({
+                "exitCode": status.and_then(|status| status.code()),
+                "silentForMs": u64::try_from(last_output.elapsed().as_millis()).unwrap_or(u64::MAX),
+                "logStarted": tail.started,
+            }),
+        ],
+    );
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn tails_new_lines_and_classifies_the_session() {
+        let directory = std::env::temp_dir().join(format!(
+            "celemod-log-tail-{}-{}",
+            std::process::id(),
+            crash_analysis::now_millis()
+        ));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("log.txt");
+        fs::write(&path, "old session\n").unwrap();
+
+        let mut tail = Tail::new(path.clone());
+        std::thread::sleep(Duration::from_millis(20));
+        let launched_at = crash_analysis::now_millis();
+        // A previous session's log is not followed until the game writes.
+        assert!(tail.poll(launched_at).is_empty());
+        assert!(!tail.started);
+        // File times use a coarse clock that can lag behind `now_millis`.
+        std::thread::sleep(Duration::from_millis(20));
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(
+            file,
+            "(01/01/2026 10:00:00) [Everest] [Warn] [loader] Failed loading mod Pack.zip\r\npartial"
+        )
+        .unwrap();
+        let lines = tail.poll(launched_at);
+        assert_eq!(lines.len(), 1);
+        let line = parse_line(&lines[0]);
+        assert_eq!(line.level.as_deref(), Some("Warn"));
+        assert_eq!(line.tag.as_deref(), Some("loader"));
+        assert!(is_load_warning(&line));
+
+        writeln!(file, " line").unwrap();
+        assert_eq!(tail.poll(launched_at), ["partial line"]);
+        assert!(parse_line("partial line").level.is_none());
+
+        fs::write(&path, "rotated\n").unwrap();
+        assert_eq!(tail.poll(launched_at), ["rotated"]);
+
+        assert_eq!(classify(true, true, true), SessionOutcome::Crash);
+        assert_eq!(classify(true, false, true), SessionOutcome::Hang);
+        assert_eq!(classify(true, false, false), SessionOutcome::Exit);
+        assert_eq!(classify(false, false, false), SessionOutcome::NotStarted);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -3,12 +3,16 @@ use std::{
     fs::{self, File, OpenOptions},
     io::Write,
     path::{Path, PathBuf},
-    sync::{Mutex, OnceLock},
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
     time::{SystemTime, UNIX_EPOCH},
 };
 
 static LOG_FILE: OnceLock<Mutex<Option<File>>> = OnceLock::new();
 static LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+static CONSOLE_TO_STDERR: AtomicBool = AtomicBool::new(false);
 
 fn default_log_path() -> PathBuf {
     dirs::home_dir()
@@ -37,6 +41,11 @@ pub(crate) fn initialize() {
     }));
 }
 
+/// Keeps stdout clean for machine-readable output, e.g. the headless CLI.
+pub(crate) fn route_console_to_stderr() {
+    CONSOLE_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
 pub(crate) fn path() -> &'static Path {
     LOG_PATH.get_or_init(default_log_path).as_path()
 }
@@ -58,7 +67,7 @@ fn write(level: &str, source: &str, message: fmt::Arguments<'_>) {
         let _ = file.flush();
     }
 
-    if level == "ERROR" || level == "WARN" {
+    if level == "ERROR" || level == "WARN" || CONSOLE_TO_STDERR.load(Ordering::Relaxed) {
         eprint!("{line}");
     } else {
         print!("{line}");
@@ -0,0 +1,576 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{mod_archive_xx_hash, mod_updates, replace_local_mod_archive, verify_mod_archive_hash};
+
+/// Lives beside the Mods folder so a backup never shows up as a Mod.
+const BACKUP_DIRECTORY: &str = "celemod_mod_backups";
+const INDEX_FILE: &str = "index.json";
+const DEFAULT_SIZE_CAP: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Downloads finish on several threads at once; the index is rewritten as a
+/// whole, so every read-modify-write goes through this lock.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackupReason {
+    Delete,
+    Replace,
+    CrashFix,
+    Update,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub id: String,
+    /// File or folder name inside `Mods`.
+    pub file: String,
+    /// xxHash of the stored archive; `None` for folder Mods, which are moved
+    /// into the store as they are.
+    pub hash: Option<String>,
+    pub reason: BackupReason,
+    pub created_at: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupIndex {
+    /// Total size the store may use before the oldest entries are evicted.
+    /// Zero turns backups off entirely.
+    #[serde(default = "default_size_cap")]
+    size_cap: u64,
+    entries: Vec<BackupEntry>,
+}
+
+fn default_size_cap() -> u64 {
+    DEFAULT_SIZE_CAP
+}
+
+impl Default for BackupIndex {
+    fn default() -> Self {
+        Self {
+            size_cap: DEFAULT_SIZE_CAP,
+            entries: Vec::new(),
+        }
+    }
+}
+
+pub(super) fn backups_directory(game_path: &Path) -> PathBuf {
+    game_path.join(BACKUP_DIRECTORY)
+}
+
+fn archives_directory(game_path: &Path) -> PathBuf {
+    backups_directory(game_path).join("archives")
+}
+
+fn folders_directory(game_path: &Path) -> PathBuf {
+    backups_directory(game_path).join("folders")
+}
+
+fn archive_path(game_path: &Path, hash: &str) -> anyhow::Result<PathBuf> {
+    if hash.len() != 16 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("Invalid backup id {hash:?}");
+    }
+    Ok(archives_directory(game_path).join(format!("{}.zip", hash.to_ascii_lowercase())))
+}
+
+fn folder_path(game_path: &Path, entry: &BackupEntry) -> PathBuf {
+    folders_directory(game_path)
+        .join(&entry.id)
+        .join(&entry.file)
+}
+
+/// Sets the total size the store may use before the oldest entries are
+/// evicted. Zero turns backups off entirely. The cap is kept in the index, so
+/// it applies from the next backup on and survives restarts.
+pub fn set_size_cap(game_path: &Path, bytes: u64) -> anyhow::Result<()> {
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+    let mut index = read_index(game_path)?;
+    index.size_cap = bytes;
+    write_index(game_path, &index)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// A missing index is an empty store. An unreadable or damaged one is an
+/// error: treating it as empty would make the next cleanup delete every
+/// stored archive.
+fn read_index(game_path: &Path) -> anyhow::Result<BackupIndex> {
+    let path = backups_directory(game_path).join(INDEX_FILE);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(BackupIndex::default()),
+        Err(error) => {
+            return Err(error).with_context(|| {
+                format!("Failed to read the Mod backup index {}", path.display())
+            });
+        }
+    };
+    serde_json::from_str(&contents).with_context(|| {
+        format!(
+            "The Mod backup index {} is damaged. Repair or remove it before using Mod backups",
+            path.display()
+        )
+    })
+}
+
+fn write_index(game_path: &Path, index: &BackupIndex) -> anyhow::Result<()> {
+    let directory = backups_directory(game_path);
+    fs::create_dir_all(&directory)?;
+    let path = directory.join(INDEX_FILE);
+    let temporary = path.with_extension("json.partial");
+    fs::write(&temporary, serde_json::to_vec_pretty(index)?)?;
+    fs::rename(&temporary, &path)?;
+    Ok(())
+}
+
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => directory_size(&entry.path()),
+            _ => entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+        })
+        .sum()
+}
+
+fn mod_file_name(path: &Path) -> anyhow::Result<String> {
+    Ok(path
+        .file_name()
+        .context("Mod path has no file name")?
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// Archives are shared by hash, so the store size counts each one once.
+fn stored_size(entries: &[BackupEntry]) -> u64 {
+    let mut seen = HashSet::new();
+    entries
+        .iter()
+        .filter(|entry| entry.hash.as_ref().is_none_or(|hash| seen.insert(hash)))
+        .map(|entry| entry.size)
+        .sum()
+}
+
+/// Removes archives and folders no index entry refers to any more.
+fn remove_unreferenced(game_path: &Path, index: &BackupIndex) {
+    if let Ok(archives) = fs::read_dir(archives_directory(game_path)) {
+        for archive in archives.flatten() {
+            let name = archive.file_name().to_string_lossy().into_owned();
+            let referenced = name.strip_suffix(".zip").is_some_and(|hash| {
+                index
+                    .entries
+                    .iter()
+                    .any(|entry| entry.hash.as_deref() == Some(hash))
+            });
+            if !referenced {
+                let _ = fs::remove_file(archive.path());
+            }
+        }
+    }
+    if let Ok(folders) = fs::read_dir(folders_directory(game_path)) {
+        for folder in folders.flatten() {
+            let id = folder.file_name().to_string_lossy().into_owned();
+            if !index
+                .entries
+                .iter()
+                .any(|entry| entry.hash.is_none() && entry.id == id)
+            {
+                let _ = fs::remove_dir_all(folder.path());
+            }
+        }
+    }
+}
+
+/// Drops the oldest entries until the store fits in `cap`. The entry that was
+/// just added is never evicted, even when it alone exceeds the cap, and
+/// neither is an archive an update batch can still roll back to.
+fn enforce_size_cap(game_path: &Path, index: &mut BackupIndex, cap: u64, keep_id: &str) {
+    let pinned = mod_updates::pinned_backup_hashes(game_path);
+    index.entries.sort_by_key(|entry| entry.created_at);
+    while stored_size(&index.entries) > cap {
+        let Some(position) = index.entries.iter().position(|entry| {
+            entry.id != keep_id
+                && entry
+                    .hash
+                    .as_ref()
+                    .is_none_or(|hash| !pinned.contains(&hash.to_ascii_lowercase()))
+        }) else {
+            break;
+        };
+        let evicted = index.entries.remove(position);
+        crate::logging::info(format_args!(
+            "Evicting Mod backup {} ({}) to stay under the size cap",
+            evicted.id, evicted.file
+        ));
+    }
+    remove_unreferenced(game_path, index);
+}
+
+fn next_id(index: &BackupIndex, created_at: u64) -> String {
+    let mut id = created_at;
+    while index.entries.iter().any(|entry| entry.id == id.to_string()) {
+        id += 1;
+    }
+    id.to_string()
+}
+
+fn record_entry(
+    game_path: &Path,
+    index: &mut BackupIndex,
+    file: String,
+    hash: Option<String>,
+    reason: BackupReason,
+    size: u64,
+) -> anyhow::Result<BackupEntry> {
+    let created_at = now_millis();
+    // Update-all backs an archive up before the download replaces it; the
+    // second store of the same bytes only refreshes the existing entry.
+    if let Some(hash) = &hash
+        && let Some(existing) = index
+            .entries
+            .iter_mut()
+            .find(|entry| entry.hash.as_ref() == Some(hash) && entry.file == file)
+    {
+        existing.created_at = created_at;
+        let entry = existing.clone();
+        enforce_size_cap(game_path, index, index.size_cap, &entry.id);
+        write_index(game_path, index)?;
+        return Ok(entry);
+    }
+    let entry = BackupEntry {
+        id: next_id(index, created_at),
+        file,
+        hash,
+        reason,
+        created_at,
+        size,
+    };
+    index.entries.push(entry.clone());
+    enforce_size_cap(game_path, index, index.size_cap, &entry.id);
+    write_index(game_path, index)?;
+    Ok(entry)
+}
+
+fn store_archive_file(game_path: &Path, archive: &Path) -> anyhow::Result<String> {
+    let hash = mod_archive_xx_hash(archive)?;
+    let destination = archive_path(game_path, &hash)?;
+    if !destination.is_file() {
+        fs::create_dir_all(archives_directory(game_path))?;
+        let temporary = destination.with_extension("zip.partial");
+        fs::copy(archive, &temporary)
+            .with_context(|| format!("Failed to back up {}", archive.display()))?;
+        fs::rename(&temporary, &destination)?;
+    }
+    Ok(hash)
+}
+
+/// Copies an archive into the store and returns its xxHash. Identical
+/// archives share a single copy. With the store disabled nothing is kept and
+/// `None` is returned.
+pub fn store_archive(
+    game_path: &Path,
+    archive: &Path,
+    reason: BackupReason,
+) -> anyhow::Result<Option<String>> {
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+    let mut index = read_index(game_path)?;
+    if index.size_cap == 0 {
+        return Ok(None);
+    }
+    let hash = store_archive_file(game_path, archive)?;
+    let size = fs::metadata(archive)?.len();
+    record_entry(
+        game_path,
+        &mut index,
+        mod_file_name(archive)?,
+        Some(hash.clone()),
+        reason,
+        size,
+    )?;
+    Ok(Some(hash))
+}
+
+/// Takes a Mod archive or folder out of `Mods`, keeping it in the store.
+/// Archives are copied by hash and then removed; folders are moved whole.
+pub fn remove_to_backups(
+    game_path: &Path,
+    path: &Path,
+    reason: BackupReason,
+) -> anyhow::Result<()> {
+    if !path.is_dir() {
+        store_archive(game_path, path, reason)?;
+        fs::remove_file(path)?;
+        return Ok(());
+    }
+
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+    let mut index = read_index(game_path)?;
+    if index.size_cap == 0 {
+        fs::remove_dir_all(path)?;
+        return Ok(());
+    }
+    let file = mod_file_name(path)?;
+    let id = next_id(&index, now_millis());
+    let destination = folders_directory(game_path).join(&id).join(&file);
+    fs::create_dir_all(destination.parent().unwrap())?;
+    fs::rename(path, &destination)
+        .with_context(|| format!("Failed to move {} into the backup store", path.display()))?;
+    let entry = BackupEntry {
+        id: id.clone(),
+        file,
+        hash: None,
+        reason,
+        created_at: now_millis(),
+        size: directory_size(&destination),
+    };
+    index.entries.push(entry);
+    let cap = index.size_cap;
+    enforce_size_cap(game_path, &mut index, cap, &id);
+    write_index(game_path, &index)
+}
+
+/// Newest first.
+pub fn list_backups(game_path: &Path) -> anyhow::Result<Vec<BackupEntry>> {
+    let mut entries = read_index(game_path)?.entries;
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
+    Ok(entries)
+}
+
+/// Puts a stored archive back at `destination`, replacing whatever is there.
+pub fn restore_archive(game_path: &Path, hash: &str, destination: &Path) -> anyhow::Result<()> {
+    let source = archive_path(game_path, hash)?;
+    if !source.is_file() {
+        bail!("Backup {hash} no longer exists");
+    }
+    verify_mod_archive_hash(&source, &[hash.to_string()])
+        .with_context(|| format!("Backup {hash} is damaged"))?;
+    if destination.is_dir() {
+        remove_to_backups(game_path, destination, BackupReason::Replace)?;
+    }
+    replace_local_mod_archive(&source, destination, BackupReason::Replace)
+}
+
+/// Restores one entry into `Mods` under its original name. Whatever occupies
+/// that name now is backed up first. Folder entries leave the store, archive
+/// entries stay so the restore itself can be repeated.
+pub fn restore_backup(game_path: &Path, id: &str) -> anyhow::Result<BackupEntry> {
+    let entry = read_index(game_path)?
+        .entries
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .with_context(|| format!("Backup {id} does not exist"))?;
+    let mods_directory = game_path.join("Mods");
+    fs::create_dir_all(&mods_directory)?;
+    let destination = mods_directory.join(&entry.file);
+    match &entry.hash {
+        Some(hash) => restore_archive(game_path, hash, &destination)?,
+        None => {
+            let source = folder_path(game_path, &entry);
+            if !source.is_dir() {
+                bail!("Backup {id} no longer exists");
+            }
+            if destination.exists() {
+                remove_to_backups(game_path, &destination, BackupReason::Replace)?;
+            }
+            fs::rename(&source, &destination)
+                .with_context(|| format!("Failed to restore {}", entry.file))?;
+            let _guard = INDEX_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+            let mut index = read_index(game_path)?;
+            index.entries.retain(|item| item.id != entry.id);
+            remove_unreferenced(game_path, &index);
+            write_index(game_path, &index)?;
+        }
+    }
+    Ok(entry)
+}
+
+/// Deletes the given entries, or the whole store when `ids` is `None`, and
+/// returns how many bytes were freed.
+pub fn purge_backups(game_path: &Path, ids: Option<&[String]>) -> anyhow::Result<u64> {
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+    let mut index = read_index(game_path)?;
+    let before = stored_size(&index.entries);
+    match ids {
+        Some(ids) => {
+            if let Some(missing) = ids
+                .iter()
+                .find(|id| !index.entries.iter().any(|entry| &entry.id == *id))
+            {
+                bail!("Backup {missing} does not exist");
+            }
+            index.entries.retain(|entry| !ids.contains(&entry.id));
+        }
+        None => index.entries.clear(),
+    }
+    remove_unreferenced(game_path, &index);
+    write_index(game_path, &index)?;
+    Ok(before - stored_size(&index.entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_game_path(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "celemod-backups-{name}-{}-{unique}",
+            std::process::id()
+        ));
+        fs::create_dir_all(path.join("Mods")).unwrap();
+        path
+    }
+
+    #[test]
+    fn deleted_archives_and_folders_can_be_restored() {
+        let game_path = test_game_path("restore");
+        let archive = game_path.join("Mods").join("Archive.zip");
+        let folder = game_path.join("Mods").join("FolderMod");
+        fs::write(&archive, b"archive").unwrap();
+        fs::create_dir_all(folder.join("nested")).unwrap();
+        fs::write(folder.join("nested").join("file.txt"), b"folder mod").unwrap();
+
+        remove_to_backups(&game_path, &archive, BackupReason::Delete).unwrap();
+        remove_to_backups(&game_path, &folder, BackupReason::Delete).unwrap();
+        assert!(!archive.exists() && !folder.exists());
+
+        let backups = list_backups(&game_path).unwrap();
+        assert_eq!(backups.len(), 2);
+        for entry in &backups {
+            restore_backup(&game_path, &entry.id).unwrap();
+        }
+        assert_eq!(fs::read(&archive).unwrap(), b"archive");
+        assert_eq!(
+            fs::read(folder.join("nested").join("file.txt")).unwrap(),
+            b"folder mod"
+        );
+        assert_eq!(list_backups(&game_path).unwrap().len(), 1);
+
+        assert_eq!(purge_backups(&game_path, None).unwrap(), 7);
+        assert!(list_backups(&game_path).unwrap().is_empty());
+        assert_eq!(
+            fs::read_dir(archives_directory(&game_path))
+                .unwrap()
+                .count(),
+            0
+        );
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
+    #[test]
+    fn size_cap_evicts_the_oldest_entries_first() {
+        let game_path = test_game_path("eviction");
+        let mut index = BackupIndex::default();
+        for (id, contents) in [("1", "first"), ("2", "second"), ("3", "third")] {
+            let archive = game_path.join("Mods").join(format!("{id}.zip"));
+            fs::write(&archive, contents).unwrap();
+            let hash = store_archive_file(&game_path, &archive).unwrap();
+            index.entries.push(BackupEntry {
+                id: id.to_string(),
+                file: format!("{id}.zip"),
+                hash: Some(hash),
+                reason: BackupReason::Replace,
+                created_at: id.parse().unwrap(),
+                size: contents.len() as u64,
+            });
+        }
+
+        enforce_size_cap(&game_path, &mut index, 11, "3");
+        let ids = index
+            .entries
+            .iter()
+            .map(|entry| entry.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["2", "3"]);
+        assert_eq!(
+            fs::read_dir(archives_directory(&game_path))
+                .unwrap()
+                .count(),
+            2
+        );
+
+        // An update batch that can still roll back to "2" keeps it stored.
+        let journal = mod_updates::UpdateJournal {
+            id: "1".to_string(),
+            created_at: 1,
+            entries: vec![mod_updates::UpdateJournalEntry {
+                name: "Two".to_string(),
+                file: "2.zip".to_string(),
+                previous_version: Some("1.0.0".to_string()),
+                new_version: "1.1.0".to_string(),
+                backup_hash: index.entries[0].hash.clone(),
+                status: mod_updates::UpdateStatus::Updated,
+                error: String::new(),
+                changelog: None,
+            }],
+        };
+        let journals = backups_directory(&game_path).join("updates");
+        fs::create_dir_all(&journals).unwrap();
+        fs::write(
+            journals.join("1.json"),
+            serde_json::to_vec(&journal).unwrap(),
+        )
+        .unwrap();
+
+        enforce_size_cap(&game_path, &mut index, 1, "3");
+        let ids = index
+            .entries
+            .iter()
+            .map(|entry| entry.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["2", "3"]);
+
+        fs::remove_file(journals.join("1.json")).unwrap();
+        enforce_size_cap(&game_path, &mut index, 1, "3");
+        assert_eq!(index.entries.len(), 1);
+        assert_eq!(index.entries[0].id, "3");
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
+    #[test]
+    fn damaged_index_keeps_stored_archives() {
+        let game_path = test_game_path("damaged-index");
+        let archive = game_path.join("Mods").join("Archive.zip");
+        fs::write(&archive, b"archive").unwrap();
+        set_size_cap(&game_path, 64).unwrap();
+        let hash = store_archive(&game_path, &archive, BackupReason::Replace)
+            .unwrap()
+            .unwrap();
+
+        let index = backups_directory(&game_path).join(INDEX_FILE);
+        let mut contents = fs::read_to_string(&index).unwrap();
+        assert!(contents.contains("\"sizeCap\": 64"));
+        contents.truncate(contents.len() / 2);
+        fs::write(&index, contents).unwrap();
+
+        fs::write(&archive, b"changed archive").unwrap();
+        assert!(store_archive(&game_path, &archive, BackupReason::Replace).is_err());
+        assert!(list_backups(&game_path).is_err());
+        assert!(archive_path(&game_path, &hash).unwrap().is_file());
+        fs::remove_dir_all(game_path).unwrap();
+    }
+}
@@ -0,0 +1,519 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    EverestModMetadata, mod_archive_xx_hash, parse_mod_yaml_document, read_mod_yaml_bytes,
+    read_to_string_bom,
+};
+
+/// Stored beside the Mods folder, where `celemod_yaml_cache` used to be.
+const INDEX_FILE: &str = "celemod_mod_index.json";
+/// One `.yaml` per archive, replaced by the index.
+const LEGACY_YAML_CACHE: &str = "celemod_yaml_cache";
+/// Version 2 added `assemblies`; older indexes are rebuilt.
+const INDEX_VERSION: u32 = 2;
+/// Folder Mods keep their DLLs in `bin/` or similar; deeper trees are source.
+const ASSEMBLY_SEARCH_DEPTH: usize = 3;
+
+/// Everything the app needs from one entry of the Mods folder. An entry is
+/// reused for as long as its stamp (size and modification time of the
+/// archive, or of a folder Mod's `everest.yaml`) stays the same.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct IndexedMod {
+    pub file: String,
+    pub directory: bool,
+    pub stamp_size: u64,
+    pub stamp_modified: u64,
+    pub metadata: Vec<EverestModMetadata>,
+    /// Why the metadata could not be read; the entry is still kept so a
+    /// broken archive is not reopened on every scan.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Filled the first time something asks for the archive hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xx_hash: Option<String>,
+    /// File stems of the `.dll` files the Mod ships, for matching stack
+    /// frames. A folder Mod's list is refreshed with its `everest.yaml`.
+    #[serde(default)]
+    pub assemblies: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    mods: HashMap<String, IndexedMod>,
+}
+
+#[derive(Default)]
+struct LoadedIndex {
+    mods: HashMap<String, Arc<IndexedMod>>,
+    dirty: bool,
+}
+
+lazy_static! {
+    static ref INDEXES: Mutex<HashMap<PathBuf, LoadedIndex>> = Mutex::new(HashMap::new());
+}
+
+fn index_path(mods_dir: &Path) -> Option<PathBuf> {
+    mods_dir.parent().map(|parent| parent.join(INDEX_FILE))
+}
+
+fn load_index(mods_dir: &Path) -> LoadedIndex {
+    let mods = index_path(mods_dir)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<IndexFile>(&contents).ok())
+        .filter(|index| index.version == INDEX_VERSION)
+        .map(|index| {
+            index
+                .mods
+                .into_iter()
+                .map(|(file, entry)| (file, Arc::new(entry)))
+                .collect()
+        })
+        .unwrap_or_default();
+    LoadedIndex { mods, dirty: false }
+}
+
+fn save_index(mods_dir: &Path, index: &mut LoadedIndex) {
+    let Some(path) = index_path(mods_dir) else {
+        return;
+    };
+    let file = IndexFile {
+        version: INDEX_VERSION,
+        mods: index
+            .mods
+            .iter()
+            .map(|(file, entry)| (file.clone(), entry.as_ref().clone()))
+            .collect(),
+    };
+    let temporary = path.with_extension("json.partial");
+    let result = serde_json::to_vec(&file)
+        .map_err(anyhow::Error::from)
+        .and_then(|contents| {
+            fs::write(&temporary, contents)?;
+            fs::rename(&temporary, &path)?;
+            Ok(())
+        });
+    match result {
+        Ok(()) => {
+            index.dirty = false;
+            let legacy = mods_dir.with_file_name(LEGACY_YAML_CACHE);
+            if legacy.is_dir() {
+                let _ = fs::remove_dir_all(legacy);
+            }
+        }
+        Err(error) => crate::logging::warn(format_args!(
+            "Failed to save the Mod index {}: {error:#}",
+            path.display()
+        )),
+    }
+}
+
+fn modified_millis(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|value| value.duration_since(UNIX_EPOCH).ok())
+        .map(|value| value.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn directory_yaml(path: &Path) -> Option<PathBuf> {
+    fs::read_dir(path).ok()?.flatten().find_map(|entry| {
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        (name == "everest.yaml" || name == "everest.yml").then(|| entry.path())
+    })
+}
+
+/// The file whose stamp decides whether an entry is stale, or `None` when
+/// the entry is not a Mod at all.
+fn stamp_source(path: &Path, directory: bool) -> Option<PathBuf> {
+    if directory {
+        directory_yaml(path)
+    } else {
+        path.extension()
+            .is_some_and(|extension| extension == "zip")
+            .then(|| path.to_path_buf())
+    }
+}
+
+fn read_archive_yaml(path: &Path) -> anyhow::Result<String> {
+    use strip_bom::StripBom;
+
+    Ok(String::from_utf8(read_mod_yaml_bytes(path)?)?
+        .strip_bom()
+        .to_string())
+}
+
+fn dll_stem(name: &str) -> Option<String> {
+    let file_name = name.rsplit(['/', '\\']).next()?;
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    (extension.eq_ignore_ascii_case("dll") && !stem.is_empty()).then(|| stem.to_string())
+}
+
+fn archive_assemblies(path: &Path) -> Vec<String> {
+    fs::File::open(path)
+        .ok()
+        .and_then(|file| zip::ZipArchive::new(file).ok())
+        .map(|archive| archive.file_names().filter_map(dll_stem).collect())
+        .unwrap_or_default()
+}
+
+fn directory_assemblies(path: &Path, depth: usize, assemblies: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        if entry_path.is_dir() {
+            if depth > 0 {
+                directory_assemblies(&entry_path, depth - 1, assemblies);
+            }
+        } else if let Some(stem) = dll_stem(&entry.file_name().to_string_lossy()) {
+            assemblies.push(stem);
+        }
+    }
+}
+
+fn mod_assemblies(path: &Path, directory: bool) -> Vec<String> {
+    let mut assemblies = Vec::new();
+    if directory {
+        directory_assemblies(path, ASSEMBLY_SEARCH_DEPTH, &mut assemblies);
+    } else {
+        assemblies = archive_assemblies(path);
+    }
+    assemblies.sort();
+    assemblies.dedup();
+    assemblies
+}
+
+fn index_entry(
+    path: &Path,
+    file: &str,
+    directory: bool,
+    stamp: &Path,
+) -> anyhow::Result<IndexedMod> {
+    let metadata = fs::metadata(stamp)?;
+    let parsed = if directory {
+        read_to_string_bom(stamp)
+    } else {
+        read_archive_yaml(path)
+    }
+    .and_then(|yaml| Ok(parse_mod_yaml_document(&yaml)?));
+    let (metadata_entries, error) = match parsed {
+        Ok(entries) => (entries, None),
+        Err(error) => {
+            crate::logging::warn(format_args!(
+                "[ WARNING ] Failed to parse {file:?}: {error:#}"
+            ));
+            (Vec::new(), Some(format!("{error:#}")))
+        }
+    };
+    if metadata_entries.iter().any(|entry| entry.name.is_none()) {
+        crate::logging::warn(format_args!(
+            "[ WARNING ] Skipping unnamed metadata in {file:?}"
+        ));
+    }
+    Ok(IndexedMod {
+        file: file.to_string(),
+        directory,
+        stamp_size: metadata.len(),
+        stamp_modified: modified_millis(&metadata),
+        metadata: metadata_entries,
+        error,
+        xx_hash: None,
+        assemblies: mod_assemblies(path, directory),
+    })
+}
+
+/// One entry of the Mods folder and the file that stamps it.
+struct Candidate {
+    path: PathBuf,
+    file: String,
+    directory: bool,
+    stamp: PathBuf,
+    stamp_metadata: fs::Metadata,
+}
+
+fn candidate(path: PathBuf, file: String, directory: bool) -> Option<Candidate> {
+    let stamp = stamp_source(&path, directory)?;
+    let stamp_metadata = fs::metadata(&stamp).ok()?;
+    Some(Candidate {
+        path,
+        file,
+        directory,
+        stamp,
+        stamp_metadata,
+    })
+}
+
+/// The indexed entry of each candidate that is still current.
+fn current_entries(mods_dir: &Path, candidates: &[Candidate]) -> Vec<Option<Arc<IndexedMod>>> {
+    let mut indexes = INDEXES.lock().unwrap();
+    let index = indexes
+        .entry(mods_dir.to_path_buf())
+        .or_insert_with(|| load_index(mods_dir));
+    candidates
+        .iter()
+        .map(|candidate| {
+            index
+                .mods
+                .get(&candidate.file)
+                .filter(|existing| {
+                    existing.directory == candidate.directory
+                        && existing.stamp_size == candidate.stamp_metadata.len()
+                        && existing.stamp_modified == modified_millis(&candidate.stamp_metadata)
+                })
+                .cloned()
+        })
+        .collect()
+}
+
+/// Uses the current entry of every candidate and indexes the rest. Archives
+/// are opened without holding the index lock, so a slow scan never blocks
+/// lookups of other Mods folders or of entries that did not change.
+fn refresh_entries(
+    mods_dir: &Path,
+    candidates: &[Candidate],
+) -> (Vec<Arc<IndexedMod>>, Vec<Arc<IndexedMod>>) {
+    let mut mods = Vec::with_capacity(candidates.len());
+    let mut fresh = Vec::new();
+    for (candidate, current) in candidates.iter().zip(current_entries(mods_dir, candidates)) {
+        let entry = match current {
+            Some(entry) => entry,
+            None => {
+                let Ok(entry) = index_entry(
+                    &candidate.path,
+                    &candidate.file,
+                    candidate.directory,
+                    &candidate.stamp,
+                ) else {
+                    continue;
+                };
+                let entry = Arc::new(entry);
+                fresh.push(Arc::clone(&entry));
+                entry
+            }
+        };
+        mods.push(entry);
+    }
+    (mods, fresh)
+}
+
+fn insert_entries(index: &mut LoadedIndex, fresh: Vec<Arc<IndexedMod>>) {
+    for entry in fresh {
+        index.mods.insert(entry.file.clone(), entry);
+        index.dirty = true;
+    }
+}
+
+/// Lists every Mod in `mods_dir`, in directory order, parsing only the
+/// entries that changed since the last scan.
+pub(super) fn scan_mods(mods_dir: &Path) -> Vec<Arc<IndexedMod>> {
+    let Ok(entries) = fs::read_dir(mods_dir) else {
+        return Vec::new();
+    };
+    let candidates = entries
+        .flatten()
+        .filter_map(|entry| {
+            let directory = entry.file_type().ok()?.is_dir();
+            let file = entry.file_name().to_string_lossy().into_owned();
+            candidate(entry.path(), file, directory)
+        })
+        .collect::<Vec<_>>();
+    let (mods, fresh) = refresh_entries(mods_dir, &candidates);
+
+    let seen = mods
+        .iter()
+        .map(|entry| entry.file.as_str())
+        .collect::<HashSet<_>>();
+    let mut indexes = INDEXES.lock().unwrap();
+    let index = indexes
+        .entry(mods_dir.to_path_buf())
+        .or_insert_with(|| load_index(mods_dir));
+    insert_entries(index, fresh);
+    let before = index.mods.len();
+    index.mods.retain(|file, _| seen.contains(file.as_str()));
+    if index.dirty || before != index.mods.len() {
+        save_index(mods_dir, index);
+    }
+    mods
+}
+
+/// Looks up a single entry without scanning the whole folder.
+pub(super) fn indexed_mod(mods_dir: &Path, file: &str) -> Option<Arc<IndexedMod>> {
+    let path = mods_dir.join(file);
+    let directory = path.is_dir();
+    let candidates = [candidate(path, file.to_string(), directory)?];
+    let (mut mods, fresh) = refresh_entries(mods_dir, &candidates);
+    if !fresh.is_empty() {
+        let mut indexes = INDEXES.lock().unwrap();
+        let index = indexes
+            .entry(mods_dir.to_path_buf())
+            .or_insert_with(|| load_index(mods_dir));
+        insert_entries(index, fresh);
+    }
+    mods.pop()
+}
+
+/// `mod_archive_xx_hash` for an archive inside a Mods folder, remembered in
+/// the index until the archive changes. Call `flush` after a batch.
+pub(super) fn archive_xx_hash(path: &Path) -> anyhow::Result<String> {
+    let (Some(mods_dir), Some(file)) = (path.parent(), path.file_name()) else {
+        return mod_archive_xx_hash(path);
+    };
+    let file = file.to_string_lossy();
+    if let Some(hash) = indexed_mod(mods_dir, &file).and_then(|entry| entry.xx_hash.clone()) {
+        return Ok(hash);
+    }
+    let hash = mod_archive_xx_hash(path)?;
+    let mut indexes = INDEXES.lock().unwrap();
+    if let Some(index) = indexes.get_mut(mods_dir)
+        && let Some(entry) = index.mods.get_mut(file.as_ref())
+    {
+        // A concurrent change would have produced a new entry; only the one
+        // that was current when hashing started gets the hash.
+        Arc::make_mut(entry).xx_hash = Some(hash.clone());
+        index.dirty = true;
+    }
+    Ok(hash)
+}
+
+/// Persists hashes and entries recorded since the last save.
+pub(super) fn flush(mods_dir: &Path) {
+    let mut indexes = INDEXES.lock().unwrap();
+    if let Some(index) = indexes.get_mut(mods_dir)
+        && index.dirty
+    {
+        save_index(mods_dir, index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::SystemTime;
+    use zip::write::SimpleFileOptions;
+
+    fn test_mods_dir(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir()
+            .join(format!(
+                "celemod-index-{name}-{}-{unique}",
+                std::process::id()
+            ))
+            .join("Mods");
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn write_mod(path: &Path, yaml: &str) {
+        let mut writer = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        writer
+            .start_file("everest.yaml", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(yaml.as_bytes()).unwrap();
+        writer.finish().unwrap();
+    }
+
+    fn names(mods: &[Arc<IndexedMod>]) -> Vec<String> {
+        let mut names = mods
+            .iter()
+            .flat_map(|entry| entry.metadata.iter())
+            .filter_map(|metadata| metadata.name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn scans_incrementally_and_persists_the_index() {
+        let mods_dir = test_mods_dir("incremental");
+        write_mod(
+            &mods_dir.join("Pack.zip"),
+            "- Name: First\n  Version: 1.0.0\n- Name: Second\n  Version: 2.0.0\n",
+        );
+        fs::create_dir_all(mods_dir.join("Folder")).unwrap();
+        fs::write(
+            mods_dir.join("Folder").join("everest.yaml"),
+            "- Name: FolderMod\n  Version: 0.1.0\n",
+        )
+        .unwrap();
+        fs::write(mods_dir.join("notes.txt"), "not a Mod").unwrap();
+        fs::write(mods_dir.join("Broken.zip"), "not a zip").unwrap();
+
+        let mods = scan_mods(&mods_dir);
+        assert_eq!(mods.len(), 3);
+        assert_eq!(names(&mods), ["First", "FolderMod", "Second"]);
+        let broken = mods
+            .iter()
+            .find(|entry| entry.file == "Broken.zip")
+            .unwrap();
+        assert!(broken.error.is_some());
+        let index_file = index_path(&mods_dir).unwrap();
+        assert!(index_file.is_file());
+
+        // A fresh process trusts the stored entries while the stamps match.
+        let mut stored: IndexFile =
+            serde_json::from_str(&fs::read_to_string(&index_file).unwrap()).unwrap();
+        stored.mods.get_mut("Pack.zip").unwrap().metadata[0].name = Some("FromIndex".into());
+        fs::write(&index_file, serde_json::to_vec(&stored).unwrap()).unwrap();
+        INDEXES.lock().unwrap().remove(&mods_dir);
+        assert!(names(&scan_mods(&mods_dir)).contains(&"FromIndex".to_string()));
+
+        fs::remove_file(mods_dir.join("Pack.zip")).unwrap();
+        write_mod(
+            &mods_dir.join("Pack.zip"),
+            "- Name: Replaced\n  Version: 3.0.0\n",
+        );
+        fs::remove_dir_all(mods_dir.join("Folder")).unwrap();
+        let mods = scan_mods(&mods_dir);
+        assert_eq!(names(&mods), ["Replaced"]);
+        let stored: IndexFile =
+            serde_json::from_str(&fs::read_to_string(&index_file).unwrap()).unwrap();
+        assert!(!stored.mods.contains_key("Folder"));
+
+        fs::remove_dir_all(mods_dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn archive_hashes_are_cached_until_the_archive_changes() {
+        let mods_dir = test_mods_dir("hashes");
+        let archive = mods_dir.join("Example.zip");
+        write_mod(&archive, "- Name: Example\n  Version: 1.0.0\n");
+
+        let hash = archive_xx_hash(&archive).unwrap();
+        assert_eq!(hash, mod_archive_xx_hash(&archive).unwrap());
+        flush(&mods_dir);
+        let stored: IndexFile =
+            serde_json::from_str(&fs::read_to_string(index_path(&mods_dir).unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(
+            stored.mods["Example.zip"].xx_hash.as_deref(),
+            Some(hash.as_str())
+        );
+
+        fs::remove_file(&archive).unwrap();
+        write_mod(&archive, "- Name: Example\n  Version: 1.0.10\n");
+        assert_eq!(
+            archive_xx_hash(&archive).unwrap(),
+            mod_archive_xx_hash(&archive).unwrap()
+        );
+        assert_ne!(archive_xx_hash(&archive).unwrap(), hash);
+
+        fs::remove_dir_all(mods_dir.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,537 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{DownloadInfo, DownloadStatus, LocalMod, everest, mod_backups, resolver::ModVersion};
+
+/// Update batches kept for rollback; older ones are deleted by the next batch.
+const KEPT_UPDATE_JOURNALS: usize = 20;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModUpdate {
+    pub name: String,
+    pub file: String,
+    pub installed_version: String,
+    pub latest_version: String,
+    pub download_url: String,
+    pub game_banana_file_id: i64,
+    /// Release notes published with the new file, when the catalog has any.
+    pub changelog: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateStatus {
+    Pending,
+    Updated,
+    /// A dependency the batch pulled in that was not installed before.
+    Installed,
+    Failed,
+    RolledBack,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateJournalEntry {
+    pub name: String,
+    pub file: String,
+    pub previous_version: Option<String>,
+    pub new_version: String,
+    pub backup_hash: Option<String>,
+    pub status: UpdateStatus,
+    #[serde(default)]
+    pub error: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateJournal {
+    pub id: String,
+    pub created_at: u64,
+    pub entries: Vec<UpdateJournalEntry>,
+}
+
+/// Zipped Mods whose catalog version is newer than the installed one. Unpacked
+/// directory Mods are usually work in progress and are never replaced.
+pub fn plan_mod_updates(
+    mods_directory: &Path,
+    installed: &[LocalMod],
+    catalog: &HashMap<String, everest::ModInfoCached>,
+) -> Vec<ModUpdate> {
+    let mut seen_files = HashSet::new();
+    let mut updates = installed
+        .iter()
+        .filter(|item| mods_directory.join(&item.file).is_file())
+        .filter_map(|item| {
+            let latest = catalog.get(&item.name)?;
+            (ModVersion::parse(&latest.version)? > ModVersion::parse(&item.version)?).then(|| {
+                ModUpdate {
+                    name: item.name.clone(),
+                    file: item.file.clone(),
+                    installed_version: item.version.clone(),
+                    latest_version: latest.version.clone(),
+                    download_url: latest.download_url.clone(),
+                    game_banana_file_id: latest.game_banana_file_id,
+                    changelog: None,
+                }
+            })
+        })
+        .filter(|update| seen_files.insert(update.file.to_ascii_lowercase()))
+        .collect::<Vec<_>>();
+    updates.sort_unstable_by_key(|update| update.name.to_ascii_lowercase());
+    updates
+}
+
+/// Fills in the release notes the catalog published with each new version.
+pub fn attach_changelogs(updates: &mut [ModUpdate]) {
+    let Ok(changelogs) = everest::get_mod_changelogs() else {
+        return;
+    };
+    for update in updates {
+        update.changelog = changelogs.get(&update.name).cloned();
+    }
+}
+
+fn journal_directory(game_path: &Path) -> PathBuf {
+    mod_backups::backups_directory(game_path).join("updates")
+}
+
+fn journal_path(game_path: &Path, id: &str) -> anyhow::Result<PathBuf> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        bail!("Invalid update batch id {id:?}");
+    }
+    Ok(journal_directory(game_path).join(format!("{id}.json")))
+}
+
+fn write_journal(game_path: &Path, journal: &UpdateJournal) -> anyhow::Result<()> {
+    fs::create_dir_all(journal_directory(game_path))?;
+    fs::write(
+        journal_path(game_path, &journal.id)?,
+        serde_json::to_string_pretty(journal)?,
+    )?;
+    Ok(())
+}
+
+pub fn read_update_journal(game_path: &Path, id: &str) -> anyhow::Result<UpdateJournal> {
+    let path = journal_path(game_path, id)?;
+    let contents = fs::read_to_string(&path).context("Update batch not found")?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// Every recorded batch, newest first.
+pub fn list_update_journals(game_path: &Path) -> Vec<UpdateJournal> {
+    let Ok(entries) = fs::read_dir(journal_directory(game_path)) else {
+        return Vec::new();
+    };
+    let mut journals = entries
+        .flatten()
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|contents| serde_json::from_str::<UpdateJournal>(&contents).ok())
+        .collect::<Vec<_>>();
+    journals.sort_unstable_by_key(|journal| std::cmp::Reverse(journal.created_at));
+    journals
+}
+
+/// Removes all but the newest `KEPT_UPDATE_JOURNALS` batches.
+fn prune_update_journals(game_path: &Path) {
+    for journal in list_update_journals(game_path)
+        .into_iter()
+        .skip(KEPT_UPDATE_JOURNALS)
+    {
+        if let Ok(path) = journal_path(game_path, &journal.id)
+            && let Err(error) = fs::remove_file(&path)
+        {
+            crate::logging::warn(format_args!(
+                "Failed to remove update batch {}: {error}",
+                path.display()
+            ));
+        }
+    }
+}
+
+/// Archives a recorded batch can still roll back to. The backup store never
+/// evicts these. Once a newer batch updates the same Mod, the older backup is
+/// no longer pinned: rolling back restores the newest state.
+pub fn pinned_backup_hashes(game_path: &Path) -> HashSet<String> {
+    let mut superseded = HashSet::new();
+    let mut pinned = HashSet::new();
+    for journal in list_update_journals(game_path) {
+        let mut updated = Vec::new();
+        for entry in journal.entries {
+            let key = entry.name.to_ascii_lowercase();
+            if superseded.contains(&key) {
+                continue;
+            }
+            if matches!(entry.status, UpdateStatus::Pending | UpdateStatus::Updated) {
+                updated.push(key);
+            }
+            if entry.status != UpdateStatus::RolledBack
+                && let Some(hash) = entry.backup_hash
+            {
+                pinned.insert(hash.to_ascii_lowercase());
+            }
+        }
+        superseded.extend(updated);
+    }
+    pinned
+}
+
+/// Backs up every archive that is about to be replaced and records the batch
+/// before the first download starts, so a crash mid-update can still be rolled
+/// back.
+pub fn begin_update_batch(
+    game_path: &Path,
+    updates: &[ModUpdate],
+) -> anyhow::Result<UpdateJournal> {
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let mut id = created_at;
+    while journal_path(game_path, &id.to_string())?.exists() {
+        id += 1;
+    }
+    let mut journal = UpdateJournal {
+        id: id.to_string(),
+        created_at,
+        entries: Vec::with_capacity(updates.len()),
+    };
+    for update in updates {
+        let archive = game_path.join("Mods").join(&update.file);
+        let backup_hash =
+            mod_backups::store_archive(game_path, &archive, mod_backups::BackupReason::Update)?;
+        journal.entries.push(UpdateJournalEntry {
+            name: update.name.clone(),
+            file: update.file.clone(),
+            previous_version: Some(update.installed_version.clone()),
+            new_version: update.latest_version.clone(),
+            backup_hash,
+            status: UpdateStatus::Pending,
+            error: String::new(),
+            changelog: update.changelog.clone(),
+        });
+        // Written after every backup so the backups already taken are pinned
+        // against eviction while the rest of the batch is stored.
+        write_journal(game_path, &journal)?;
+    }
+    prune_update_journals(game_path);
+    Ok(journal)
+}
+
+/// Records the outcome of every download task, including dependencies the
+/// queue added on its own.
+pub fn finish_update_batch(
+    game_path: &Path,
+    journal: &mut UpdateJournal,
+    tasks: &[DownloadInfo],
+    catalog: &HashMap<String, everest::ModInfoCached>,
+) -> anyhow::Result<()> {
+    for task in tasks {
+        let entry = journal
+            .entries
+            .iter_mut()
+            .find(|entry| entry.name == task.name);
+        match (entry, &task.status) {
+            (Some(entry), DownloadStatus::Finished) => entry.status = UpdateStatus::Updated,
+            (Some(entry), _) => {
+                entry.status = UpdateStatus::Failed;
+                entry.error = task.data.clone();
+            }
+            (None, DownloadStatus::Finished) => journal.entries.push(UpdateJournalEntry {
+                name: task.name.clone(),
+                file: Path::new(&task.dest)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                previous_version: None,
+                new_version: catalog
+                    .get(&task.name)
+                    .map(|item| item.version.clone())
+                    .unwrap_or_default(),
+                backup_hash: None,
+                status: UpdateStatus::Installed,
+                error: String::new(),
+                changelog: None,
+            }),
+            (None, _) => {}
+        }
+    }
+    write_journal(game_path, journal)
+}
+
+/// Undoes a batch, or only the entry for `mod_name`: replaced archives are
+/// restored from the backup store and dependencies the batch installed are
+/// removed again.
+pub fn rollback_mod_updates(
+    game_path: &Path,
+    id: &str,
+    mod_name: Option<&str>,
+) -> anyhow::Result<UpdateJournal> {
+    let mut journal = read_update_journal(game_path, id)?;
+    let mods_directory = game_path.join("Mods");
+    let mut matched = false;
+    let mut errors = Vec::new();
+    for entry in journal.entries.iter_mut().filter(|entry| {
+        matches!(
+            entry.status,
+            UpdateStatus::Updated | UpdateStatus::Installed
+        ) && mod_name.is_none_or(|name| entry.name.eq_ignore_ascii_case(name))
+    }) {
+        matched = true;
+        let destination = mods_directory.join(&entry.file);
+        let result = match &entry.backup_hash {
+            Some(hash) => mod_backups::restore_archive(game_path, hash, &destination),
+            None if entry.previous_version.is_some() => Err(anyhow::anyhow!(
+                "No backup was kept for the replaced archive"
+            )),
+            None if destination.is_file() => mod_backups::remove_to_backups(
+                game_path,
+                &destination,
+                mod_backups::BackupReason::Delete,
+            ),
+            None => Ok(()),
+        };
+        match result {
+            Ok(()) => {
+                entry.status = UpdateStatus::RolledBack;
+                entry.error.clear();
+            }
+            Err(error) => {
+                entry.error = format!("{error:#}");
+                errors.push(format!("{}: {error:#}", entry.name));
+            }
+        }
+    }
+    if !matched {
+        match mod_name {
+            Some(name) => bail!("{name} has nothing to roll back in this batch"),
+            None => bail!("This batch has nothing to roll back"),
+        }
+    }
+    write_journal(game_path, &journal)?;
+    if !errors.is_empty() {
+        bail!("Failed to roll back some Mods: {}", errors.join(", "));
+    }
+    Ok(journal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn test_game_path(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "celemod-updates-{name}-{}-{unique}",
+            std::process::id()
+        ));
+        fs::create_dir_all(path.join("Mods")).unwrap();
+        path
+    }
+
+    fn write_mod(path: &Path, name: &str, version: &str) {
+        let mut writer = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        writer
+            .start_file("everest.yaml", SimpleFileOptions::default())
+            .unwrap();
+        write!(writer, "- Name: {name}\n  Version: {version}\n").unwrap();
+        writer.finish().unwrap();
+    }
+
+    fn local(name: &str, version: &str, file: &str) -> LocalMod {
+        LocalMod {
+            game_banana_id: -1,
+            name: name.to_string(),
+            deps: Vec::new(),
+            version: version.to_string(),
+            file: file.to_string(),
+            size: 0,
+            modified_at: 0,
+        }
+    }
+
+    fn catalog_entry(name: &str, version: &str) -> (String, everest::ModInfoCached) {
+        (
+            name.to_string(),
+            everest::ModInfoCached {
+                name: name.to_string(),
+                version: version.to_string(),
+                game_banana_id: 1,
+                game_banana_file_id: 2,
+                download_url: format!("https://example.invalid/{name}.zip"),
+                xx_hash: Vec::new(),
+            },
+        )
+    }
+
+    fn task(name: &str, dest: &Path, status: DownloadStatus) -> DownloadInfo {
+        DownloadInfo {
+            name: name.to_string(),
+            url: String::new(),
+            dest: dest.to_string_lossy().into_owned(),
+            status,
+            data: "Download failed".to_string(),
+            downloaded_bytes: 0,
+            total_bytes: 0,
+            speed_bytes_per_sec: 0.0,
+        }
+    }
+
+    #[test]
+    fn plans_only_newer_zipped_mods() {
+        let game_path = test_game_path("plan");
+        let mods = game_path.join("Mods");
+        write_mod(&mods.join("Old.zip"), "Old", "1.0.0");
+        write_mod(&mods.join("Current.zip"), "Current", "2.0.0");
+        fs::create_dir_all(mods.join("Dev")).unwrap();
+        let installed = [
+            local("Old", "1.0.0", "Old.zip"),
+            local("Current", "2.0.0", "Current.zip"),
+            local("Dev", "1.0.0", "Dev"),
+        ];
+        let catalog = HashMap::from([
+            catalog_entry("Old", "1.10.0"),
+            catalog_entry("Current", "2.0.0"),
+            catalog_entry("Dev", "9.0.0"),
+        ]);
+
+        let updates = plan_mod_updates(&mods, &installed, &catalog);
+
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].name, "Old");
+        assert_eq!(updates[0].latest_version, "1.10.0");
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
+    #[test]
+    fn pins_only_the_latest_backup_of_each_mod_and_prunes_old_batches() {
+        let game_path = test_game_path("pinned");
+        let entry = |name: &str, hash: &str, status: UpdateStatus| UpdateJournalEntry {
+            name: name.to_string(),
+            file: format!("{name}.zip"),
+            previous_version: Some("1.0.0".to_string()),
+            new_version: "1.1.0".to_string(),
+            backup_hash: Some(hash.to_string()),
+            status,
+            error: String::new(),
+            changelog: None,
+        };
+        for (created_at, entries) in [
+            (
+                1,
+                vec![
+                    entry("First", "aaaa", UpdateStatus::Updated),
+                    entry("Second", "bbbb", UpdateStatus::Updated),
+                ],
+            ),
+            (2, vec![entry("first", "cccc", UpdateStatus::Updated)]),
+            (3, vec![entry("Second", "dddd", UpdateStatus::Failed)]),
+        ] {
+            let journal = UpdateJournal {
+                id: created_at.to_string(),
+                created_at,
+                entries,
+            };
+            write_journal(&game_path, &journal).unwrap();
+        }
+
+        assert_eq!(
+            pinned_backup_hashes(&game_path),
+            HashSet::from(["bbbb", "cccc", "dddd"].map(str::to_string))
+        );
+
+        for created_at in 4..KEPT_UPDATE_JOURNALS as u64 + 4 {
+            let journal = UpdateJournal {
+                id: created_at.to_string(),
+                created_at,
+                entries: Vec::new(),
+            };
+            write_journal(&game_path, &journal).unwrap();
+        }
+        prune_update_journals(&game_path);
+        let journals = list_update_journals(&game_path);
+        assert_eq!(journals.len(), KEPT_UPDATE_JOURNALS);
+        assert!(journals.iter().all(|journal| journal.created_at > 3));
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
+    #[test]
+    fn journals_and_rolls_back_a_batch() {
+        let game_path = test_game_path("rollback");
+        let mods = game_path.join("Mods");
+        write_mod(&mods.join("First.zip"), "First", "1.0.0");
+        write_mod(&mods.join("Second.zip"), "Second", "1.0.0");
+        let catalog = HashMap::from([
+            catalog_entry("First", "1.1.0"),
+            catalog_entry("Second", "1.1.0"),
+            catalog_entry("NewDependency", "1.0.0"),
+        ]);
+        let installed = [
+            local("First", "1.0.0", "First.zip"),
+            local("Second", "1.0.0", "Second.zip"),
+        ];
+        let updates = plan_mod_updates(&mods, &installed, &catalog);
+        let original_first = fs::read(mods.join("First.zip")).unwrap();
+
+        let mut journal = begin_update_batch(&game_path, &updates).unwrap();
+        assert!(
+            journal
+                .entries
+                .iter()
+                .all(|entry| entry.status == UpdateStatus::Pending)
+        );
+        // Simulate the download queue replacing First and pulling in a new
+        // dependency while Second fails.
+        write_mod(&mods.join("First.zip"), "First", "1.1.0");
+        write_mod(&mods.join("NewDependency.zip"), "NewDependency", "1.0.0");
+        let tasks = [
+            task("First", &mods.join("First.zip"), DownloadStatus::Finished),
+            task("Second", &mods.join("Second.zip"), DownloadStatus::Failed),
+            task(
+                "NewDependency",
+                &mods.join("NewDependency.zip"),
+                DownloadStatus::Finished,
+            ),
+        ];
+        finish_update_batch(&game_path, &mut journal, &tasks, &catalog).unwrap();
+
+        let statuses = list_update_journals(&game_path)[0]
+            .entries
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                ("First".to_string(), UpdateStatus::Updated),
+                ("Second".to_string(), UpdateStatus::Failed),
+                ("NewDependency".to_string(), UpdateStatus::Installed),
+            ]
+        );
+        assert!(rollback_mod_updates(&game_path, &journal.id, Some("Second")).is_err());
+
+        let rolled_back = rollback_mod_updates(&game_path, &journal.id, None).unwrap();
+
+        assert_eq!(fs::read(mods.join("First.zip")).unwrap(), original_first);
+        assert!(!mods.join("NewDependency.zip").exists());
+        assert!(mods.join("Second.zip").exists());
+        assert_eq!(rolled_back.entries[0].status, UpdateStatus::RolledBack);
+        assert_eq!(rolled_back.entries[1].status, UpdateStatus::Failed);
+        assert!(rollback_mod_updates(&game_path, &journal.id, None).is_err());
+        fs::remove_dir_all(game_path).unwrap();
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::Context;
+use lazy_static::lazy_static;
+use notify_debouncer_mini::{
+    DebounceEventResult, Debouncer, new_debouncer,
+    notify::{RecommendedWatcher, RecursiveMode},
+};
+use serde::Serialize;
+use tauri::ipc::Channel;
+
+use super::{
+    IpcEvent, LocalMod, blacklist, everest, get_installed_mods_sync_with_catalog, send_event,
+};
+
+/// Browsers and Olympus write archives in several steps; one batch per quiet
+/// half second keeps a download from showing up as a burst of updates.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+lazy_static! {
+    static ref WATCHERS: Mutex<HashMap<PathBuf, Debouncer<RecommendedWatcher>>> =
+        Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(super) struct ModChanges<'a> {
+    pub added: Vec<&'a LocalMod>,
+    pub removed: Vec<&'a LocalMod>,
+    pub updated: Vec<&'a LocalMod>,
+}
+
+impl ModChanges<'_> {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// A zip can contain several Mods, so entries are matched by file and name.
+pub(super) fn diff_mods<'a>(previous: &'a [LocalMod], current: &'a [LocalMod]) -> ModChanges<'a> {
+    let key = |item: &LocalMod| (item.file.clone(), item.name.clone());
+    let before = previous
+        .iter()
+        .map(|item| (key(item), item))
+        .collect::<HashMap<_, _>>();
+    let after = current.iter().map(key).collect::<HashSet<_>>();
+
+    let mut changes = ModChanges::default();
+    for item in current {
+        match before.get(&key(item)) {
+            None => changes.added.push(item),
+            Some(old)
+                if old.version != item.version
+                    || old.size != item.size
+                    || old.modified_at != item.modified_at =>
+            {
+                changes.updated.push(item)
+            }
+            Some(_) => {}
+        }
+    }
+    changes.removed = previous
+        .iter()
+        .filter(|item| !after.contains(&key(item)))
+        .collect();
+    changes
+}
+
+fn scan(mods_dir: &Path) -> Vec<LocalMod> {
+    // Never trigger a catalog download from a file event.
+    get_installed_mods_sync_with_catalog(
+        mods_dir.to_string_lossy().into_owned(),
+        everest::get_mod_cached_if_loaded(),
+    )
+}
+
+#[derive(Default)]
+struct Touched {
+    mods: bool,
+    blacklist: bool,
+    presets: bool,
+    profiles: bool,
+}
+
+fn classify(paths: impl IntoIterator<Item = PathBuf>, profiles_dir: &Path) -> Touched {
+    let mut touched = Touched::default();
+    for path in paths {
+        if path.starts_with(profiles_dir) {
+            touched.profiles = true;
+            continue;
+        }
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.eq_ignore_ascii_case("blacklist.txt") => touched.blacklist = true,
+            Some(name) if name.eq_ignore_ascii_case("modpresets.txt") => touched.presets = true,
+            _ => touched.mods = true,
+        }
+    }
+    touched
+}
+
+/// Watches `Mods/` (including `blacklist.txt` and Olympus' `modpresets.txt`)
+/// and the profile directory, replacing any earlier watcher for the game.
+///
+/// Events are `["mods", {added, removed, updated}]` with `LocalMod` entries,
+/// `["blacklist", {currentProfiles, blacklistedFiles}]`, `["profiles",
+/// [profile...]]`, `["presets", null]` and `["error", message]`.
+pub(super) fn watch_game_folder(game_path: &str, channel: Channel<IpcEvent>) -> anyhow::Result<()> {
+    let game = PathBuf::from(game_path);
+    let mods_dir = game.join("Mods");
+    let profiles_dir = blacklist::profiles_directory(game_path);
+    std::fs::create_dir_all(&profiles_dir)?;
+
+    let game_path = game_path.to_string();
+    let watched_mods_dir = mods_dir.clone();
+    let watched_profiles_dir = profiles_dir.clone();
+    let mut snapshot = scan(&mods_dir);
+    let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+        let events = match result {
+            Ok(events) => events,
+            Err(error) => {
+                send_event(
+                    &channel,
+                    vec![
+                        serde_json::json!("error"),
+                        serde_json::json!(error.to_string()),
+                    ],
+                );
+                return;
+            }
+        };
+        let touched = classify(
+            events.into_iter().map(|event| event.path),
+            &watched_profiles_dir,
+        );
+        if touched.mods {
+            let current = scan(&watched_mods_dir);
+            let changes = diff_mods(&snapshot, &current);
+            if !changes.is_empty() {
+                send_event(
+                    &channel,
+                    vec![serde_json::json!("mods"), serde_json::json!(changes)],
+                );
+            }
+            snapshot = current;
+        }
+        if touched.blacklist {
+            let mut blacklisted = blacklist::direct_blacklisted_files(&game_path)
+                .into_iter()
+                .collect::<Vec<_>>();
+            blacklisted.sort();
+            send_event(
+                &channel,
+                vec![
+                    serde_json::json!("blacklist"),
+                    serde_json::json!({
+                        "currentProfiles": blacklist::get_current_profiles(&game_path),
+                        "blacklistedFiles": blacklisted,
+                    }),
+                ],
+            );
+        }
+        if touched.profiles {
+            send_event(
+                &channel,
+                vec![
+                    serde_json::json!("profiles"),
+                    serde_json::json!(blacklist::get_mod_blacklist_profiles(&game_path)),
+                ],
+            );
+        }
+        if touched.presets {
+            send_event(
+                &channel,
+                vec![serde_json::json!("presets"), serde_json::Value::Null],
+            );
+        }
+    })
+    .context("Failed to start the file watcher")?;
+    // Folder Mods are only noticed when they are added, removed or renamed;
+    // watching their contents would cost one watch per directory.
+    debouncer
+        .watcher()
+        .watch(&mods_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", mods_dir.display()))?;
+    debouncer
+        .watcher()
+        .watch(&profiles_dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", profiles_dir.display()))?;
+    WATCHERS.lock().unwrap().insert(game, debouncer);
+    Ok(())
+}
+
+/// Stops the watcher for a game; returns whether one was running.
+pub(super) fn unwatch_game_folder(game_path: &str) -> bool {
+    WATCHERS
+        .lock()
+        .unwrap()
+        .remove(Path::new(game_path))
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_mod(name: &str, file: &str, version: &str) -> LocalMod {
+        LocalMod {
+            game_banana_id: -1,
+            name: name.to_string(),
+            deps: Vec::new(),
+            version: version.to_string(),
+            file: file.to_string(),
+            size: 1,
+            modified_at: 1,
+        }
+    }
+
+    #[test]
+    fn reports_added_removed_and_updated_mods() {
+        let previous = vec![
+            local_mod("Kept", "Kept.zip", "1.0.0"),
+            local_mod("Gone", "Gone.zip", "1.0.0"),
+            local_mod("Bumped", "Pack.zip", "1.0.0"),
+            local_mod("Sibling", "Pack.zip", "1.0.0"),
+        ];
+        let current = vec![
+            local_mod("Kept", "Kept.zip", "1.0.0"),
+            local_mod("Bumped", "Pack.zip", "1.1.0"),
+            local_mod("Sibling", "Pack.zip", "1.0.0"),
+            local_mod("New", "New.zip", "0.1.0"),
+            local_mod("Kept", "Copy of Kept.zip", "1.0.0"),
+        ];
+
+        let changes = diff_mods(&previous, &current);
+        let names = |items: &[&LocalMod]| {
+            items
+                .iter()
+                .map(|item| format!("{}@{}", item.name, item.file))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&changes.added),
+            ["New@New.zip", "Kept@Copy of Kept.zip"]
+        );
+        assert_eq!(names(&changes.removed), ["Gone@Gone.zip"]);
+        assert_eq!(names(&changes.updated), ["Bumped@Pack.zip"]);
+        assert!(diff_mods(&current, &current).is_empty());
+
+        let touched = classify(
+            [
+                PathBuf::from("/game/Mods/blacklist.txt"),
+                PathBuf::from("/game/celemod_blacklist_profiles/Speedrun.json"),
+            ],
+            Path::new("/game/celemod_blacklist_profiles"),
+        );
+        assert!(touched.blacklist && touched.profiles && !touched.mods && !touched.presets);
+    }
+}
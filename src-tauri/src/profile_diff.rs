@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::{LocalMod, blacklist, get_installed_mods_sync, resolver};
+
+/// One side of a comparison.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DiffSide {
+    Profile {
+        name: String,
+    },
+    /// Whatever `Mods/blacklist.txt` leaves enabled right now.
+    Blacklist,
+    /// An edited or imported profile that has not been saved.
+    Unsaved {
+        profile: blacklist::ModBlacklistProfile,
+    },
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModChange {
+    pub name: String,
+    /// Only enabled because another enabled Mod requires it.
+    pub via_dependency: bool,
+    /// Enabled Mods on the same side that require it.
+    pub required_by: Vec<String>,
+    /// The highest version those Mods require.
+    pub required_version: Option<String>,
+    /// `None` when the Mod is not installed.
+    pub installed_version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileDiff {
+    pub left: String,
+    pub right: String,
+    /// Enabled on the right only.
+    pub added: Vec<ModChange>,
+    /// Enabled on the left only.
+    pub removed: Vec<ModChange>,
+    /// Enabled on both sides, but only as a dependency on one of them.
+    pub dependency_only: Vec<String>,
+    pub unchanged: usize,
+}
+
+/// Lowercased name to the stored name and whether it is only a dependency.
+fn effective_mods(installed: &[LocalMod], explicit: &[String]) -> BTreeMap<String, (String, bool)> {
+    let explicit_keys = explicit
+        .iter()
+        .map(|name| name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    blacklist::expand_installed_dependencies(installed, explicit)
+        .into_iter()
+        .map(|name| {
+            let key = name.to_ascii_lowercase();
+            let via_dependency = !explicit_keys.contains(&key);
+            (key, (name, via_dependency))
+        })
+        .collect()
+}
+
+fn change(
+    installed: &[LocalMod],
+    enabled: &BTreeMap<String, (String, bool)>,
+    key: &str,
+) -> ModChange {
+    let (name, via_dependency) = enabled[key].clone();
+    let mut required_by = Vec::new();
+    let mut required_version = None::<&str>;
+    for dependent in installed
+        .iter()
+        .filter(|dependent| enabled.contains_key(&dependent.name.to_ascii_lowercase()))
+    {
+        for dependency in dependent
+            .deps
+            .iter()
+            .filter(|dependency| dependency.name.eq_ignore_ascii_case(key))
+        {
+            required_by.push(dependent.name.clone());
+            if required_version.is_none_or(|current| {
+                resolver::ModVersion::parse(&dependency.version)
+                    > resolver::ModVersion::parse(current)
+            }) {
+                required_version = Some(&dependency.version);
+            }
+        }
+    }
+    required_by.sort_unstable_by_key(|name| name.to_ascii_lowercase());
+    required_by.dedup();
+    ModChange {
+        name,
+        via_dependency,
+        required_by,
+        required_version: required_version.map(str::to_owned),
+        installed_version: installed
+            .iter()
+            .filter(|mod_info| mod_info.name.eq_ignore_ascii_case(key))
+            .map(|mod_info| mod_info.version.as_str())
+            .max_by_key(|version| resolver::ModVersion::parse(version))
+            .map(str::to_owned),
+    }
+}
+
+fn diff(
+    installed: &[LocalMod],
+    (left, left_explicit): (String, &[String]),
+    (right, right_explicit): (String, &[String]),
+) -> ProfileDiff {
+    let before = effective_mods(installed, left_explicit);
+    let after = effective_mods(installed, right_explicit);
+    let mut result = ProfileDiff {
+        left,
+        right,
+        added: Vec::new(),
+        removed: Vec::new(),
+        dependency_only: Vec::new(),
+        unchanged: 0,
+    };
+    for (key, (name, via_dependency)) in &after {
+        match before.get(key) {
+            None => result.added.push(change(installed, &after, key)),
+            Some((_, was_dependency)) if was_dependency != via_dependency => {
+                result.dependency_only.push(name.clone());
+            }
+            Some(_) => result.unchanged += 1,
+        }
+    }
+    result.removed = before
+        .keys()
+        .filter(|key| !after.contains_key(*key))
+        .map(|key| change(installed, &before, key))
+        .collect();
+    result
+}
+
+fn side_mods(game_path: &str, side: DiffSide) -> anyhow::Result<(String, Vec<String>)> {
+    Ok(match side {
+        DiffSide::Profile { name } => {
+            let resolved = blacklist::resolve_mod_profile(game_path, &name)?;
+            (resolved.name, resolved.enabled_mods)
+        }
+        DiffSide::Blacklist => {
+            let profile = blacklist::get_direct_blacklist_profile(game_path)?;
+            (profile.name, profile.enabled_mods)
+        }
+        DiffSide::Unsaved { profile } => {
+            let resolved = blacklist::preview_mod_profile(game_path, profile)?;
+            (resolved.name, resolved.enabled_mods)
+        }
+    })
+}
+
+/// Compares what two sides enable once inheritance and required
+/// dependencies are applied.
+pub fn diff_profiles(
+    game_path: &str,
+    left: DiffSide,
+    right: DiffSide,
+) -> anyhow::Result<ProfileDiff> {
+    let (left, left_mods) = side_mods(game_path, left)?;
+    let (right, right_mods) = side_mods(game_path, right)?;
+    let installed = get_installed_mods_sync(format!("{game_path}/Mods"));
+    Ok(diff(&installed, (left, &left_mods), (right, &right_mods)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ModDependency;
+    use super::*;
+
+    fn local_mod(name: &str, version: &str, deps: &[(&str, &str)]) -> LocalMod {
+        LocalMod {
+            game_banana_id: -1,
+            name: name.to_string(),
+            deps: deps
+                .iter()
+                .map(|(name, version)| ModDependency {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    optional: false,
+                })
+                .collect(),
+            version: version.to_string(),
+            file: format!("{name}.zip"),
+            size: 0,
+            modified_at: 0,
+        }
+    }
+
+    #[test]
+    fn reports_added_removed_and_dependency_differences() {
+        let installed = vec![
+            local_mod("Map", "1.0.0", &[("Helper", "1.2.0")]),
+            local_mod("OtherMap", "1.0.0", &[("Helper", "1.5.0")]),
+            local_mod("Helper", "1.4.0", &[]),
+            local_mod("Old", "1.0.0", &[]),
+        ];
+        let left = ["Helper".to_string(), "Old".to_string()];
+        let right = [
+            "Map".to_string(),
+            "OtherMap".to_string(),
+            "Missing".to_string(),
+        ];
+        let result = diff(
+            &installed,
+            ("Left".to_string(), &left),
+            ("Right".to_string(), &right),
+        );
+
+        let added = result
+            .added
+            .iter()
+            .map(|change| change.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(added, ["Map", "Missing", "OtherMap"]);
+        assert!(result.added[1].installed_version.is_none());
+        assert_eq!(result.removed.len(), 1);
+        assert_eq!(result.removed[0].name, "Old");
+        assert_eq!(result.dependency_only, ["Helper"]);
+
+        let helper = change(&installed, &effective_mods(&installed, &right), "helper");
+        assert!(helper.via_dependency);
+        assert_eq!(helper.required_by, ["Map", "OtherMap"]);
+        assert_eq!(helper.required_version.as_deref(), Some("1.5.0"));
+        assert_eq!(helper.installed_version.as_deref(), Some("1.4.0"));
+    }
+}
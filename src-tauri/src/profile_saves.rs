@@ -0,0 +1,413 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{blacklist, crash_analysis::now_millis, is_celeste_running, shared_save_directories};
+
+/// Each isolated profile gets `<name>/Saves`; `<name>` is what
+/// `EVEREST_SAVEPATH` points at.
+const SAVES_ROOT: &str = "celemod_profile_saves";
+const ISOLATION_FILE: &str = "isolation.json";
+const BACKUP_DIRECTORY: &str = ".backups";
+const BACKUP_MANIFEST_FILE: &str = "backup.json";
+/// Backups kept for each profile and for the shared saves.
+const KEPT_SAVE_BACKUPS: usize = 10;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Isolation {
+    enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSaveInfo {
+    pub isolated: bool,
+    pub saves_directory: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveBackup {
+    pub id: String,
+    /// `None` for the shared saves.
+    pub profile: Option<String>,
+    pub created_at: u64,
+    pub size: u64,
+}
+
+fn root(game_path: &Path) -> PathBuf {
+    game_path.join(SAVES_ROOT)
+}
+
+fn profile_root(game_path: &Path, profile_name: &str) -> anyhow::Result<PathBuf> {
+    blacklist::validate_profile_name(profile_name)?;
+    Ok(root(game_path).join(profile_name))
+}
+
+fn is_isolated(game_path: &Path, profile_name: &str) -> bool {
+    profile_root(game_path, profile_name)
+        .ok()
+        .and_then(|root| fs::read(root.join(ISOLATION_FILE)).ok())
+        .and_then(|bytes| serde_json::from_slice::<Isolation>(&bytes).ok())
+        .is_some_and(|isolation| isolation.enabled)
+}
+
+/// What `EVEREST_SAVEPATH` should be for `profile_names`: the first of them
+/// with isolated saves.
+fn save_root_for(game_path: &Path, profile_names: &[String]) -> Option<PathBuf> {
+    profile_names
+        .iter()
+        .find(|name| is_isolated(game_path, name))
+        .and_then(|name| profile_root(game_path, name).ok())
+}
+
+pub(super) fn active_save_root(game_path: &Path) -> Option<PathBuf> {
+    save_root_for(
+        game_path,
+        &blacklist::get_current_profiles(&game_path.to_string_lossy()),
+    )
+}
+
+/// The save directory the game will use once `profile_names` are applied.
+pub(super) fn save_directory_for(game_path: &Path, profile_names: &[String]) -> PathBuf {
+    match save_root_for(game_path, profile_names) {
+        Some(root) => root.join("Saves"),
+        None => shared_save_directory(game_path),
+    }
+}
+
+/// The platform save directory the game uses without a profile override.
+fn shared_save_directory(game_path: &Path) -> PathBuf {
+    let directories = shared_save_directories(game_path);
+    directories
+        .iter()
+        .find(|directory| directory.join("settings.celeste").is_file())
+        .cloned()
+        .or_else(|| directories.into_iter().find(|directory| directory.is_dir()))
+        .unwrap_or_else(|| game_path.join("Saves"))
+}
+
+fn saves_directory(game_path: &Path, profile_name: Option<&str>) -> anyhow::Result<PathBuf> {
+    match profile_name {
+        Some(name) => Ok(profile_root(game_path, name)?.join("Saves")),
+        None => Ok(shared_save_directory(game_path)),
+    }
+}
+
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => directory_size(&entry.path()),
+            _ => entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+        })
+        .sum()
+}
+
+fn copy_directory(source: &Path, destination: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_directory(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn ensure_game_closed(game_path: &Path) -> anyhow::Result<()> {
+    if is_celeste_running(game_path) {
+        bail!("Close Celeste before changing save files");
+    }
+    Ok(())
+}
+
+pub fn get_profile_save_info(
+    game_path: &Path,
+    profile_name: &str,
+) -> anyhow::Result<ProfileSaveInfo> {
+    let directory = saves_directory(game_path, Some(profile_name))?;
+    Ok(ProfileSaveInfo {
+        isolated: is_isolated(game_path, profile_name),
+        size: directory_size(&directory),
+        saves_directory: directory.to_string_lossy().into_owned(),
+    })
+}
+
+/// Turning isolation off keeps the profile's saves for later.
+pub fn set_profile_save_isolation(
+    game_path: &Path,
+    profile_name: &str,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    let profile_name = match blacklist::get_mod_blacklist_profiles(&game_path.to_string_lossy())
+        .into_iter()
+        .find(|profile| profile.name.eq_ignore_ascii_case(profile_name))
+    {
+        Some(profile) => profile.name,
+        None if enabled => bail!("Profile not found"),
+        None => profile_name.to_owned(),
+    };
+    let root = profile_root(game_path, &profile_name)?;
+    fs::create_dir_all(root.join("Saves"))?;
+    let path = root.join(ISOLATION_FILE);
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(
+        &temp_path,
+        serde_json::to_vec_pretty(&Isolation { enabled })?,
+    )?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+/// `None` backs up the shared saves. Only the newest `KEPT_SAVE_BACKUPS`
+/// of each profile are kept.
+pub fn backup_profile_saves(
+    game_path: &Path,
+    profile_name: Option<&str>,
+) -> anyhow::Result<SaveBackup> {
+    let backup = store_save_backup(game_path, profile_name)?;
+    prune_save_backups(game_path, profile_name);
+    Ok(backup)
+}
+
+fn store_save_backup(game_path: &Path, profile_name: Option<&str>) -> anyhow::Result<SaveBackup> {
+    let source = saves_directory(game_path, profile_name)?;
+    let backups = root(game_path).join(BACKUP_DIRECTORY);
+    let created_at = now_millis();
+    let mut id = created_at.to_string();
+    let mut suffix = 1;
+    while backups.join(&id).exists() {
+        suffix += 1;
+        id = format!("{created_at}-{suffix}");
+    }
+    let staging = backups.join(format!(".{id}.tmp"));
+    let _ = fs::remove_dir_all(&staging);
+    if source.is_dir() {
+        copy_directory(&source, &staging.join("Saves"))?;
+    } else {
+        fs::create_dir_all(staging.join("Saves"))?;
+    }
+    let backup = SaveBackup {
+        id: id.clone(),
+        profile: profile_name.map(str::to_owned),
+        created_at,
+        size: directory_size(&staging),
+    };
+    fs::write(
+        staging.join(BACKUP_MANIFEST_FILE),
+        serde_json::to_vec_pretty(&backup)?,
+    )?;
+    fs::rename(&staging, backups.join(id))?;
+    Ok(backup)
+}
+
+/// Newest first.
+pub fn list_save_backups(game_path: &Path) -> Vec<SaveBackup> {
+    let Ok(entries) = fs::read_dir(root(game_path).join(BACKUP_DIRECTORY)) else {
+        return Vec::new();
+    };
+    let mut backups = entries
+        .flatten()
+        .filter_map(|entry| fs::read(entry.path().join(BACKUP_MANIFEST_FILE)).ok())
+        .filter_map(|bytes| serde_json::from_slice::<SaveBackup>(&bytes).ok())
+        .collect::<Vec<_>>();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    backups
+}
+
+pub fn delete_save_backup(game_path: &Path, backup_id: &str) -> anyhow::Result<()> {
+    blacklist::validate_profile_name(backup_id).context("Invalid backup")?;
+    fs::remove_dir_all(root(game_path).join(BACKUP_DIRECTORY).join(backup_id))?;
+    Ok(())
+}
+
+fn prune_save_backups(game_path: &Path, profile_name: Option<&str>) {
+    for backup in list_save_backups(game_path)
+        .into_iter()
+        .filter(|backup| match (backup.profile.as_deref(), profile_name) {
+            (Some(stored), Some(name)) => stored.eq_ignore_ascii_case(name),
+            (stored, name) => stored == name,
+        })
+        .skip(KEPT_SAVE_BACKUPS)
+    {
+        if let Err(error) = delete_save_backup(game_path, &backup.id) {
+            crate::logging::warn(format_args!(
+                "Failed to remove save backup {}: {error:#}",
+                backup.id
+            ));
+        }
+    }
+}
+
+/// Replaces the saves of `profile_name` with a copy of `source`, after
+/// backing up what was there. The old folder is only removed once the copy
+/// is complete.
+fn replace_saves(
+    game_path: &Path,
+    profile_name: Option<&str>,
+    source: &Path,
+) -> anyhow::Result<SaveBackup> {
+    let destination = saves_directory(game_path, profile_name)?;
+    if source == destination {
+        bail!("Source and destination are the same save folder");
+    }
+    // Pruned only after the copy: `source` may be the oldest backup.
+    let backup = store_save_backup(game_path, profile_name)?;
+    let parent = destination
+        .parent()
+        .context("Save folder has no parent directory")?;
+    let staging = parent.join(".Saves.celemod-tmp");
+    let _ = fs::remove_dir_all(&staging);
+    copy_directory(source, &staging)?;
+    let previous = parent.join(".Saves.celemod-old");
+    let _ = fs::remove_dir_all(&previous);
+    if destination.exists() {
+        fs::rename(&destination, &previous)?;
+    }
+    if let Err(error) = fs::rename(&staging, &destination) {
+        let _ = fs::rename(&previous, &destination);
+        return Err(error.into());
+    }
+    let _ = fs::remove_dir_all(previous);
+    prune_save_backups(game_path, profile_name);
+    Ok(backup)
+}
+
+/// Copies saves between profiles; `None` on either side is the shared saves.
+pub fn copy_profile_saves(
+    game_path: &Path,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> anyhow::Result<SaveBackup> {
+    ensure_game_closed(game_path)?;
+    let source = saves_directory(game_path, from)?;
+    if !source.is_dir() {
+        bail!("There are no saves to copy");
+    }
+    replace_saves(game_path, to, &source)
+}
+
+/// Puts a backup back where it was taken from, backing up the current saves.
+pub fn restore_save_backup(game_path: &Path, backup_id: &str) -> anyhow::Result<SaveBackup> {
+    ensure_game_closed(game_path)?;
+    let backup = list_save_backups(game_path)
+        .into_iter()
+        .find(|backup| backup.id == backup_id)
+        .context("Backup not found")?;
+    let source = root(game_path)
+        .join(BACKUP_DIRECTORY)
+        .join(&backup.id)
+        .join("Saves");
+    replace_saves(game_path, backup.profile.as_deref(), &source)
+}
+
+/// Keeps the save folder in step with `rename_mod_blacklist_profile`.
+pub(super) fn rename_profile(
+    game_path: &Path,
+    old_name: &str,
+    new_name: &str,
+) -> anyhow::Result<()> {
+    let source = profile_root(game_path, old_name)?;
+    if source.is_dir() {
+        fs::rename(source, profile_root(game_path, new_name)?)?;
+    }
+    Ok(())
+}
+
+/// A removed profile's saves become a backup instead of being deleted.
+pub(super) fn remove_profile(game_path: &Path, profile_name: &str) -> anyhow::Result<()> {
+    let source = profile_root(game_path, profile_name)?;
+    if !source.join("Saves").is_dir() {
+        return Ok(());
+    }
+    backup_profile_saves(game_path, Some(profile_name))?;
+    fs::remove_dir_all(source)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_backs_up_and_restores_profile_saves() {
+        let game_path = std::env::temp_dir().join(format!(
+            "celemod-profile-saves-{}-{}",
+            std::process::id(),
+            now_millis()
+        ));
+        let randomizer = saves_directory(&game_path, Some("Randomizer")).unwrap();
+        let practice = saves_directory(&game_path, Some("Practice")).unwrap();
+        fs::create_dir_all(&randomizer).unwrap();
+        fs::write(randomizer.join("0.celeste"), "randomizer").unwrap();
+        fs::create_dir_all(&practice).unwrap();
+        fs::write(practice.join("0.celeste"), "practice").unwrap();
+
+        let backup = replace_saves(&game_path, Some("Practice"), &randomizer).unwrap();
+        assert_eq!(backup.profile.as_deref(), Some("Practice"));
+        assert_eq!(
+            fs::read_to_string(practice.join("0.celeste")).unwrap(),
+            "randomizer"
+        );
+
+        let source = root(&game_path)
+            .join(BACKUP_DIRECTORY)
+            .join(&backup.id)
+            .join("Saves");
+        replace_saves(&game_path, Some("Practice"), &source).unwrap();
+        assert_eq!(
+            fs::read_to_string(practice.join("0.celeste")).unwrap(),
+            "practice"
+        );
+        assert_eq!(list_save_backups(&game_path).len(), 2);
+
+        remove_profile(&game_path, "Randomizer").unwrap();
+        assert!(!randomizer.exists());
+        assert!(
+            list_save_backups(&game_path)
+                .iter()
+                .any(|backup| backup.profile.as_deref() == Some("Randomizer"))
+        );
+        fs::remove_dir_all(game_path).unwrap();
+    }
+
+    #[test]
+    fn keeps_only_the_newest_backups_of_each_profile() {
+        let game_path = std::env::temp_dir().join(format!(
+            "celemod-profile-save-backups-{}-{}",
+            std::process::id(),
+            now_millis()
+        ));
+        let practice = saves_directory(&game_path, Some("Practice")).unwrap();
+        fs::create_dir_all(&practice).unwrap();
+        fs::write(practice.join("0.celeste"), "practice").unwrap();
+
+        backup_profile_saves(&game_path, None).unwrap();
+        for _ in 0..KEPT_SAVE_BACKUPS + 2 {
+            backup_profile_saves(&game_path, Some("Practice")).unwrap();
+        }
+        let backups = list_save_backups(&game_path);
+        let count = |profile: Option<&str>| {
+            backups
+                .iter()
+                .filter(|backup| backup.profile.as_deref() == profile)
+                .count()
+        };
+        assert_eq!(count(Some("Practice")), KEPT_SAVE_BACKUPS);
+        assert_eq!(count(None), 1);
+        fs::remove_dir_all(game_path).unwrap();
+    }
+}
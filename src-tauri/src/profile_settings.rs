@@ -0,0 +1,523 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blacklist, crash_analysis::now_millis, is_celeste_running, keybindings, profile_saves,
+};
+
+/// Lives beside the Mods folder, one directory per profile that owns settings.
+const SETTINGS_DIRECTORY: &str = "celemod_profile_settings";
+const MANIFEST_FILE: &str = "settings.json";
+const ACTIVE_FILE: &str = "active.json";
+const BACKUP_DIRECTORY: &str = ".backup";
+const BACKUP_MANIFEST_FILE: &str = "backup.json";
+const SETTINGS_VERSION: u32 = 1;
+const EVEREST_LAUNCH: &str = "everest-launch.txt";
+/// Prefix of snapshot entries that belong in the save directory.
+const SAVES_PREFIX: &str = "Saves/";
+
+/// The Everest settings a profile carries with it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSettings {
+    version: u32,
+    pub captured_at: u64,
+    /// Launch through the non-accelerated loader while this profile is active.
+    pub legacy_loader: bool,
+    /// Whether `everest-launch.txt` existed; applying removes it otherwise.
+    pub everest_launch: bool,
+    /// `modsettings-*.celeste` file names.
+    pub mod_settings: Vec<String>,
+}
+
+/// Which profile's settings are currently in place.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActiveSettings {
+    profile: Option<String>,
+    legacy_loader: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupEntry {
+    /// `everest-launch.txt` or `Saves/<file>`.
+    target: String,
+    existed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBackup {
+    pub taken_at: u64,
+    /// The profile whose settings were replaced, if any.
+    pub profile: Option<String>,
+    legacy_loader: bool,
+    /// Where the `Saves/` entries were taken from.
+    save_directory: PathBuf,
+    entries: Vec<BackupEntry>,
+}
+
+fn settings_root(game_path: &Path) -> PathBuf {
+    game_path.join(SETTINGS_DIRECTORY)
+}
+
+fn profile_directory(game_path: &Path, profile_name: &str) -> PathBuf {
+    settings_root(game_path).join(profile_name)
+}
+
+fn is_mod_settings_file(name: &str) -> bool {
+    name.starts_with("modsettings-")
+        && name.ends_with(".celeste")
+        && !name.contains(['/', '\\'])
+        && name != "modsettings-.celeste"
+}
+
+fn target_path(game_path: &Path, save_directory: &Path, target: &str) -> PathBuf {
+    match target.strip_prefix(SAVES_PREFIX) {
+        Some(file) => save_directory.join(file),
+        None => game_path.join(target),
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> anyhow::Result<()> {
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+/// Copies `source` next to `target` first, so the final step is a rename.
+fn replace_file(source: &Path, target: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut temp_name = target.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".celemod-tmp");
+    let temp_path = target.with_file_name(temp_name);
+    fs::copy(source, &temp_path)
+        .with_context(|| format!("Failed to stage {}", target.display()))?;
+    fs::rename(&temp_path, target).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })?;
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+fn stored_profile_name(game_path: &Path, profile_name: &str) -> anyhow::Result<String> {
+    blacklist::get_mod_blacklist_profiles(&game_path.to_string_lossy())
+        .into_iter()
+        .find(|profile| profile.name.eq_ignore_ascii_case(profile_name))
+        .map(|profile| profile.name)
+        .context("Profile not found")
+}
+
+pub fn get_profile_settings(game_path: &Path, profile_name: &str) -> Option<ProfileSettings> {
+    blacklist::validate_profile_name(profile_name).ok()?;
+    read_json::<ProfileSettings>(&profile_directory(game_path, profile_name).join(MANIFEST_FILE))
+        .filter(|settings| settings.version == SETTINGS_VERSION)
+}
+
+fn capture(
+    game_path: &Path,
+    save_directory: &Path,
+    profile_name: &str,
+    legacy_loader: bool,
+    mod_settings: Option<Vec<String>>,
+) -> anyhow::Result<ProfileSettings> {
+    let mut mod_settings = match mod_settings {
+        Some(files) => files,
+        None => fs::read_dir(save_directory)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| is_mod_settings_file(name))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    mod_settings.sort_unstable_by_key(|name| name.to_ascii_lowercase());
+    mod_settings.dedup_by(|left, right| left.eq_ignore_ascii_case(right));
+    if let Some(name) = mod_settings.iter().find(|name| !is_mod_settings_file(name)) {
+        bail!("{name} is not a Mod settings file");
+    }
+
+    let root = settings_root(game_path);
+    fs::create_dir_all(&root)?;
+    let staging = root.join(format!(".{profile_name}.tmp"));
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(staging.join("Saves"))?;
+    let launch = game_path.join(EVEREST_LAUNCH);
+    let everest_launch = launch.is_file();
+    if everest_launch {
+        fs::copy(&launch, staging.join(EVEREST_LAUNCH))?;
+    }
+    for name in &mod_settings {
+        fs::copy(save_directory.join(name), staging.join("Saves").join(name))
+            .with_context(|| format!("Failed to read {name}"))?;
+    }
+    let settings = ProfileSettings {
+        version: SETTINGS_VERSION,
+        captured_at: now_millis(),
+        legacy_loader,
+        everest_launch,
+        mod_settings,
+    };
+    write_json(&staging.join(MANIFEST_FILE), &settings)?;
+
+    let directory = profile_directory(game_path, profile_name);
+    let _ = fs::remove_dir_all(&directory);
+    fs::rename(&staging, &directory)?;
+    // The live files are this snapshot now; re-applying must not swap them.
+    write_json(
+        &root.join(ACTIVE_FILE),
+        &ActiveSettings {
+            profile: Some(profile_name.to_string()),
+            legacy_loader,
+        },
+    )?;
+    Ok(settings)
+}
+
+/// Snapshots the live `everest-launch.txt` and Mod settings into a profile.
+/// `mod_settings` limits the snapshot to those files; `None` takes all.
+pub fn capture_profile_settings(
+    game_path: &Path,
+    profile_name: &str,
+    legacy_loader: bool,
+    mod_settings: Option<Vec<String>>,
+) -> anyhow::Result<ProfileSettings> {
+    let profile_name = stored_profile_name(game_path, profile_name)?;
+    capture(
+        game_path,
+        &keybindings::save_directory(game_path),
+        &profile_name,
+        legacy_loader,
+        mod_settings,
+    )
+}
+
+pub fn clear_profile_settings(game_path: &Path, profile_name: &str) -> anyhow::Result<()> {
+    blacklist::validate_profile_name(profile_name)?;
+    let directory = profile_directory(game_path, profile_name);
+    if directory.is_dir() {
+        fs::remove_dir_all(directory)?;
+    }
+    let active_path = settings_root(game_path).join(ACTIVE_FILE);
+    if let Some(active) = read_json::<ActiveSettings>(&active_path)
+        && active
+            .profile
+            .is_some_and(|active| active.eq_ignore_ascii_case(profile_name))
+    {
+        remove_if_exists(&active_path)?;
+    }
+    Ok(())
+}
+
+/// Keeps the settings directory in step with `rename_mod_blacklist_profile`.
+pub(super) fn rename_profile(
+    game_path: &Path,
+    old_name: &str,
+    new_name: &str,
+) -> anyhow::Result<()> {
+    let source = profile_directory(game_path, old_name);
+    if source.is_dir() {
+        fs::rename(source, profile_directory(game_path, new_name))?;
+    }
+    let active_path = settings_root(game_path).join(ACTIVE_FILE);
+    if let Some(mut active) = read_json::<ActiveSettings>(&active_path)
+        && active
+            .profile
+            .as_ref()
+            .is_some_and(|active| active.eq_ignore_ascii_case(old_name))
+    {
+        active.profile = Some(new_name.to_string());
+        write_json(&active_path, &active)?;
+    }
+    Ok(())
+}
+
+/// Whether the profile settings in place ask for the legacy loader.
+pub fn active_legacy_loader(game_path: &Path) -> bool {
+    read_json::<ActiveSettings>(&settings_root(game_path).join(ACTIVE_FILE))
+        .is_some_and(|active| active.legacy_loader)
+}
+
+/// Puts the snapshot of `profile_name` in place, or with `None` goes back to
+/// the defaults: no launch flags and the regular loader. Mod settings are
+/// left alone then, since nothing says which ones the defaults would be.
+fn apply(
+    game_path: &Path,
+    save_directory: &Path,
+    profile_name: Option<&str>,
+    settings: &ProfileSettings,
+) -> anyhow::Result<()> {
+    let root = settings_root(game_path);
+    let source = profile_name.map(|name| profile_directory(game_path, name));
+    let mut targets = vec![(
+        EVEREST_LAUNCH.to_string(),
+        source
+            .as_ref()
+            .filter(|_| settings.everest_launch)
+            .map(|source| source.join(EVEREST_LAUNCH)),
+    )];
+    if let Some(source) = &source {
+        targets.extend(settings.mod_settings.iter().map(|name| {
+            (
+                format!("{SAVES_PREFIX}{name}"),
+                Some(source.join("Saves").join(name)),
+            )
+        }));
+    }
+    let description = match profile_name {
+        Some(name) => format!("the settings of {name}"),
+        None => "the default settings".to_string(),
+    };
+    for (target, source) in &targets {
+        if let Some(source) = source
+            && !source.is_file()
+        {
+            bail!(
+                "The snapshot of {} is missing {target}",
+                profile_name.unwrap_or_default()
+            );
+        }
+    }
+
+    // Back up everything about to change before touching any of it.
+    let active_path = root.join(ACTIVE_FILE);
+    let active = read_json::<ActiveSettings>(&active_path).unwrap_or_default();
+    let staging = root.join(".backup.tmp");
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(staging.join("Saves"))?;
+    let mut entries = Vec::new();
+    for (target, _) in &targets {
+        let live = target_path(game_path, save_directory, target);
+        let existed = live.is_file();
+        if existed {
+            fs::copy(&live, staging.join(target))?;
+        }
+        entries.push(BackupEntry {
+            target: target.clone(),
+            existed,
+        });
+    }
+    write_json(
+        &staging.join(BACKUP_MANIFEST_FILE),
+        &SettingsBackup {
+            taken_at: now_millis(),
+            profile: active.profile,
+            legacy_loader: active.legacy_loader,
+            save_directory: save_directory.to_path_buf(),
+            entries,
+        },
+    )?;
+    let backup = root.join(BACKUP_DIRECTORY);
+    let _ = fs::remove_dir_all(&backup);
+    fs::rename(&staging, &backup)?;
+
+    let swapped = targets.iter().try_for_each(|(target, source)| {
+        let live = target_path(game_path, save_directory, target);
+        match source {
+            Some(source) => replace_file(source, &live),
+            None => Ok(remove_if_exists(&live)?),
+        }
+    });
+    if let Err(error) = swapped {
+        if let Err(restore_error) = restore(game_path) {
+            crate::logging::error(format_args!(
+                "Failed to roll back profile settings: {restore_error:#}"
+            ));
+        }
+        return Err(error.context(format!("Failed to apply {description}")));
+    }
+    write_json(
+        &active_path,
+        &ActiveSettings {
+            profile: profile_name.map(str::to_owned),
+            legacy_loader: settings.legacy_loader,
+        },
+    )
+}
+
+/// Swaps in the settings of the first of `profile_names` that owns a
+/// snapshot, or the defaults when none does, unless they are already in
+/// place. The outgoing profile's snapshot is refreshed from the live files
+/// first, so edits made while it was active are kept. Returns whether the
+/// live settings changed.
+pub fn switch_profile_settings(game_path: &Path, profile_names: &[String]) -> anyhow::Result<bool> {
+    let incoming = profile_names.iter().find_map(|name| {
+        let name = stored_profile_name(game_path, name).ok()?;
+        let settings = get_profile_settings(game_path, &name)?;
+        Some((name, settings))
+    });
+    let active = read_json::<ActiveSettings>(&settings_root(game_path).join(ACTIVE_FILE))
+        .unwrap_or_default();
+    if active.profile.as_deref() == incoming.as_ref().map(|(name, _)| name.as_str()) {
+        return Ok(false);
+    }
+    // Everest writes its settings back on exit and would undo the swap.
+    if is_celeste_running(game_path) {
+        bail!("Close Celeste before switching to a profile with different settings");
+    }
+    if let Some(outgoing) = &active.profile
+        && let Some(previous) = get_profile_settings(game_path, outgoing)
+    {
+        let live = keybindings::save_directory(game_path);
+        let mod_settings = previous
+            .mod_settings
+            .into_iter()
+            .filter(|name| live.join(name).is_file())
+            .collect();
+        capture(
+            game_path,
+            &live,
+            outgoing,
+            active.legacy_loader,
+            Some(mod_settings),
+        )
+        .with_context(|| format!("Failed to keep the current settings of {outgoing}"))?;
+    }
+    let save_directory = profile_saves::save_directory_for(game_path, profile_names);
+    match incoming {
+        Some((profile_name, settings)) => {
+            apply(game_path, &save_directory, Some(&profile_name), &settings)?;
+            crate::logging::info(format_args!(
+                "Applied the Everest settings of {profile_name}"
+            ));
+        }
+        None => {
+            let defaults = ProfileSettings {
+                version: SETTINGS_VERSION,
+                captured_at: now_millis(),
+                legacy_loader: false,
+                everest_launch: false,
+                mod_settings: Vec::new(),
+            };
+            apply(game_path, &save_directory, None, &defaults)?;
+            crate::logging::info(format_args!("Reset the Everest settings to the defaults"));
+        }
+    }
+    Ok(true)
+}
+
+pub fn get_settings_backup(game_path: &Path) -> Option<SettingsBackup> {
+    read_json(
+        &settings_root(game_path)
+            .join(BACKUP_DIRECTORY)
+            .join(BACKUP_MANIFEST_FILE),
+    )
+}
+
+fn restore(game_path: &Path) -> anyhow::Result<()> {
+    let root = settings_root(game_path);
+    let backup_directory = root.join(BACKUP_DIRECTORY);
+    let backup = get_settings_backup(game_path).context("No settings backup found")?;
+    for entry in &backup.entries {
+        let live = target_path(game_path, &backup.save_directory, &entry.target);
+        if entry.existed {
+            replace_file(&backup_directory.join(&entry.target), &live)?;
+        } else {
+            remove_if_exists(&live)?;
+        }
+    }
+    write_json(
+        &root.join(ACTIVE_FILE),
+        &ActiveSettings {
+            profile: backup.profile,
+            legacy_loader: backup.legacy_loader,
+        },
+    )
+}
+
+/// Puts back the files the last profile switch replaced.
+pub fn restore_settings_backup(game_path: &Path) -> anyhow::Result<()> {
+    if is_celeste_running(game_path) {
+        bail!("Close Celeste before restoring settings");
+    }
+    restore(game_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swaps_profile_settings_and_restores_the_backup() {
+        let game_path = std::env::temp_dir().join(format!(
+            "celemod-profile-settings-{}-{}",
+            std::process::id(),
+            now_millis()
+        ));
+        let saves = game_path.join("Saves");
+        fs::create_dir_all(&saves).unwrap();
+        let launch = game_path.join(EVEREST_LAUNCH);
+        fs::write(&launch, "--speedrun\n").unwrap();
+        fs::write(saves.join("modsettings-Speedrun.celeste"), "Timer: true\n").unwrap();
+        fs::write(saves.join("settings.celeste"), "<Settings />").unwrap();
+
+        let settings = capture(&game_path, &saves, "Speedrun", true, None).unwrap();
+        assert_eq!(settings.mod_settings, ["modsettings-Speedrun.celeste"]);
+        assert!(active_legacy_loader(&game_path));
+
+        // Practice has no launch flags and turns the timer off.
+        fs::remove_file(&launch).unwrap();
+        fs::write(saves.join("modsettings-Speedrun.celeste"), "Timer: false\n").unwrap();
+        let practice = capture(&game_path, &saves, "Practice", false, None).unwrap();
+        assert!(!practice.everest_launch);
+
+        apply(&game_path, &saves, Some("Speedrun"), &settings).unwrap();
+        assert_eq!(fs::read_to_string(&launch).unwrap(), "--speedrun\n");
+        assert_eq!(
+            fs::read_to_string(saves.join("modsettings-Speedrun.celeste")).unwrap(),
+            "Timer: true\n"
+        );
+        assert!(active_legacy_loader(&game_path));
+        let backup = get_settings_backup(&game_path).unwrap();
+        assert_eq!(backup.profile.as_deref(), Some("Practice"));
+
+        restore(&game_path).unwrap();
+        assert!(!launch.exists());
+        assert_eq!(
+            fs::read_to_string(saves.join("modsettings-Speedrun.celeste")).unwrap(),
+            "Timer: false\n"
+        );
+        assert!(!active_legacy_loader(&game_path));
+
+        // A profile without a snapshot gets the default flags and loader.
+        apply(&game_path, &saves, Some("Speedrun"), &settings).unwrap();
+        let defaults = ProfileSettings {
+            version: SETTINGS_VERSION,
+            captured_at: 0,
+            legacy_loader: false,
+            everest_launch: false,
+            mod_settings: Vec::new(),
+        };
+        apply(&game_path, &saves, None, &defaults).unwrap();
+        assert!(!launch.exists());
+        assert!(!active_legacy_loader(&game_path));
+        assert_eq!(
+            fs::read_to_string(saves.join("modsettings-Speedrun.celeste")).unwrap(),
+            "Timer: true\n"
+        );
+        fs::remove_dir_all(game_path).unwrap();
+    }
+}
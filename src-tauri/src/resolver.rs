@@ -0,0 +1,779 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use ::ureq::get;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use super::{EverestModDependency, LocalMod, everest};
+
+const DEPENDENCY_GRAPH_URL: &str =
+    "https://maddie480.ovh/celeste/mod_dependency_graph.yaml?format=everestyaml";
+
+/// Dependencies of the catalog build of a Mod, as published by the Everest
+/// dependency graph. Installed Mods use their own everest.yaml instead.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(super) struct DependencyGraphEntry {
+    dependencies: Option<Vec<EverestModDependency>>,
+    optional_dependencies: Option<Vec<EverestModDependency>>,
+}
+
+lazy_static! {
+    static ref DEPENDENCY_GRAPH: Mutex<Option<Arc<HashMap<String, DependencyGraphEntry>>>> =
+        Mutex::new(None);
+}
+
+/// A Mod version as Everest compares it: up to four numeric components, with
+/// anything after the numeric prefix (`-beta`, `+build`) ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ModVersion([u64; 4]);
+
+impl ModVersion {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value
+            .trim()
+            .trim_start_matches(|c: char| !c.is_ascii_digit());
+        let end = value
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(value.len());
+        let mut parts = [0; 4];
+        for (index, part) in value[..end]
+            .trim_end_matches('.')
+            .split('.')
+            .take(4)
+            .enumerate()
+        {
+            parts[index] = part.parse().ok()?;
+        }
+        Some(Self(parts))
+    }
+
+    pub fn major(self) -> u64 {
+        self.0[0]
+    }
+}
+
+/// Everest's `VersionSatisfiesDependency`: the major version must match and
+/// the installed version must not be older. `0.0.*` satisfies anything, which
+/// is how Everest treats development builds.
+pub fn version_satisfies(required: &str, installed: &str) -> bool {
+    let Some(required) = ModVersion::parse(required) else {
+        return true;
+    };
+    let Some(installed) = ModVersion::parse(installed) else {
+        return false;
+    };
+    if installed.0[0] == 0 && installed.0[1] == 0 {
+        return true;
+    }
+    installed.major() == required.major() && installed >= required
+}
+
+pub(super) fn is_loader_dependency(name: &str) -> bool {
+    matches!(
+        name.to_ascii_lowercase().as_str(),
+        "celeste" | "everest" | "everestcore"
+    )
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionConstraint {
+    pub required_by: String,
+    pub version: String,
+    pub optional: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PlannedAction {
+    Install,
+    Update,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedInstall {
+    pub name: String,
+    pub action: PlannedAction,
+    pub installed_version: Option<String>,
+    pub version: String,
+    pub download_url: String,
+    pub game_banana_file_id: i64,
+    pub requested: bool,
+    pub required_by: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProblemKind {
+    /// Required, but neither installed nor published in the catalog.
+    MissingFromCatalog,
+    /// The newest published version is still older than a dependent needs.
+    CatalogTooOld,
+    /// Dependents agree on a major version, but the only candidates are a
+    /// different major version, which Everest refuses to load against.
+    MajorVersionBreak,
+    /// Dependents require different major versions of the same Mod.
+    ConflictingRequirements,
+    /// Required dependencies form a loop. Everest loads these, but the plan
+    /// reports them because a broken member takes the whole loop down.
+    Cycle,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolutionProblem {
+    pub kind: ProblemKind,
+    pub name: String,
+    pub installed_version: Option<String>,
+    pub catalog_version: Option<String>,
+    pub constraints: Vec<VersionConstraint>,
+    pub cycle: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionalChoice {
+    pub name: String,
+    pub requested_by: Vec<String>,
+    pub version: String,
+    pub available: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPlan {
+    /// Ordered so that every Mod comes after the dependencies it needs.
+    pub steps: Vec<PlannedInstall>,
+    pub problems: Vec<ResolutionProblem>,
+    pub optional_dependencies: Vec<OptionalChoice>,
+}
+
+impl InstallPlan {
+    /// Cycles are informational; everything else blocks the install.
+    pub fn is_installable(&self) -> bool {
+        self.problems
+            .iter()
+            .all(|problem| problem.kind == ProblemKind::Cycle)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Installed,
+    Catalog,
+}
+
+struct Chosen {
+    name: String,
+    version: String,
+    source: Source,
+}
+
+struct Resolver<'a> {
+    installed: HashMap<String, &'a LocalMod>,
+    catalog: &'a HashMap<String, everest::ModInfoCached>,
+    graph: &'a HashMap<String, DependencyGraphEntry>,
+    chosen: HashMap<String, Chosen>,
+}
+
+impl Resolver<'_> {
+    fn catalog_entry(&self, name: &str) -> Option<&everest::ModInfoCached> {
+        self.catalog.get(name).or_else(|| {
+            self.catalog
+                .values()
+                .find(|item| item.name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    fn choose_catalog(&mut self, name: &str) -> bool {
+        let Some(item) = self.catalog_entry(name) else {
+            return false;
+        };
+        let chosen = Chosen {
+            name: item.name.clone(),
+            version: item.version.clone(),
+            source: Source::Catalog,
+        };
+        self.chosen.insert(name.to_ascii_lowercase(), chosen);
+        true
+    }
+
+    fn dependencies_of(&self, chosen: &Chosen) -> Vec<(String, String, bool)> {
+        match chosen.source {
+            Source::Installed => self
+                .installed
+                .get(&chosen.name.to_ascii_lowercase())
+                .map(|item| {
+                    item.deps
+                        .iter()
+                        .map(|dep| (dep.name.clone(), dep.version.clone(), dep.optional))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Source::Catalog => {
+                let Some(entry) = self.graph.get(&chosen.name) else {
+                    return Vec::new();
+                };
+                [
+                    (&entry.dependencies, false),
+                    (&entry.optional_dependencies, true),
+                ]
+                .into_iter()
+                .flat_map(|(dependencies, optional)| {
+                    dependencies.iter().flatten().filter_map(move |dep| {
+                        Some((
+                            dep.name.clone()?,
+                            dep.version.clone().unwrap_or_default(),
+                            optional,
+                        ))
+                    })
+                })
+                .collect()
+            }
+        }
+    }
+
+    /// Every dependency edge from the currently chosen Mods, keyed by the
+    /// lowercase dependency name.
+    fn constraints(&self) -> HashMap<String, (String, Vec<VersionConstraint>)> {
+        let mut result: HashMap<String, (String, Vec<VersionConstraint>)> = HashMap::new();
+        let mut chosen = self.chosen.values().collect::<Vec<_>>();
+        chosen.sort_unstable_by_key(|chosen| chosen.name.to_ascii_lowercase());
+        for chosen in chosen {
+            for (name, version, optional) in self.dependencies_of(chosen) {
+                if is_loader_dependency(&name) {
+                    continue;
+                }
+                result
+                    .entry(name.to_ascii_lowercase())
+                    .or_insert_with(|| (name.clone(), Vec::new()))
+                    .1
+                    .push(VersionConstraint {
+                        required_by: chosen.name.clone(),
+                        version,
+                        optional,
+                    });
+            }
+        }
+        result
+    }
+
+    fn satisfies_all(version: &str, constraints: &[VersionConstraint]) -> bool {
+        constraints
+            .iter()
+            .all(|constraint| version_satisfies(&constraint.version, version))
+    }
+
+    /// Upgrades Mods to their catalog version until every constraint that can
+    /// be satisfied is. A Mod only ever moves from "installed" to "catalog", so
+    /// this terminates after at most one pass per Mod.
+    fn settle(&mut self) {
+        loop {
+            let mut changed = false;
+            for (key, (name, constraints)) in self.constraints() {
+                let current = self.chosen.get(&key);
+                if current.is_none() && constraints.iter().all(|c| c.optional) {
+                    continue;
+                }
+                if current.is_some_and(|current| {
+                    Self::satisfies_all(&current.version, &constraints)
+                        || current.source == Source::Catalog
+                }) {
+                    continue;
+                }
+                if self
+                    .catalog_entry(&name)
+                    .is_some_and(|item| Self::satisfies_all(&item.version, &constraints))
+                {
+                    changed |= self.choose_catalog(&name);
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    fn problem(&self, name: &str, constraints: &[VersionConstraint]) -> Option<ResolutionProblem> {
+        let chosen = self.chosen.get(&name.to_ascii_lowercase());
+        if chosen.is_some_and(|chosen| Self::satisfies_all(&chosen.version, constraints)) {
+            return None;
+        }
+        let required = constraints
+            .iter()
+            .filter(|constraint| !constraint.optional)
+            .collect::<Vec<_>>();
+        if chosen.is_none() && required.is_empty() {
+            return None;
+        }
+        let catalog = self.catalog_entry(name);
+        let majors = constraints
+            .iter()
+            .filter_map(|constraint| ModVersion::parse(&constraint.version))
+            .map(ModVersion::major)
+            .collect::<HashSet<_>>();
+        let newest_required = constraints
+            .iter()
+            .filter_map(|constraint| ModVersion::parse(&constraint.version))
+            .max();
+        let kind = if majors.len() > 1 {
+            ProblemKind::ConflictingRequirements
+        } else if let Some(catalog) = catalog {
+            let catalog_version = ModVersion::parse(&catalog.version);
+            if catalog_version.map(ModVersion::major) != newest_required.map(ModVersion::major) {
+                ProblemKind::MajorVersionBreak
+            } else if catalog_version < newest_required {
+                ProblemKind::CatalogTooOld
+            } else {
+                ProblemKind::ConflictingRequirements
+            }
+        } else if self.installed.contains_key(&name.to_ascii_lowercase()) {
+            ProblemKind::CatalogTooOld
+        } else {
+            ProblemKind::MissingFromCatalog
+        };
+        Some(ResolutionProblem {
+            kind,
+            name: chosen
+                .map(|chosen| chosen.name.clone())
+                .unwrap_or_else(|| name.to_string()),
+            installed_version: self
+                .installed
+                .get(&name.to_ascii_lowercase())
+                .map(|item| item.version.clone()),
+            catalog_version: catalog.map(|item| item.version.clone()),
+            constraints: constraints.to_vec(),
+            cycle: Vec::new(),
+        })
+    }
+
+    fn required_edges(&self) -> HashMap<String, Vec<String>> {
+        self.chosen
+            .iter()
+            .map(|(key, chosen)| {
+                let mut edges = self
+                    .dependencies_of(chosen)
+                    .into_iter()
+                    .filter(|(name, _, optional)| !optional && !is_loader_dependency(name))
+                    .map(|(name, _, _)| name.to_ascii_lowercase())
+                    .filter(|name| self.chosen.contains_key(name))
+                    .collect::<Vec<_>>();
+                edges.sort_unstable();
+                edges.dedup();
+                (key.clone(), edges)
+            })
+            .collect()
+    }
+
+    fn cycles(&self, edges: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+        fn visit(
+            node: &str,
+            edges: &HashMap<String, Vec<String>>,
+            stack: &mut Vec<String>,
+            done: &mut HashSet<String>,
+            cycles: &mut Vec<Vec<String>>,
+        ) {
+            if let Some(start) = stack.iter().position(|item| item == node) {
+                cycles.push(stack[start..].to_vec());
+                return;
+            }
+            if done.contains(node) {
+                return;
+            }
+            stack.push(node.to_string());
+            for next in edges.get(node).into_iter().flatten() {
+                visit(next, edges, stack, done, cycles);
+            }
+            stack.pop();
+            done.insert(node.to_string());
+        }
+
+        let mut nodes = edges.keys().cloned().collect::<Vec<_>>();
+        nodes.sort_unstable();
+        let mut done = HashSet::new();
+        let mut cycles = Vec::new();
+        for node in nodes {
+            visit(&node, edges, &mut Vec::new(), &mut done, &mut cycles);
+        }
+        cycles
+            .into_iter()
+            .map(|cycle| {
+                cycle
+                    .iter()
+                    .map(|key| self.chosen[key].name.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Dependencies first, so a partially applied plan never leaves a Mod
+    /// installed without what it needs.
+    fn install_order(&self, edges: &HashMap<String, Vec<String>>) -> Vec<String> {
+        fn visit(
+            node: &str,
+            edges: &HashMap<String, Vec<String>>,
+            visited: &mut HashSet<String>,
+            order: &mut Vec<String>,
+        ) {
+            if !visited.insert(node.to_string()) {
+                return;
+            }
+            for next in edges.get(node).into_iter().flatten() {
+                visit(next, edges, visited, order);
+            }
+            order.push(node.to_string());
+        }
+
+        let mut nodes = edges.keys().cloned().collect::<Vec<_>>();
+        nodes.sort_unstable();
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        for node in nodes {
+            visit(&node, edges, &mut visited, &mut order);
+        }
+        order
+    }
+}
+
+/// Resolves the full set of downloads needed to install `requested` (and the
+/// optional dependencies the user opted into) without touching the disk.
+pub fn plan_mod_install(
+    requested: &[String],
+    optional_selected: &[String],
+    installed: &[LocalMod],
+    catalog: &HashMap<String, everest::ModInfoCached>,
+    graph: &HashMap<String, DependencyGraphEntry>,
+) -> InstallPlan {
+    let mut resolver = Resolver {
+        installed: HashMap::new(),
+        catalog,
+        graph,
+        chosen: HashMap::new(),
+    };
+    for item in installed {
+        let key = item.name.to_ascii_lowercase();
+        resolver.installed.entry(key.clone()).or_insert(item);
+        resolver.chosen.entry(key).or_insert_with(|| Chosen {
+            name: item.name.clone(),
+            version: item.version.clone(),
+            source: Source::Installed,
+        });
+    }
+
+    let mut plan = InstallPlan::default();
+    for name in requested.iter().chain(optional_selected) {
+        let key = name.to_ascii_lowercase();
+        let newer = resolver.catalog_entry(name).is_some_and(|item| {
+            resolver.chosen.get(&key).is_none_or(|current| {
+                ModVersion::parse(&item.version) > ModVersion::parse(&current.version)
+            })
+        });
+        if newer {
+            resolver.choose_catalog(name);
+        } else if !resolver.chosen.contains_key(&key) {
+            plan.problems.push(ResolutionProblem {
+                kind: ProblemKind::MissingFromCatalog,
+                name: name.clone(),
+                installed_version: None,
+                catalog_version: None,
+                constraints: Vec::new(),
+                cycle: Vec::new(),
+            });
+        }
+    }
+    resolver.settle();
+
+    let constraints = resolver.constraints();
+    let mut names = constraints.keys().cloned().collect::<Vec<_>>();
+    names.sort_unstable();
+    for key in &names {
+        let (name, constraints) = &constraints[key];
+        if let Some(problem) = resolver.problem(name, constraints) {
+            plan.problems.push(problem);
+        } else if !resolver.chosen.contains_key(key) {
+            let version = constraints
+                .iter()
+                .max_by_key(|constraint| ModVersion::parse(&constraint.version))
+                .map(|constraint| constraint.version.clone())
+                .unwrap_or_default();
+            plan.optional_dependencies.push(OptionalChoice {
+                name: name.clone(),
+                requested_by: constraints
+                    .iter()
+                    .map(|constraint| constraint.required_by.clone())
+                    .collect(),
+                available: resolver
+                    .catalog_entry(name)
+                    .is_some_and(|item| Resolver::satisfies_all(&item.version, constraints)),
+                version,
+            });
+        }
+    }
+
+    let edges = resolver.required_edges();
+    for cycle in resolver.cycles(&edges) {
+        plan.problems.push(ResolutionProblem {
+            kind: ProblemKind::Cycle,
+            name: cycle[0].clone(),
+            installed_version: None,
+            catalog_version: None,
+            constraints: Vec::new(),
+            cycle,
+        });
+    }
+
+    let requested_keys = requested
+        .iter()
+        .chain(optional_selected)
+        .map(|name| name.to_ascii_lowercase())
+        .collect::<HashSet<_>>();
+    for key in resolver.install_order(&edges) {
+        let chosen = &resolver.chosen[&key];
+        if chosen.source != Source::Catalog {
+            continue;
+        }
+        let Some(item) = resolver.catalog_entry(&chosen.name) else {
+            continue;
+        };
+        let required_by = constraints
+            .get(&key)
+            .map(|(_, constraints)| {
+                constraints
+                    .iter()
+                    .filter(|constraint| !constraint.optional)
+                    .map(|constraint| constraint.required_by.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        plan.steps.push(PlannedInstall {
+            name: chosen.name.clone(),
+            action: if resolver.installed.contains_key(&key) {
+                PlannedAction::Update
+            } else {
+                PlannedAction::Install
+            },
+            installed_version: resolver
+                .installed
+                .get(&key)
+                .map(|item| item.version.clone()),
+            version: chosen.version.clone(),
+            download_url: item.download_url.clone(),
+            game_banana_file_id: item.game_banana_file_id,
+            requested: requested_keys.contains(&key),
+            required_by,
+        });
+    }
+    plan
+}
+
+fn dependency_graph_cache_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|directory| directory.join("CeleMod").join("mod_dependency_graph.yaml"))
+}
+
+fn fetch_dependency_graph() -> anyhow::Result<String> {
+    Ok(get(DEPENDENCY_GRAPH_URL)
+        .set(
+            "User-Agent",
+            &format!("CeleMod/{}-{}", env!("VERSION"), &env!("GIT_HASH")[..6]),
+        )
+        .timeout(std::time::Duration::from_secs(20))
+        .set("Accept-Encoding", "gzip, deflate, br")
+        .call()?
+        .into_string()?)
+}
+
+/// Loads the Everest dependency graph once per session, falling back to the
+/// last downloaded copy when the network is unavailable.
+pub(super) fn get_dependency_graph() -> anyhow::Result<Arc<HashMap<String, DependencyGraphEntry>>> {
+    if let Some(graph) = DEPENDENCY_GRAPH.lock().unwrap().as_ref() {
+        return Ok(Arc::clone(graph));
+    }
+    let raw = match fetch_dependency_graph() {
+        Ok(raw) => {
+            if let Some(path) = dependency_graph_cache_path() {
+                if let Some(parent) = path.parent() {
+                    let _ = std::fs::create_dir_all(parent);
+                }
+                let _ = std::fs::write(path, &raw);
+            }
+            raw
+        }
+        Err(error) => {
+            crate::logging::error(format_args!(
+                "Failed to fetch Mod dependency graph: {error:#}"
+            ));
+            dependency_graph_cache_path()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .ok_or(error)?
+        }
+    };
+    let graph = Arc::new(serde_yaml::from_str::<HashMap<String, DependencyGraphEntry>>(&raw)?);
+    *DEPENDENCY_GRAPH.lock().unwrap() = Some(Arc::clone(&graph));
+    Ok(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::ModDependency;
+
+    fn installed(name: &str, version: &str, deps: &[(&str, &str, bool)]) -> LocalMod {
+        LocalMod {
+            game_banana_id: -1,
+            name: name.to_string(),
+            deps: deps
+                .iter()
+                .map(|(name, version, optional)| ModDependency {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    optional: *optional,
+                })
+                .collect(),
+            version: version.to_string(),
+            file: format!("{name}.zip"),
+            size: 0,
+            modified_at: 0,
+        }
+    }
+
+    fn catalog(entries: &[(&str, &str)]) -> HashMap<String, everest::ModInfoCached> {
+        entries
+            .iter()
+            .map(|(name, version)| {
+                (
+                    name.to_string(),
+                    everest::ModInfoCached {
+                        name: name.to_string(),
+                        version: version.to_string(),
+                        game_banana_id: 1,
+                        game_banana_file_id: 2,
+                        download_url: format!("https://example.invalid/{name}.zip"),
+                        xx_hash: Vec::new(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn graph(yaml: &str) -> HashMap<String, DependencyGraphEntry> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn kinds(plan: &InstallPlan) -> Vec<(&str, ProblemKind)> {
+        plan.problems
+            .iter()
+            .map(|problem| (problem.name.as_str(), problem.kind))
+            .collect()
+    }
+
+    #[test]
+    fn follows_everest_version_rules() {
+        assert!(version_satisfies("1.2.0", "1.2.0"));
+        assert!(version_satisfies("1.2.0", "1.10.0"));
+        assert!(version_satisfies("1.2", "1.2.0-beta"));
+        assert!(!version_satisfies("1.2.1", "1.2.0"));
+        assert!(!version_satisfies("1.2.0", "2.0.0"));
+        assert!(version_satisfies("3.0.0", "0.0.1"));
+        assert!(version_satisfies("", "1.0.0"));
+        assert!(ModVersion::parse("v1.10.0") > ModVersion::parse("1.9.9"));
+    }
+
+    #[test]
+    fn plans_transitive_installs_and_updates_dependencies_first() {
+        let installed = [installed("Helper", "1.0.0", &[])];
+        let catalog = catalog(&[("Map", "1.0.0"), ("Helper", "1.3.0"), ("Lib", "2.1.0")]);
+        let graph = graph(
+            "Map:\n  Dependencies:\n  - Name: Helper\n    Version: 1.2.0\n  - Name: Everest\n    Version: 1.4000.0\nHelper:\n  Dependencies:\n  - Name: Lib\n    Version: 2.0.0\nLib: {}\n",
+        );
+
+        let plan = plan_mod_install(&["Map".to_string()], &[], &installed, &catalog, &graph);
+
+        assert!(plan.is_installable(), "{:?}", plan.problems);
+        let steps = plan
+            .steps
+            .iter()
+            .map(|step| (step.name.as_str(), step.action, step.requested))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            [
+                ("Lib", PlannedAction::Install, false),
+                ("Helper", PlannedAction::Update, false),
+                ("Map", PlannedAction::Install, true),
+            ]
+        );
+        assert_eq!(plan.steps[1].required_by, ["Map"]);
+    }
+
+    #[test]
+    fn reports_conflicts_major_breaks_and_outdated_catalog() {
+        let installed = [
+            installed("OldMap", "1.0.0", &[("Helper", "1.0.0", false)]),
+            installed("Helper", "1.0.0", &[]),
+        ];
+        let catalog = catalog(&[
+            ("NewMap", "1.0.0"),
+            ("Helper", "2.0.0"),
+            ("Stale", "1.0.0"),
+            ("Bleeding", "1.0.0"),
+        ]);
+        let graph = graph(
+            "NewMap:\n  Dependencies:\n  - Name: Helper\n    Version: 2.0.0\n  - Name: Stale\n    Version: 1.5.0\n  - Name: Ghost\n    Version: 1.0.0\nBleeding:\n  Dependencies:\n  - Name: Helper\n    Version: 1.5.0\n",
+        );
+
+        let conflict = plan_mod_install(&["NewMap".to_string()], &[], &installed, &catalog, &graph);
+        assert!(!conflict.is_installable());
+        assert_eq!(
+            kinds(&conflict),
+            [
+                ("Ghost", ProblemKind::MissingFromCatalog),
+                ("Helper", ProblemKind::ConflictingRequirements),
+                ("Stale", ProblemKind::CatalogTooOld),
+            ]
+        );
+
+        let major = plan_mod_install(&["Bleeding".to_string()], &[], &installed, &catalog, &graph);
+        assert_eq!(kinds(&major), [("Helper", ProblemKind::MajorVersionBreak)]);
+    }
+
+    #[test]
+    fn reports_cycles_and_optional_choices() {
+        let installed = [installed("Present", "1.0.0", &[])];
+        let catalog = catalog(&[
+            ("A", "1.0.0"),
+            ("B", "1.0.0"),
+            ("Extra", "1.0.0"),
+            ("Present", "1.1.0"),
+        ]);
+        let graph = graph(
+            "A:\n  Dependencies:\n  - Name: B\n    Version: 1.0.0\n  OptionalDependencies:\n  - Name: Extra\n    Version: 1.0.0\n  - Name: Present\n    Version: 1.1.0\nB:\n  Dependencies:\n  - Name: A\n    Version: 1.0.0\n",
+        );
+
+        let plan = plan_mod_install(&["A".to_string()], &[], &installed, &catalog, &graph);
+
+        assert!(plan.is_installable());
+        assert_eq!(plan.problems[0].cycle, ["A", "B"]);
+        assert_eq!(plan.optional_dependencies.len(), 1);
+        assert_eq!(plan.optional_dependencies[0].name, "Extra");
+        assert!(plan.optional_dependencies[0].available);
+        // An installed optional dependency that is too old still has to be
+        // updated: Everest refuses to load against it.
+        assert!(plan.steps.iter().any(|step| step.name == "Present"));
+
+        let with_extra = plan_mod_install(
+            &["A".to_string()],
+            &["Extra".to_string()],
+            &installed,
+            &catalog,
+            &graph,
+        );
+        assert!(with_extra.optional_dependencies.is_empty());
+        assert!(with_extra.steps.iter().any(|step| step.name == "Extra"));
+    }
+}
@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+
+use super::crash_analysis::normalize_token;
+
+/// Namespace segments that say nothing about which Mod a frame belongs to.
+const NEUTRAL_SEGMENTS: [&str; 5] = ["celeste", "mod", "monocle", "everest", "module"];
+/// Assemblies that many Mods ship or reference; a frame in them is no evidence.
+const FRAMEWORK_ASSEMBLIES: [&str; 10] = [
+    "fna",
+    "mmhook",
+    "monomod",
+    "monocecil",
+    "system",
+    "microsoft",
+    "mscorlib",
+    "netstandard",
+    "yamldotnet",
+    "steamworks",
+];
+/// Words in the localized "--- End of inner exception stack trace ---" line.
+const INNER_TRACE_END: [&str; 5] = ["inner", "内部", "intern", "внутрен", "interne"];
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StackFrame {
+    /// Namespace of the declaring type, e.g. `Celeste.Mod.FrostHelper`.
+    pub module: String,
+    pub type_name: String,
+    pub method: String,
+    /// Source file or IL offset, as printed by the runtime.
+    pub location: Option<String>,
+    /// A MonoMod-generated `DMD<Type::Method>` body, i.e. a hooked method.
+    pub patched: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedException {
+    pub exception_type: String,
+    pub message: String,
+    pub frames: Vec<StackFrame>,
+}
+
+/// One thrown exception and its inner exceptions, outermost first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExceptionChain {
+    pub exceptions: Vec<ParsedException>,
+}
+
+fn strip_log_prefix(line: &str) -> &str {
+    // `(01/01/2026 10:00:00) [Everest] [Error] [tag] message`
+    let mut rest = line.trim();
+    if rest.starts_with('(')
+        && let Some((_, after)) = rest.split_once(") ")
+    {
+        rest = after;
+    }
+    while rest.starts_with('[')
+        && let Some((_, after)) = rest.split_once("] ")
+    {
+        rest = after;
+    }
+    rest
+}
+
+fn parse_header(line: &str) -> Option<ParsedException> {
+    let (exception_type, message) = match line.split_once(':') {
+        Some((name, message)) => (name.trim(), message.trim()),
+        None => (line.trim(), ""),
+    };
+    let last = exception_type.rsplit('.').next()?;
+    let is_type_name = exception_type.contains('.')
+        && !exception_type.contains(char::is_whitespace)
+        && exception_type
+            .chars()
+            .all(|character| character.is_alphanumeric() || matches!(character, '.' | '_' | '`'))
+        && (last.ends_with("Exception") || last.ends_with("Error"));
+    is_type_name.then(|| ParsedException {
+        exception_type: exception_type.to_string(),
+        message: message.to_string(),
+        frames: Vec::new(),
+    })
+}
+
+fn parse_frame(line: &str) -> Option<StackFrame> {
+    let body = line
+        .trim()
+        .strip_prefix("at ")
+        .or_else(|| line.trim().strip_prefix("在 "))?
+        .trim();
+    // Mono prints `(wrapper dynamic-method) Type.DMD<...>(...)`.
+    let body = match body.strip_prefix("(wrapper ") {
+        Some(rest) => rest.split_once(") ").map_or(rest, |(_, rest)| rest),
+        None => body,
+    };
+    let signature_end = body.find('(').unwrap_or(body.len());
+    let name = body[..signature_end].trim();
+    let after_signature = body[signature_end..]
+        .find(')')
+        .map_or("", |index| &body[signature_end + index + 1..]);
+    let location = [" in ", " 位置 "].iter().find_map(|separator| {
+        after_signature
+            .split_once(separator)
+            .map(|(_, location)| location.trim().to_string())
+    });
+
+    let (type_name, method, patched) = if let Some(start) = name.find("DMD<") {
+        let inner = name[start + 4..].trim_end_matches('>');
+        let (type_name, method) = inner.split_once("::")?;
+        (type_name.to_string(), method.to_string(), true)
+    } else {
+        // Constructors keep their leading dot: `Type..ctor`.
+        let split = name
+            .rfind("..")
+            .or_else(|| name.rfind('.'))
+            .filter(|index| *index > 0)?;
+        let method = name[split + 1..].trim_start_matches('.');
+        let method = if name[split..].starts_with("..") {
+            format!(".{method}")
+        } else {
+            method.to_string()
+        };
+        (name[..split].to_string(), method, false)
+    };
+    // Nested and compiler-generated types: `Outer+<>c__DisplayClass`.
+    let outer_type = type_name.split('+').next().unwrap_or(&type_name);
+    let module = outer_type
+        .rsplit_once('.')
+        .map(|(namespace, _)| namespace.to_string())
+        .unwrap_or_default();
+    Some(StackFrame {
+        module,
+        type_name,
+        method,
+        location,
+        patched,
+    })
+}
+
+/// Parses every exception in `text`. Both runtime formats are accepted:
+/// `System.Exception: outer ---> System.Exception: inner` on one line or
+/// with the inner exception on its own ` ---> ` line, followed by the inner
+/// frames, `--- End of inner exception stack trace ---` and the outer frames.
+pub(super) fn parse(text: &str) -> Vec<ExceptionChain> {
+    fn finish(current: &mut Vec<ParsedException>, chains: &mut Vec<ExceptionChain>) {
+        if !current.is_empty() {
+            chains.push(ExceptionChain {
+                exceptions: std::mem::take(current),
+            });
+        }
+    }
+
+    let mut chains = Vec::new();
+    let mut current: Vec<ParsedException> = Vec::new();
+    // How many inner traces have ended; frames belong to the exception that
+    // many levels above the innermost one.
+    let mut ended = 0;
+
+    for raw in text.lines() {
+        let line = strip_log_prefix(raw);
+        if let Some(frame) = parse_frame(line) {
+            if let Some(index) = current.len().checked_sub(1 + ended) {
+                current[index].frames.push(frame);
+            } else if let Some(outermost) = current.first_mut() {
+                outermost.frames.push(frame);
+            }
+            continue;
+        }
+        if line.starts_with("---") && line.ends_with("---") {
+            let lower = line.to_lowercase();
+            if INNER_TRACE_END.iter().any(|word| lower.contains(word)) {
+                ended += 1;
+            }
+            continue;
+        }
+        let (line, is_inner) = match line.strip_prefix("--->") {
+            Some(rest) => (rest.trim(), true),
+            None => (line, false),
+        };
+        let mut parts = line.split(" ---> ");
+        let Some(first) = parts.next().and_then(parse_header) else {
+            continue;
+        };
+        if !is_inner {
+            finish(&mut current, &mut chains);
+            ended = 0;
+        }
+        current.push(first);
+        current.extend(parts.filter_map(parse_header));
+    }
+    finish(&mut current, &mut chains);
+    chains
+}
+
+/// Normalized assembly names that cannot identify a single Mod.
+pub(super) fn is_framework_assembly(normalized: &str) -> bool {
+    normalized == "celeste"
+        || FRAMEWORK_ASSEMBLIES
+            .iter()
+            .any(|prefix| normalized.starts_with(prefix))
+}
+
+/// Whether `frame` is declared in the assembly named `assembly`, judged by
+/// its namespace. Only whole segments count, so `Frost` does not match
+/// `FrostHelper`.
+pub(super) fn frame_in_assembly(frame: &StackFrame, assembly: &str) -> bool {
+    let assembly = normalize_token(assembly);
+    if assembly.len() < 4 || is_framework_assembly(&assembly) {
+        return false;
+    }
+    // The patched method is vanilla or another Mod's; the hook is its own frame.
+    if frame.patched {
+        return false;
+    }
+    let type_name = frame
+        .type_name
+        .split('+')
+        .next()
+        .unwrap_or(&frame.type_name);
+    type_name
+        .split('.')
+        .map(normalize_token)
+        .filter(|segment| !NEUTRAL_SEGMENTS.contains(&segment.as_str()))
+        .any(|segment| segment == assembly)
+}
+
+/// Confidence for a Mod frame: the innermost exception's first frame is the
+/// strongest evidence, later frames and outer exceptions weaker.
+pub(super) fn frame_confidence(position: usize, outer_levels: usize) -> u8 {
+    let penalty = position.saturating_mul(2) + outer_levels.saturating_mul(6);
+    98_u8.saturating_sub(penalty.min(28) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_chains_inner_exceptions_and_localized_frames() {
+        let text = "(01/01/2026 10:00:00) [Everest] [Error] [crit-error-handler] ENCOUNTERED A CRITICAL ERROR\n\
+            System.Exception: Failed loading startup mod FrostHelper ---> System.NullReferenceException: Object reference not set\n   \
+            at FrostHelper.Entities.Spinner..ctor(EntityData data) in D:\\src\\Spinner.cs:line 12\n   \
+            at DMD<Celeste.Level::LoadLevel>(Level this, IntroTypes playerIntro, Boolean isFromLoader)\n   \
+            --- End of inner exception stack trace ---\n   \
+            at Celeste.Mod.Everest.Loader.LoadMod(EverestModuleMetadata meta)\n\
+            System.InvalidOperationException: 集合已修改\n   \
+            在 Celeste.Mod.MaxHelpingHand.Module.MaxHelpingHandModule.onModRegister(EverestModule module) 位置 C:\\Hand.cs:行号 40\n   \
+            at (wrapper dynamic-method) Celeste.Player.DMD<Celeste.Player::Update>(Celeste.Player)";
+        let chains = parse(text);
+        assert_eq!(chains.len(), 2);
+
+        let first = &chains[0].exceptions;
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].exception_type, "System.Exception");
+        assert_eq!(first[1].exception_type, "System.NullReferenceException");
+        assert_eq!(first[0].frames.len(), 1);
+        assert_eq!(first[0].frames[0].method, "LoadMod");
+        let spinner = &first[1].frames[0];
+        assert_eq!(
+            (
+                spinner.module.as_str(),
+                spinner.type_name.as_str(),
+                spinner.method.as_str()
+            ),
+            (
+                "FrostHelper.Entities",
+                "FrostHelper.Entities.Spinner",
+                ".ctor"
+            )
+        );
+        assert_eq!(
+            spinner.location.as_deref(),
+            Some("D:\\src\\Spinner.cs:line 12")
+        );
+        assert!(first[1].frames[1].patched);
+
+        let second = &chains[1].exceptions[0];
+        assert_eq!(second.message, "集合已修改");
+        assert_eq!(second.frames[0].method, "onModRegister");
+        assert_eq!(
+            second.frames[0].location.as_deref(),
+            Some("C:\\Hand.cs:行号 40")
+        );
+        assert_eq!(second.frames[1].type_name, "Celeste.Player");
+
+        assert!(frame_in_assembly(spinner, "FrostHelper"));
+        assert!(!frame_in_assembly(spinner, "Frost"));
+        assert!(frame_in_assembly(&second.frames[0], "MaxHelpingHand"));
+        assert!(!frame_in_assembly(&second.frames[1], "Celeste"));
+        assert!(frame_confidence(0, 0) > frame_confidence(3, 0));
+        assert!(frame_confidence(0, 0) > frame_confidence(0, 1));
+    }
+}
@@ -22,11 +22,59 @@ interface CrashSuspect {
   confidence: number;
   evidence: string;
   dependents: { name: string; optional: boolean }[];
+  changedSinceLastGood: boolean;
+}
+
+interface DependencyIssue {
+  dependent: string;
+  dependency: string;
+  requiredVersion: string;
+  optional: boolean;
+  problem: "missing" | "disabled" | "tooOld" | "wrongMajor";
+  installedVersion?: string;
+  fix?: {
+    enableFile?: string;
+    download?: {
+      version: string;
+      gameBananaFileId: number;
+      downloadUrl: string;
+    };
+  };
+}
+
+interface StackFrame {
+  module: string;
+  typeName: string;
+  method: string;
+  location?: string;
+  patched: boolean;
+}
+
+interface ExceptionChain {
+  exceptions: { exceptionType: string; message: string; frames: StackFrame[] }[];
+}
+
+interface LaunchMod {
+  name: string;
+  version: string;
+  file: string;
+  xxHash: string;
+}
+
+interface LaunchDiff {
+  lastGoodAt: number;
+  added: LaunchMod[];
+  removed: LaunchMod[];
+  updated: { name: string; from: string; to: string; rebuilt: boolean }[];
+  reEnabled: LaunchMod[];
+  everestVersion?: [number | null, number | null];
+  everestLaunchChanged: boolean;
 }
 
 interface CrashAnalysis {
   fingerprint: string;
   eventId: string;
+  signature: string;
   crashIndex: number;
   logModifiedAt: number;
   sourceLog: string;
@@ -37,9 +85,13 @@ interface CrashAnalysis {
   reasons: string[];
   suggestions: string[];
   suspects: CrashSuspect[];
+  dependencyIssues?: DependencyIssue[];
+  stack: ExceptionChain[];
+  changesSinceLastGood?: LaunchDiff;
   everestVersion?: number;
   isEverestUltra: boolean;
   excerpt: string;
+  locale: string;
 }
 
 interface OfficialEverestVersion {
@@ -328,6 +380,14 @@ const CrashPopup = ({
     analysis.everestVersion &&
     latestEverest.version > analysis.everestVersion,
   );
+  const dependencyFixes = useMemo(() => {
+    const fixes = new Map<string, NonNullable<DependencyIssue["fix"]>>();
+    for (const issue of analysis.dependencyIssues || []) {
+      if (issue.fix && !fixes.has(issue.dependency))
+        fixes.set(issue.dependency, issue.fix);
+    }
+    return [...fixes.entries()];
+  }, [analysis]);
   const exception = useMemo(() => displayException(analysis), [analysis]);
   const stacktrace = useMemo(
     () => formatStacktrace(analysis, exception),
@@ -416,6 +476,44 @@ const CrashPopup = ({
       await callRemote("restart_game_with_loader", gamePath, false);
     });
 
+  const fixDependenciesAndRestart = () =>
+    runAction(async () => {
+      await callRemote("stop_game_for_restart", gamePath);
+      const toEnable = dependencyFixes
+        .filter(([, fix]) => fix.enableFile)
+        .map(([name, fix]) => ({ name, file: fix.enableFile! }));
+      if (toEnable.length > 0) {
+        setStatus(_i18n.t("正在启用依赖…"));
+        await globalContext.blacklist.setModsEnabled(toEnable, true);
+      }
+      const downloads = dependencyFixes.filter(([, fix]) => fix.download);
+      for (let index = 0; index < downloads.length; index += 1) {
+        const [name, fix] = downloads[index];
+        const download = fix.download!;
+        const source =
+          download.gameBananaFileId !== -1
+            ? String(download.gameBananaFileId)
+            : download.downloadUrl;
+        await new Promise<void>((resolve, reject) => {
+          downloadMod(name, source, {
+            force: true,
+            ownerId: `crash-${analysis.fingerprint}`,
+            onProgress: (_task, progress) =>
+              setStatus(
+                `${_i18n.t("正在下载依赖")} ${name} (${index + 1}/${
+                  downloads.length
+                }) · ${progress.toFixed(0)}%`,
+              ),
+            onFinished: () => resolve(),
+            onFailed: (_task, reason) =>
+              reject(new Error(`${name}: ${reason}`)),
+          });
+        });
+      }
+      setStatus(_i18n.t("依赖已修复，正在重启…"));
+      await callRemote("restart_game_with_loader", gamePath, false);
+    });
+
   const updateEverestAndRestart = () =>
     runAction(async () => {
       if (!latestEverest) throw new Error(_i18n.t("没有可用的 Everest 更新"));
@@ -634,6 +732,15 @@ const CrashPopup = ({
             {_i18n.t("安装 {name} 修复并重启", { name: crashModFix.mod_name })}
           </button>
         ) : null}
+        {dependencyFixes.length > 0 ? (
+          <button
+            className="primary"
+            disabled={busy}
+            onClick={fixDependenciesAndRestart}
+          >
+            {_i18n.t("修复 Mod 依赖并重启")}
+          </button>
+        ) : null}
         {suspects.length > 0 ? (
           <button
             disabled={busy || selectedSuspects.length === 0}
@@ -699,6 +806,7 @@ export const CrashAssistant = () => {
         const analysis = await callRemote<CrashAnalysis | null>(
           "check_everest_crash",
           gamePath,
+          _i18n.currentLang,
         );
         if (
           active &&
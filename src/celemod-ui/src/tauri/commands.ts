@@ -104,7 +104,7 @@ const parameterNames: Record<string, string[]> = {
   enable_window_controls: [],
   do_self_update: ["url", "onEvent"],
   start_game_directly: ["path", "origin"],
-  check_everest_crash: ["gamePath"],
+  check_everest_crash: ["gamePath", "language"],
   stop_game_for_restart: ["gamePath"],
   restart_game_with_loader: ["gamePath", "legacyLoader"],
   reveal_crash_report: ["path"],